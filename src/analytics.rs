@@ -0,0 +1,373 @@
+//! Aggregations over Gong call metadata.
+//!
+//! The `call_analytics` tool pages through `list_calls_extensive` and feeds
+//! each call into [`CallAnalytics`], which keeps running totals per time
+//! bucket and per primary user.
+
+use chrono::{DateTime, Datelike, Duration};
+use gong_rs::models;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+/// Default number of `list_calls_extensive` pages aggregated per request
+pub(crate) const DEFAULT_MAX_PAGES: usize = 10;
+
+/// Participant counts for a single call, split by affiliation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ParticipantSummary {
+    pub total: usize,
+    pub internal: usize,
+    pub external: usize,
+    pub speakers: usize,
+}
+
+impl ParticipantSummary {
+    pub fn from_parties(parties: Option<&Vec<models::Party>>) -> Self {
+        let Some(parties) = parties else {
+            return Self::default();
+        };
+
+        Self {
+            total: parties.len(),
+            internal: parties
+                .iter()
+                .filter(
+                    |p| matches!(p.affiliation, Some(ref a) if format!("{:?}", a) == "Internal"),
+                )
+                .count(),
+            external: parties
+                .iter()
+                .filter(
+                    |p| matches!(p.affiliation, Some(ref a) if format!("{:?}", a) == "External"),
+                )
+                .count(),
+            speakers: parties.iter().filter(|p| p.speaker_id.is_some()).count(),
+        }
+    }
+
+    /// A call is customer-facing as soon as one external party attended
+    pub fn is_customer_facing(&self) -> bool {
+        self.external > 0
+    }
+
+    pub fn to_json(self) -> Value {
+        json!({
+            "total": self.total,
+            "internal": self.internal,
+            "external": self.external,
+        })
+    }
+}
+
+/// Participants who joined or left between two calls
+///
+/// Participants are identified by lower-cased email address, falling back to
+/// their name, so the same person is matched across calls.
+pub(crate) fn participant_changes(
+    previous: &[String],
+    current: &[String],
+) -> (Vec<String>, Vec<String>) {
+    let joined = current
+        .iter()
+        .filter(|p| !previous.contains(p))
+        .cloned()
+        .collect();
+    let left = previous
+        .iter()
+        .filter(|p| !current.contains(p))
+        .cloned()
+        .collect();
+    (joined, left)
+}
+
+/// Key used to match a participant across calls
+pub(crate) fn participant_key(email: Option<&String>, name: Option<&String>) -> Option<String> {
+    email
+        .map(|e| e.to_lowercase())
+        .or_else(|| name.cloned())
+        .filter(|key| !key.is_empty())
+}
+
+/// Time bucket used to group calls by their start time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Bucket {
+    Day,
+    Week,
+    Month,
+}
+
+impl Bucket {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+
+    /// Returns the bucket key for an ISO 8601 start time.
+    ///
+    /// Days and weeks are keyed by their first date (weeks start on Monday),
+    /// months by `YYYY-MM`. Dates are taken in the call's own UTC offset so a
+    /// late-evening call is not pushed into the next day.
+    pub fn key(&self, started: &str) -> Option<String> {
+        let started = DateTime::parse_from_rfc3339(started).ok()?;
+        let date = started.date_naive();
+
+        Some(match self {
+            Self::Day => date.format("%Y-%m-%d").to_string(),
+            Self::Week => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                monday.format("%Y-%m-%d").to_string()
+            }
+            Self::Month => format!("{:04}-{:02}", date.year(), date.month()),
+        })
+    }
+}
+
+/// The subset of call metadata the analytics need
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CallFacts {
+    pub started: Option<String>,
+    pub duration: i64,
+    pub direction: Option<String>,
+    pub primary_user_id: Option<String>,
+    pub participants: ParticipantSummary,
+}
+
+/// Running metrics for a group of calls
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct CallMetrics {
+    pub calls: usize,
+    pub total_duration: i64,
+    pub inbound: usize,
+    pub outbound: usize,
+    pub other_direction: usize,
+    pub internal_only: usize,
+    pub customer_facing: usize,
+}
+
+impl CallMetrics {
+    fn add(&mut self, facts: &CallFacts) {
+        self.calls += 1;
+        self.total_duration += facts.duration;
+
+        match facts.direction.as_deref() {
+            Some("Inbound") => self.inbound += 1,
+            Some("Outbound") => self.outbound += 1,
+            _ => self.other_direction += 1,
+        }
+
+        if facts.participants.is_customer_facing() {
+            self.customer_facing += 1;
+        } else {
+            self.internal_only += 1;
+        }
+    }
+
+    pub fn average_duration(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.total_duration as f64 / self.calls as f64
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "calls": self.calls,
+            "totalDuration": self.total_duration,
+            "averageDuration": self.average_duration(),
+            "direction": {
+                "inbound": self.inbound,
+                "outbound": self.outbound,
+                "other": self.other_direction,
+            },
+            "internalOnly": self.internal_only,
+            "customerFacing": self.customer_facing,
+        })
+    }
+}
+
+/// Aggregates calls overall, per time bucket and per primary user
+#[derive(Debug, Clone)]
+pub(crate) struct CallAnalytics {
+    bucket: Bucket,
+    totals: CallMetrics,
+    by_period: BTreeMap<String, CallMetrics>,
+    by_primary_user: BTreeMap<String, CallMetrics>,
+}
+
+impl CallAnalytics {
+    pub fn new(bucket: Bucket) -> Self {
+        Self {
+            bucket,
+            totals: CallMetrics::default(),
+            by_period: BTreeMap::new(),
+            by_primary_user: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, facts: &CallFacts) {
+        self.totals.add(facts);
+
+        let period = facts
+            .started
+            .as_deref()
+            .and_then(|s| self.bucket.key(s))
+            .unwrap_or_else(|| "unknown".to_string());
+        self.by_period.entry(period).or_default().add(facts);
+
+        let user = facts
+            .primary_user_id
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string());
+        self.by_primary_user.entry(user).or_default().add(facts);
+    }
+
+    pub fn to_json(&self) -> Value {
+        let by_period: Vec<Value> = self
+            .by_period
+            .iter()
+            .map(|(period, metrics)| {
+                let mut value = metrics.to_json();
+                value["period"] = json!(period);
+                value
+            })
+            .collect();
+
+        // Busiest users first
+        let mut users: Vec<_> = self.by_primary_user.iter().collect();
+        users.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then_with(|| a.0.cmp(b.0)));
+        let by_primary_user: Vec<Value> = users
+            .into_iter()
+            .map(|(user_id, metrics)| {
+                let mut value = metrics.to_json();
+                value["primaryUserId"] = json!(user_id);
+                value
+            })
+            .collect();
+
+        json!({
+            "bucket": self.bucket.as_str(),
+            "totals": self.totals.to_json(),
+            "byPeriod": by_period,
+            "byPrimaryUser": by_primary_user,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts(
+        started: &str,
+        duration: i64,
+        direction: &str,
+        user: &str,
+        external: usize,
+    ) -> CallFacts {
+        CallFacts {
+            started: Some(started.to_string()),
+            duration,
+            direction: Some(direction.to_string()),
+            primary_user_id: Some(user.to_string()),
+            participants: ParticipantSummary {
+                total: 2 + external,
+                internal: 2,
+                external,
+                speakers: 2,
+            },
+        }
+    }
+
+    #[test]
+    fn test_bucket_keys() {
+        // 2024-01-17 is a Wednesday
+        let started = "2024-01-17T10:30:00-08:00";
+        assert_eq!(Bucket::Day.key(started), Some("2024-01-17".to_string()));
+        assert_eq!(Bucket::Week.key(started), Some("2024-01-15".to_string()));
+        assert_eq!(Bucket::Month.key(started), Some("2024-01".to_string()));
+        assert_eq!(Bucket::Week.key("not a date"), None);
+    }
+
+    #[test]
+    fn test_bucket_uses_call_local_date() {
+        // 23:30 at -08:00 is already the next day in UTC
+        assert_eq!(
+            Bucket::Day.key("2024-01-17T23:30:00-08:00"),
+            Some("2024-01-17".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bucket_parse() {
+        assert_eq!(
+            serde_json::from_value::<Bucket>(json!("week")).ok(),
+            Some(Bucket::Week)
+        );
+        assert!(serde_json::from_value::<Bucket>(json!("quarter")).is_err());
+    }
+
+    #[test]
+    fn test_analytics_aggregation() {
+        let mut analytics = CallAnalytics::new(Bucket::Week);
+        analytics.add(&facts("2024-01-15T09:00:00Z", 600, "Outbound", "u1", 2));
+        analytics.add(&facts("2024-01-16T09:00:00Z", 1200, "Inbound", "u1", 0));
+        analytics.add(&facts("2024-01-23T09:00:00Z", 300, "Conference", "u2", 1));
+
+        let totals = &analytics.totals;
+        assert_eq!(totals.calls, 3);
+        assert_eq!(totals.total_duration, 2100);
+        assert_eq!(totals.average_duration(), 700.0);
+        assert_eq!(totals.inbound, 1);
+        assert_eq!(totals.outbound, 1);
+        assert_eq!(totals.other_direction, 1);
+        assert_eq!(totals.internal_only, 1);
+        assert_eq!(totals.customer_facing, 2);
+
+        let json = analytics.to_json();
+        let periods = json["byPeriod"].as_array().unwrap();
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0]["period"], "2024-01-15");
+        assert_eq!(periods[0]["calls"], 2);
+
+        let users = json["byPrimaryUser"].as_array().unwrap();
+        assert_eq!(users[0]["primaryUserId"], "u1");
+        assert_eq!(users[0]["calls"], 2);
+    }
+
+    #[test]
+    fn test_participant_changes() {
+        let first = vec!["rep@example.com".to_string(), "cto@acme.com".to_string()];
+        let second = vec!["rep@example.com".to_string(), "cfo@acme.com".to_string()];
+
+        let (joined, left) = participant_changes(&first, &second);
+        assert_eq!(joined, vec!["cfo@acme.com".to_string()]);
+        assert_eq!(left, vec!["cto@acme.com".to_string()]);
+    }
+
+    #[test]
+    fn test_participant_key_prefers_email() {
+        let email = "Jane@Acme.com".to_string();
+        let name = "Jane Doe".to_string();
+        assert_eq!(
+            participant_key(Some(&email), Some(&name)),
+            Some("jane@acme.com".to_string())
+        );
+        assert_eq!(
+            participant_key(None, Some(&name)),
+            Some("Jane Doe".to_string())
+        );
+        assert_eq!(participant_key(None, None), None);
+    }
+
+    #[test]
+    fn test_empty_metrics_average() {
+        assert_eq!(CallMetrics::default().average_duration(), 0.0);
+    }
+}
@@ -0,0 +1,155 @@
+//! Gong API requests that keep the response headers.
+//!
+//! gong-rs returns the status and body of a failed request but drops its
+//! headers, and with them the `Retry-After` Gong sends with a 429. The
+//! endpoints this server calls are therefore sent from here, with the gong-rs
+//! configuration, parameters, models and errors, so callers are unchanged
+//! while [`crate::retry`] waits as long as Gong asks.
+
+use crate::retry;
+use gong_rs::apis::configuration::Configuration;
+use gong_rs::apis::{Error, ResponseContent, calls_api, users_api};
+use gong_rs::models;
+use reqwest::{Method, RequestBuilder, StatusCode, header};
+use serde::de::DeserializeOwned;
+
+/// `POST /v2/calls/extensive`
+pub(crate) async fn list_calls_extensive(
+    config: &Configuration,
+    params: calls_api::ListCallsExtensiveParams,
+) -> Result<models::Calls, Error<calls_api::ListCallsExtensiveError>> {
+    let body = params
+        .public_api_base_request_with_data_v2_calls_request_filter_with_owners_content_selector;
+    execute(request(config, Method::POST, "/v2/calls/extensive").json(&body)).await
+}
+
+/// `POST /v2/calls/transcript`
+pub(crate) async fn get_call_transcripts(
+    config: &Configuration,
+    params: calls_api::GetCallTranscriptsParams,
+) -> Result<models::CallTranscripts, Error<calls_api::GetCallTranscriptsError>> {
+    let body = params.public_api_base_request_v2_calls_filter;
+    execute(request(config, Method::POST, "/v2/calls/transcript").json(&body)).await
+}
+
+/// `GET /v2/users`
+pub(crate) async fn list_users(
+    config: &Configuration,
+    params: users_api::ListUsersParams,
+) -> Result<models::UsersMetadata, Error<users_api::ListUsersError>> {
+    let mut request = request(config, Method::GET, "/v2/users");
+    if let Some(cursor) = &params.cursor {
+        request = request.query(&[("cursor", cursor)]);
+    }
+    if let Some(include_avatars) = params.include_avatars {
+        request = request.query(&[("includeAvatars", include_avatars)]);
+    }
+    execute(request).await
+}
+
+/// A request to `path` with the configured credentials and user agent
+fn request(config: &Configuration, method: Method, path: &str) -> RequestBuilder {
+    let mut request = config
+        .client
+        .request(method, format!("{}{}", config.base_path, path));
+    if let Some(user_agent) = &config.user_agent {
+        request = request.header(header::USER_AGENT, user_agent);
+    }
+    if let Some((username, password)) = &config.basic_auth {
+        request = request.basic_auth(username, password.as_ref());
+    }
+    request
+}
+
+/// Send a request and parse the response like gong-rs does
+///
+/// The `Retry-After` of a 429 is handed to the retry loop.
+async fn execute<T, E>(request: RequestBuilder) -> Result<T, Error<E>>
+where
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    let response = request.send().await?;
+    let status = response.status();
+    if status == StatusCode::TOO_MANY_REQUESTS
+        && let Some(delay) = response
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| retry::parse_retry_after(value, chrono::Utc::now()))
+    {
+        retry::record_retry_after(delay);
+    }
+
+    let content = response.text().await?;
+    if status.is_success() {
+        Ok(serde_json::from_str(&content)?)
+    } else {
+        Err(Error::ResponseError(ResponseContent {
+            status,
+            entity: serde_json::from_str(&content).ok(),
+            content,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::{RateLimitConfig, RateLimiter};
+    use crate::retry::{RetryConfig, RetryError};
+    use axum::http::HeaderMap;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// A Gong stand-in answering `/v2/users` with 429 and `retry_after`
+    async fn rate_limited_server(retry_after: &'static str) -> (Configuration, Arc<AtomicU32>) {
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = requests.clone();
+        let app = axum::Router::new().route(
+            "/v2/users",
+            axum::routing::get(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut headers = HeaderMap::new();
+                headers.insert(header::RETRY_AFTER, retry_after.parse().unwrap());
+                (StatusCode::TOO_MANY_REQUESTS, headers, "{}")
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let mut config = Configuration::new();
+        config.base_path = format!("http://{}", address);
+        (config, requests)
+    }
+
+    #[tokio::test]
+    async fn test_long_retry_after_is_not_waited_for() {
+        let (config, requests) = rate_limited_server("3600").await;
+        let limiter = RateLimiter::new(RateLimitConfig {
+            quota_file: None,
+            ..RateLimitConfig::default()
+        });
+        let params = users_api::ListUsersParams {
+            cursor: None,
+            include_avatars: Some(false),
+        };
+
+        // Without the header the request would be retried after a second
+        let result = retry::send(&RetryConfig::default(), &limiter, || {
+            list_users(&config, params.clone())
+        })
+        .await;
+        match result {
+            Err(RetryError::Failed { error, retries }) => {
+                assert_eq!(retries, 0);
+                assert!(
+                    matches!(error, Error::ResponseError(response) if response.status == StatusCode::TOO_MANY_REQUESTS)
+                );
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+}
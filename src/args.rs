@@ -0,0 +1,527 @@
+//! Typed tool arguments.
+//!
+//! Every tool deserializes its arguments into one of the structs below, and
+//! the tool's input schema is derived from the same struct, so the schema the
+//! model sees and the checks the server applies can't drift apart. Wrong
+//! types and unknown fields are rejected with the path of the offending field
+//! instead of being ignored, timestamps must be ISO 8601 and limits outside
+//! their range are errors rather than being clamped.
+
+use crate::analytics::Bucket;
+use crate::mirror;
+use crate::{
+    MAX_COMPARE_CALLS, MAX_INDEX_RESULTS, MAX_TRACKER_CALLS, MAX_TRANSCRIPT_CALLS, sql_query,
+};
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::common::schema_for_type;
+use rmcp::model::JsonObject;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Value, json};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Arguments of one tool
+pub(crate) trait ToolArgs: DeserializeOwned + JsonSchema + 'static {
+    /// Checks the types alone can't express, such as `from < to`
+    fn validate(&self) -> Result<(), McpError> {
+        Ok(())
+    }
+}
+
+/// Deserialize and validate the arguments of a tool call
+pub(crate) fn parse<T: ToolArgs>(arguments: Option<&JsonObject>) -> Result<T, McpError> {
+    let value = Value::Object(arguments.cloned().unwrap_or_default());
+    let args: T = serde_path_to_error::deserialize(value).map_err(|e| {
+        let message = e.inner().to_string();
+        invalid(&parameter(e.path(), &message), &message)
+    })?;
+    args.validate()?;
+    Ok(args)
+}
+
+/// Input schema of a tool
+pub(crate) fn schema<T: ToolArgs>() -> Arc<JsonObject> {
+    schema_for_type::<T>()
+}
+
+/// The argument named `parameter` is invalid
+pub(crate) fn invalid(parameter: &str, message: &str) -> McpError {
+    McpError::invalid_params(
+        "invalid_arguments",
+        Some(json!({
+            "parameter": parameter,
+            "message": format!("{}: {}", parameter, message),
+        })),
+    )
+}
+
+/// Name of the argument a deserialization error is about
+///
+/// Missing and unknown fields are reported on the enclosing object, so their
+/// name is taken from the message.
+fn parameter(path: &serde_path_to_error::Path, message: &str) -> String {
+    let path = path.to_string();
+    if path != "." {
+        return path;
+    }
+    ["missing field `", "unknown field `"]
+        .iter()
+        .find_map(|prefix| message.strip_prefix(prefix))
+        .and_then(|rest| rest.split('`').next())
+        .unwrap_or("arguments")
+        .to_string()
+}
+
+/// An ISO 8601 timestamp or date, normalized to UTC RFC 3339
+///
+/// Normalized timestamps compare correctly as strings.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub(crate) struct Timestamp(String);
+
+impl Timestamp {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<Timestamp> for String {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        mirror::normalize_timestamp(&value).map(Self).ok_or_else(|| {
+            de::Error::custom(format!(
+                "expected an ISO 8601 timestamp such as 2024-01-01T00:00:00Z or a date such as 2024-01-01, got {:?}",
+                value
+            ))
+        })
+    }
+}
+
+impl JsonSchema for Timestamp {
+    fn schema_name() -> Cow<'static, str> {
+        "Timestamp".into()
+    }
+
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({"type": "string", "format": "date-time"})
+    }
+}
+
+/// Who said something in a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+pub(crate) enum Affiliation {
+    #[serde(alias = "internal")]
+    Internal,
+    #[serde(alias = "external")]
+    External,
+}
+
+impl Affiliation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Internal => "Internal",
+            Self::External => "External",
+        }
+    }
+}
+
+fn check_range(from: Option<&Timestamp>, to: Option<&Timestamp>) -> Result<(), McpError> {
+    match (from, to) {
+        (Some(from), Some(to)) if from >= to => Err(invalid(
+            "to_date_time",
+            &format!(
+                "must be after from_date_time ({}), got {}",
+                from.as_str(),
+                to.as_str()
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_limit(parameter: &str, value: Option<usize>, max: usize) -> Result<(), McpError> {
+    match value {
+        Some(value) if value < 1 || value > max => Err(invalid(
+            parameter,
+            &format!("must be between 1 and {}, got {}", max, value),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_ids(parameter: &str, ids: Option<&Vec<String>>) -> Result<(), McpError> {
+    match ids {
+        Some(ids) if ids.iter().any(|id| id.trim().is_empty()) => {
+            Err(invalid(parameter, "must not contain empty IDs"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_not_blank(parameter: &str, value: &str) -> Result<(), McpError> {
+    if value.trim().is_empty() {
+        return Err(invalid(parameter, "must not be empty"));
+    }
+    Ok(())
+}
+
+/// Arguments of `search_calls`
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SearchCallsArgs {
+    /// Start of time range in ISO 8601 format (e.g., '2024-01-01T00:00:00Z' or '2024-01-01T02:30:00-07:00'). Returns calls that started on or after this time.
+    pub from_date_time: Option<Timestamp>,
+    /// End of time range in ISO 8601 format. Returns calls that started before this time (exclusive).
+    pub to_date_time: Option<Timestamp>,
+    /// Filter by workspace ID. Returns only calls belonging to this workspace.
+    pub workspace_id: Option<String>,
+    /// List of specific call IDs to retrieve. If provided, only these calls are returned (within date range if specified).
+    pub call_ids: Option<Vec<String>>,
+    /// Filter by user IDs. Returns calls where these users are the primary participant/host.
+    pub primary_user_ids: Option<Vec<String>>,
+    /// Pagination cursor from a previous response. Use this to get the next page of results.
+    pub cursor: Option<String>,
+    /// Maximum number of calls to return from the current page. Without this, returns all calls from the API page (typically 100). Response includes 'truncated: true' if limited. Use this to reduce response size.
+    #[schemars(range(min = 1))]
+    pub limit: Option<usize>,
+    /// Include call agenda/structure data (segments and their durations). Default: false. Basic call metadata (id, title, started, duration, direction, parties, url) is always included. Increases response size moderately.
+    #[serde(default)]
+    pub include_structure: bool,
+}
+
+impl ToolArgs for SearchCallsArgs {
+    fn validate(&self) -> Result<(), McpError> {
+        check_range(self.from_date_time.as_ref(), self.to_date_time.as_ref())?;
+        check_ids("call_ids", self.call_ids.as_ref())?;
+        check_limit("limit", self.limit, usize::MAX)
+    }
+}
+
+/// Arguments of `call_analytics`
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CallAnalyticsArgs {
+    /// Start of time range in ISO 8601 format (e.g., '2024-01-01T00:00:00Z'). Required.
+    pub from_date_time: Timestamp,
+    /// End of time range in ISO 8601 format (exclusive). Defaults to now.
+    pub to_date_time: Option<Timestamp>,
+    /// Only aggregate calls belonging to this workspace.
+    pub workspace_id: Option<String>,
+    /// Only aggregate calls hosted by these users.
+    pub primary_user_ids: Option<Vec<String>>,
+    /// Time bucket for the per-period breakdown. Weeks start on Monday. Default: week.
+    #[serde(default = "default_bucket")]
+    pub bucket: Bucket,
+    /// Maximum number of API pages (up to 100 calls each) to aggregate. Default: 10. Response includes 'complete: false' if more calls were available.
+    #[schemars(range(min = 1))]
+    pub max_pages: Option<usize>,
+}
+
+fn default_bucket() -> Bucket {
+    Bucket::Week
+}
+
+impl ToolArgs for CallAnalyticsArgs {
+    fn validate(&self) -> Result<(), McpError> {
+        check_range(Some(&self.from_date_time), self.to_date_time.as_ref())?;
+        check_limit("max_pages", self.max_pages, usize::MAX)
+    }
+}
+
+/// Arguments of `transcript_metrics`
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TranscriptMetricsArgs {
+    /// ID of the call to analyze. The call must have a processed transcript.
+    pub call_id: String,
+}
+
+impl ToolArgs for TranscriptMetricsArgs {
+    fn validate(&self) -> Result<(), McpError> {
+        check_not_blank("call_id", &self.call_id)
+    }
+}
+
+/// Arguments of `run_local_trackers`
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LocalTrackersArgs {
+    /// IDs of the calls whose transcripts should be scanned.
+    #[schemars(length(min = 1, max = MAX_TRACKER_CALLS))]
+    pub call_ids: Vec<String>,
+    /// Names of the local trackers to run. Default: all configured trackers.
+    pub trackers: Option<Vec<String>>,
+}
+
+impl ToolArgs for LocalTrackersArgs {
+    fn validate(&self) -> Result<(), McpError> {
+        check_ids("call_ids", Some(&self.call_ids))?;
+        if self.call_ids.is_empty() || self.call_ids.len() > MAX_TRACKER_CALLS {
+            return Err(invalid(
+                "call_ids",
+                &format!(
+                    "must contain between 1 and {} call IDs, got {}",
+                    MAX_TRACKER_CALLS,
+                    self.call_ids.len()
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Arguments of `compare_calls`
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CompareCallsArgs {
+    /// IDs of the calls to compare, in the order they should be laid out (e.g., first meeting first).
+    #[schemars(length(min = 2, max = MAX_COMPARE_CALLS))]
+    pub call_ids: Vec<String>,
+}
+
+impl CompareCallsArgs {
+    /// The call IDs without duplicates, in their original order
+    pub fn distinct_call_ids(&self) -> Vec<String> {
+        let mut call_ids: Vec<String> = Vec::new();
+        for id in &self.call_ids {
+            if !call_ids.contains(id) {
+                call_ids.push(id.clone());
+            }
+        }
+        call_ids
+    }
+}
+
+impl ToolArgs for CompareCallsArgs {
+    fn validate(&self) -> Result<(), McpError> {
+        check_ids("call_ids", Some(&self.call_ids))?;
+        let count = self.distinct_call_ids().len();
+        if !(2..=MAX_COMPARE_CALLS).contains(&count) {
+            return Err(invalid(
+                "call_ids",
+                &format!(
+                    "must contain between 2 and {} distinct call IDs, got {}",
+                    MAX_COMPARE_CALLS, count
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Arguments of `get_transcripts`
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct GetTranscriptsArgs {
+    /// IDs of the calls whose transcripts should be returned.
+    pub call_ids: Option<Vec<String>>,
+    /// Return transcripts of calls that started on or after this time (ISO 8601).
+    pub from_date_time: Option<Timestamp>,
+    /// Return transcripts of calls that started before this time (ISO 8601, exclusive).
+    pub to_date_time: Option<Timestamp>,
+    /// Only return transcripts of calls in this workspace.
+    pub workspace_id: Option<String>,
+    /// Only return transcripts of calls hosted by these users.
+    pub primary_user_ids: Option<Vec<String>>,
+    /// Maximum number of transcripts to return. Default: 10, maximum: 50.
+    #[schemars(range(min = 1, max = MAX_TRANSCRIPT_CALLS))]
+    pub max_calls: Option<usize>,
+    /// Approximate cap on the combined size of all returned transcripts, in tokens. Transcripts that would exceed it are listed in 'omittedCallIds'. Default: 25000.
+    #[schemars(range(min = 1))]
+    pub max_tokens: Option<usize>,
+}
+
+impl ToolArgs for GetTranscriptsArgs {
+    fn validate(&self) -> Result<(), McpError> {
+        check_range(self.from_date_time.as_ref(), self.to_date_time.as_ref())?;
+        check_ids("call_ids", self.call_ids.as_ref())?;
+        check_limit("max_calls", self.max_calls, MAX_TRANSCRIPT_CALLS)?;
+        check_limit("max_tokens", self.max_tokens, usize::MAX)?;
+
+        let selected = |ids: &Option<Vec<String>>| ids.as_ref().is_some_and(|ids| !ids.is_empty());
+        if !selected(&self.call_ids)
+            && self.from_date_time.is_none()
+            && !selected(&self.primary_user_ids)
+        {
+            return Err(McpError::invalid_params(
+                "missing_filter",
+                Some(json!({
+                    "message": "Provide call_ids, from_date_time or primary_user_ids to select transcripts"
+                })),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Arguments of `search_index`
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SearchIndexArgs {
+    /// Words to search for. Wrap words in double quotes to search for an exact phrase, e.g. 'onboarding "too long"'. Word variants (complained, complaints) also match.
+    pub query: String,
+    /// Only return passages containing every word and phrase. Default: false (rank passages matching any of them).
+    #[serde(default)]
+    pub match_all: bool,
+    /// Only search what internal (your team) or external (customers, prospects) speakers said.
+    pub speaker_affiliation: Option<Affiliation>,
+    /// Only search calls that started on or after this time (ISO 8601).
+    pub from_date_time: Option<Timestamp>,
+    /// Only search calls that started before this time (ISO 8601, exclusive).
+    pub to_date_time: Option<Timestamp>,
+    /// Only search these calls.
+    pub call_ids: Option<Vec<String>>,
+    /// Maximum number of passages to return. Default: 10, maximum: 50.
+    #[schemars(range(min = 1, max = MAX_INDEX_RESULTS))]
+    pub limit: Option<usize>,
+}
+
+impl ToolArgs for SearchIndexArgs {
+    fn validate(&self) -> Result<(), McpError> {
+        check_not_blank("query", &self.query)?;
+        check_range(self.from_date_time.as_ref(), self.to_date_time.as_ref())?;
+        check_ids("call_ids", self.call_ids.as_ref())?;
+        check_limit("limit", self.limit, MAX_INDEX_RESULTS)
+    }
+}
+
+/// Arguments of `query_calls_sql`
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct QueryCallsSqlArgs {
+    /// A single read-only SQLite SELECT (or WITH ... SELECT) statement. Read the gong://schema resource for tables, columns and examples.
+    pub sql: String,
+    /// Maximum number of rows to return. Default: 100, maximum: 1000.
+    #[schemars(range(min = 1, max = sql_query::MAX_ROWS))]
+    pub max_rows: Option<usize>,
+}
+
+impl ToolArgs for QueryCallsSqlArgs {
+    fn validate(&self) -> Result<(), McpError> {
+        check_not_blank("sql", &self.sql)?;
+        check_limit("max_rows", self.max_rows, sql_query::MAX_ROWS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(value: Value) -> JsonObject {
+        value.as_object().unwrap().clone()
+    }
+
+    fn error_data<T: ToolArgs + std::fmt::Debug>(value: Value) -> Value {
+        let error = parse::<T>(Some(&args(value))).unwrap_err();
+        assert_eq!(error.message, "invalid_arguments");
+        error.data.unwrap()
+    }
+
+    #[test]
+    fn test_rejects_wrong_types_with_field_path() {
+        let data = error_data::<SearchCallsArgs>(json!({"cursor": 5}));
+        assert_eq!(data["parameter"], "cursor");
+        assert!(
+            data["message"]
+                .as_str()
+                .unwrap()
+                .contains("expected a string")
+        );
+
+        let data = error_data::<SearchCallsArgs>(json!({"limit": "10"}));
+        assert_eq!(data["parameter"], "limit");
+
+        let data = error_data::<SearchCallsArgs>(json!({"call_ids": ["1", 2]}));
+        assert_eq!(data["parameter"], "call_ids[1]");
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_missing_fields() {
+        let data = error_data::<SearchCallsArgs>(json!({"from": "2024-01-01"}));
+        assert_eq!(data["parameter"], "from");
+        assert!(data["message"].as_str().unwrap().contains("from_date_time"));
+
+        let data = error_data::<SearchIndexArgs>(json!({}));
+        assert_eq!(data["parameter"], "query");
+    }
+
+    #[test]
+    fn test_validates_and_normalizes_timestamps() {
+        let parsed: SearchCallsArgs = parse(Some(&args(json!({
+            "from_date_time": "2024-01-01",
+            "to_date_time": "2024-01-01T02:30:00-07:00"
+        }))))
+        .unwrap();
+        assert_eq!(
+            parsed.from_date_time.unwrap().as_str(),
+            "2024-01-01T00:00:00Z"
+        );
+        assert_eq!(
+            parsed.to_date_time.unwrap().as_str(),
+            "2024-01-01T09:30:00Z"
+        );
+
+        let data = error_data::<SearchCallsArgs>(json!({"from_date_time": "last week"}));
+        assert_eq!(data["parameter"], "from_date_time");
+
+        let data = error_data::<SearchCallsArgs>(json!({
+            "from_date_time": "2024-02-01T00:00:00Z",
+            "to_date_time": "2024-01-01T00:00:00Z"
+        }));
+        assert_eq!(data["parameter"], "to_date_time");
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_limits() {
+        let data = error_data::<SearchIndexArgs>(json!({"query": "pricing", "limit": 500}));
+        assert_eq!(data["parameter"], "limit");
+
+        let data = error_data::<CompareCallsArgs>(json!({"call_ids": ["1", "1"]}));
+        assert_eq!(data["parameter"], "call_ids");
+
+        let parsed: CompareCallsArgs =
+            parse(Some(&args(json!({"call_ids": ["1", "2", "1"]})))).unwrap();
+        assert_eq!(parsed.distinct_call_ids(), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn test_defaults_and_enums() {
+        let parsed: CallAnalyticsArgs =
+            parse(Some(&args(json!({"from_date_time": "2024-01-01"})))).unwrap();
+        assert_eq!(parsed.bucket, Bucket::Week);
+
+        let parsed: SearchIndexArgs = parse(Some(&args(
+            json!({"query": "pricing", "speaker_affiliation": "external"}),
+        )))
+        .unwrap();
+        assert_eq!(parsed.speaker_affiliation, Some(Affiliation::External));
+
+        let error = parse::<GetTranscriptsArgs>(None).unwrap_err();
+        assert_eq!(error.message, "missing_filter");
+    }
+
+    #[test]
+    fn test_schema_describes_fields() {
+        let analytics = schema::<CallAnalyticsArgs>();
+        assert_eq!(analytics["additionalProperties"], false);
+        assert_eq!(analytics["required"], json!(["from_date_time"]));
+        let from = &analytics["properties"]["from_date_time"];
+        assert_eq!(from["format"], "date-time");
+        assert!(from["description"].as_str().unwrap().contains("ISO 8601"));
+
+        let search = schema::<SearchIndexArgs>();
+        assert_eq!(search["properties"]["limit"]["maximum"], MAX_INDEX_RESULTS);
+    }
+}
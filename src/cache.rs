@@ -0,0 +1,432 @@
+//! In-memory response cache for Gong API calls.
+//!
+//! Responses are stored as serialized JSON keyed by endpoint and request
+//! parameters, so one cache serves every response type. Each kind of
+//! response has its own TTL: transcripts never change once processed, while
+//! call lists pick up new calls all the time. The cache is bounded by entry
+//! count and total size; the least recently used entries are evicted first.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The kind of response being cached, which determines its TTL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum CacheKind {
+    Calls,
+    Users,
+    Transcripts,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 3] = [CacheKind::Calls, CacheKind::Users, CacheKind::Transcripts];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheKind::Calls => "calls",
+            CacheKind::Users => "users",
+            CacheKind::Transcripts => "transcripts",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            CacheKind::Calls => 0,
+            CacheKind::Users => 1,
+            CacheKind::Transcripts => 2,
+        }
+    }
+}
+
+/// Cache limits and TTLs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CacheConfig {
+    pub calls_ttl: Duration,
+    pub users_ttl: Duration,
+    pub transcripts_ttl: Duration,
+    pub max_entries: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            calls_ttl: Duration::from_secs(60),
+            users_ttl: Duration::from_secs(10 * 60),
+            transcripts_ttl: Duration::from_secs(24 * 60 * 60),
+            max_entries: 1_000,
+            max_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Read overrides from `GONG_CACHE_*` environment variables
+    ///
+    /// TTLs are given in seconds. Setting `GONG_CACHE_MAX_ENTRIES=0` disables
+    /// the cache.
+    pub fn from_env() -> Self {
+        fn env<T: std::str::FromStr>(name: &str) -> Option<T> {
+            let value = std::env::var(name).ok()?;
+            match value.parse() {
+                Ok(parsed) => Some(parsed),
+                Err(_) => {
+                    tracing::warn!("Ignoring invalid value for {}: {}", name, value);
+                    None
+                }
+            }
+        }
+
+        let defaults = Self::default();
+        Self {
+            calls_ttl: env("GONG_CACHE_TTL_CALLS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.calls_ttl),
+            users_ttl: env("GONG_CACHE_TTL_USERS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.users_ttl),
+            transcripts_ttl: env("GONG_CACHE_TTL_TRANSCRIPTS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.transcripts_ttl),
+            max_entries: env("GONG_CACHE_MAX_ENTRIES").unwrap_or(defaults.max_entries),
+            max_bytes: env("GONG_CACHE_MAX_BYTES").unwrap_or(defaults.max_bytes),
+        }
+    }
+
+    pub fn ttl(&self, kind: CacheKind) -> Duration {
+        match kind {
+            CacheKind::Calls => self.calls_ttl,
+            CacheKind::Users => self.users_ttl,
+            CacheKind::Transcripts => self.transcripts_ttl,
+        }
+    }
+}
+
+/// Hit and miss counters for one kind of response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct Entry {
+    kind: CacheKind,
+    body: Arc<str>,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, Entry>,
+    bytes: usize,
+    // Monotonic counter used to find the least recently used entry
+    clock: u64,
+    stats: [CacheStats; 3],
+    evictions: u64,
+}
+
+impl CacheState {
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.bytes -= entry.body.len();
+        }
+    }
+
+    fn evict_lru(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.remove(&key);
+            self.evictions += 1;
+        }
+    }
+}
+
+/// A size-bounded TTL cache shared by all clones of the server
+pub(crate) struct ResponseCache {
+    config: CacheConfig,
+    state: Mutex<CacheState>,
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Build a cache key from an endpoint name and its request parameters
+    pub fn key(endpoint: &str, params: &impl Serialize) -> String {
+        format!(
+            "{}:{}",
+            endpoint,
+            serde_json::to_string(params).unwrap_or_default()
+        )
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, kind: CacheKind, key: &str) -> Option<T> {
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        state.clock += 1;
+        let clock = state.clock;
+
+        let expired = state
+            .entries
+            .get(key)
+            .is_some_and(|entry| entry.expires_at <= Instant::now());
+        if expired {
+            state.remove(key);
+        }
+
+        let body = state.entries.get_mut(key).map(|entry| {
+            entry.last_used = clock;
+            entry.body.clone()
+        });
+
+        let stats = &mut state.stats[kind.index()];
+        match body.and_then(|body| serde_json::from_str(&body).ok()) {
+            Some(value) => {
+                stats.hits += 1;
+                Some(value)
+            }
+            None => {
+                stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert<T: Serialize>(&self, kind: CacheKind, key: String, value: &T) {
+        let ttl = self.config.ttl(kind);
+        if ttl.is_zero() || self.config.max_entries == 0 {
+            return;
+        }
+
+        let Ok(body) = serde_json::to_string(value) else {
+            return;
+        };
+        // Never let a single response flush the whole cache
+        if body.len() > self.config.max_bytes / 2 {
+            return;
+        }
+
+        let mut guard = self.state.lock().unwrap();
+        let state = &mut *guard;
+        state.remove(&key);
+        while state.entries.len() >= self.config.max_entries
+            || state.bytes + body.len() > self.config.max_bytes
+        {
+            if state.entries.is_empty() {
+                break;
+            }
+            state.evict_lru();
+        }
+
+        state.clock += 1;
+        state.bytes += body.len();
+        let last_used = state.clock;
+        state.entries.insert(
+            key,
+            Entry {
+                kind,
+                body: Arc::from(body),
+                expires_at: Instant::now() + ttl,
+                last_used,
+            },
+        );
+    }
+
+    /// Drop the entries of one kind, or only those whose request mentions `mentioning`
+    ///
+    /// Keys hold the request parameters, so `mentioning` can be a call ID.
+    pub fn invalidate(&self, kind: CacheKind, mentioning: Option<&str>) {
+        let mut state = self.state.lock().unwrap();
+        let keys: Vec<String> = state
+            .entries
+            .iter()
+            .filter(|(key, entry)| {
+                entry.kind == kind && mentioning.is_none_or(|text| key.contains(text))
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            state.remove(&key);
+        }
+    }
+
+    #[cfg(test)]
+    pub fn stats(&self, kind: CacheKind) -> CacheStats {
+        self.state.lock().unwrap().stats[kind.index()]
+    }
+
+    /// Cache statistics for the `gong://status` resource
+    pub fn status(&self) -> Value {
+        let state = self.state.lock().unwrap();
+
+        let by_kind: serde_json::Map<String, Value> = CacheKind::ALL
+            .iter()
+            .map(|kind| {
+                let stats = state.stats[kind.index()];
+                let entries = state.entries.values().filter(|e| e.kind == *kind).count();
+                (
+                    kind.as_str().to_string(),
+                    json!({
+                        "entries": entries,
+                        "hits": stats.hits,
+                        "misses": stats.misses,
+                        "ttlSeconds": self.config.ttl(*kind).as_secs(),
+                    }),
+                )
+            })
+            .collect();
+
+        json!({
+            "entries": state.entries.len(),
+            "bytes": state.bytes,
+            "maxEntries": self.config.max_entries,
+            "maxBytes": self.config.max_bytes,
+            "hits": state.stats.iter().map(|s| s.hits).sum::<u64>(),
+            "misses": state.stats.iter().map(|s| s.misses).sum::<u64>(),
+            "evictions": state.evictions,
+            "byKind": by_kind,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_entries: usize, max_bytes: usize) -> ResponseCache {
+        ResponseCache::new(CacheConfig {
+            max_entries,
+            max_bytes,
+            ..CacheConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_cache_hit_and_miss() {
+        let cache = cache(10, 1024);
+        let key = ResponseCache::key("calls", &json!({"callIds": ["1"]}));
+
+        assert_eq!(cache.get::<Value>(CacheKind::Calls, &key), None);
+        cache.insert(CacheKind::Calls, key.clone(), &json!({"calls": [1]}));
+        assert_eq!(
+            cache.get::<Value>(CacheKind::Calls, &key),
+            Some(json!({"calls": [1]}))
+        );
+
+        let stats = cache.stats(CacheKind::Calls);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+    }
+
+    #[test]
+    fn test_cache_key_includes_parameters() {
+        assert_ne!(
+            ResponseCache::key("calls", &json!({"cursor": "a"})),
+            ResponseCache::key("calls", &json!({"cursor": "b"}))
+        );
+        assert_ne!(
+            ResponseCache::key("calls", &json!({})),
+            ResponseCache::key("transcripts", &json!({}))
+        );
+    }
+
+    #[test]
+    fn test_invalidate_by_kind_and_text() {
+        let cache = cache(10, 1024);
+        let list = ResponseCache::key("calls", &json!({"cursor": null}));
+        let first = ResponseCache::key("calls/transcript", &json!({"callIds": ["111"]}));
+        let second = ResponseCache::key("calls/transcript", &json!({"callIds": ["222"]}));
+        cache.insert(CacheKind::Calls, list.clone(), &json!(1));
+        cache.insert(CacheKind::Transcripts, first.clone(), &json!(2));
+        cache.insert(CacheKind::Transcripts, second.clone(), &json!(3));
+
+        cache.invalidate(CacheKind::Transcripts, Some("\"111\""));
+        assert_eq!(cache.get::<Value>(CacheKind::Transcripts, &first), None);
+        assert!(
+            cache
+                .get::<Value>(CacheKind::Transcripts, &second)
+                .is_some()
+        );
+        assert!(cache.get::<Value>(CacheKind::Calls, &list).is_some());
+
+        cache.invalidate(CacheKind::Calls, None);
+        assert_eq!(cache.get::<Value>(CacheKind::Calls, &list), None);
+    }
+
+    #[test]
+    fn test_zero_ttl_disables_kind() {
+        let cache = ResponseCache::new(CacheConfig {
+            calls_ttl: Duration::ZERO,
+            ..CacheConfig::default()
+        });
+        cache.insert(CacheKind::Calls, "k".to_string(), &json!(1));
+        assert_eq!(cache.get::<Value>(CacheKind::Calls, "k"), None);
+
+        cache.insert(CacheKind::Transcripts, "t".to_string(), &json!(1));
+        assert_eq!(
+            cache.get::<Value>(CacheKind::Transcripts, "t"),
+            Some(json!(1))
+        );
+    }
+
+    #[test]
+    fn test_expired_entries_are_dropped() {
+        let cache = ResponseCache::new(CacheConfig {
+            calls_ttl: Duration::from_millis(1),
+            ..CacheConfig::default()
+        });
+        cache.insert(CacheKind::Calls, "k".to_string(), &json!(1));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get::<Value>(CacheKind::Calls, "k"), None);
+        assert_eq!(cache.status()["entries"], 0);
+    }
+
+    #[test]
+    fn test_lru_eviction_by_entry_count() {
+        let cache = cache(2, 1024);
+        cache.insert(CacheKind::Calls, "a".to_string(), &json!(1));
+        cache.insert(CacheKind::Calls, "b".to_string(), &json!(2));
+        // Touch "a" so "b" becomes the least recently used entry
+        assert!(cache.get::<Value>(CacheKind::Calls, "a").is_some());
+        cache.insert(CacheKind::Calls, "c".to_string(), &json!(3));
+
+        assert!(cache.get::<Value>(CacheKind::Calls, "a").is_some());
+        assert!(cache.get::<Value>(CacheKind::Calls, "b").is_none());
+        assert!(cache.get::<Value>(CacheKind::Calls, "c").is_some());
+        assert_eq!(cache.status()["evictions"], 1);
+    }
+
+    #[test]
+    fn test_eviction_by_size() {
+        // Each entry serializes to 22 bytes, so only two fit in 50
+        let cache = cache(100, 50);
+        cache.insert(CacheKind::Transcripts, "a".to_string(), &"x".repeat(20));
+        cache.insert(CacheKind::Transcripts, "b".to_string(), &"y".repeat(20));
+        cache.insert(CacheKind::Transcripts, "c".to_string(), &"z".repeat(20));
+
+        let status = cache.status();
+        assert_eq!(status["entries"], 2);
+        assert_eq!(status["bytes"], 44);
+        assert!(cache.get::<String>(CacheKind::Transcripts, "a").is_none());
+        assert!(cache.get::<String>(CacheKind::Transcripts, "c").is_some());
+    }
+
+    #[test]
+    fn test_oversized_responses_are_not_cached() {
+        let cache = cache(100, 40);
+        cache.insert(CacheKind::Transcripts, "big".to_string(), &"z".repeat(30));
+        assert_eq!(cache.status()["entries"], 0);
+    }
+}
@@ -0,0 +1,205 @@
+//! Argument completion for resource templates and prompts.
+//!
+//! Gong IDs are long numbers nobody types from memory, so `callId`, `userId`
+//! and `workspaceId` complete against recent calls and cached users, matching
+//! what the user does know: a call's title, a user's name or email address,
+//! or the start of the ID itself. `repEmail` completes to email addresses.
+
+use rmcp::model::CompletionInfo;
+use serde_json::Value;
+
+/// What an argument completes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompletionKind {
+    Call,
+    User,
+    UserEmail,
+    Workspace,
+}
+
+impl CompletionKind {
+    /// The kind of the template variable or prompt argument `name`
+    pub fn for_argument(name: &str) -> Option<Self> {
+        match name {
+            "callId" => Some(Self::Call),
+            "userId" => Some(Self::User),
+            "repEmail" => Some(Self::UserEmail),
+            "workspaceId" => Some(Self::Workspace),
+            _ => None,
+        }
+    }
+}
+
+/// A possible value and the text it can be found by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Candidate {
+    pub value: String,
+    pub labels: Vec<String>,
+}
+
+/// The candidates matching `typed`, in their original order
+///
+/// A candidate matches when its value, one of its labels or a word in a
+/// label starts with `typed`, ignoring case.
+pub(crate) fn complete(candidates: Vec<Candidate>, typed: &str) -> CompletionInfo {
+    let typed = typed.trim().to_lowercase();
+    let matches = |text: &str| {
+        let text = text.to_lowercase();
+        text.starts_with(&typed) || text.split_whitespace().any(|word| word.starts_with(&typed))
+    };
+
+    let mut values: Vec<String> = Vec::new();
+    for candidate in candidates {
+        if values.contains(&candidate.value) {
+            continue;
+        }
+        if matches(&candidate.value) || candidate.labels.iter().any(|label| matches(label)) {
+            values.push(candidate.value);
+        }
+    }
+
+    let total = values.len();
+    values.truncate(CompletionInfo::MAX_VALUES);
+    CompletionInfo {
+        has_more: Some(total > values.len()),
+        total: Some(total as u32),
+        values,
+    }
+}
+
+/// Calls found by their title
+pub(crate) fn call_candidates(calls: &[Value]) -> Vec<Candidate> {
+    calls
+        .iter()
+        .filter_map(|call| {
+            let meta = &call["metaData"];
+            Some(Candidate {
+                value: meta["id"].as_str()?.to_string(),
+                labels: meta["title"]
+                    .as_str()
+                    .map(String::from)
+                    .into_iter()
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Users found by name or email address, completing to their ID or email
+pub(crate) fn user_candidates(users: &[Value], kind: CompletionKind) -> Vec<Candidate> {
+    users
+        .iter()
+        .filter_map(|user| {
+            let email = user["emailAddress"].as_str();
+            let value = match kind {
+                CompletionKind::UserEmail => email?,
+                _ => user["id"].as_str()?,
+            };
+            let name = [user["firstName"].as_str(), user["lastName"].as_str()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(" ");
+            Some(Candidate {
+                value: value.to_string(),
+                labels: [Some(name.as_str()), email]
+                    .into_iter()
+                    .flatten()
+                    .filter(|label| !label.is_empty())
+                    .map(String::from)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// The workspaces of recent calls, most active first
+///
+/// Gong doesn't name workspaces in call data, so they only match by ID.
+pub(crate) fn workspace_candidates(calls: &[Value]) -> Vec<Candidate> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for workspace_id in calls
+        .iter()
+        .filter_map(|call| call["metaData"]["workspaceId"].as_str())
+    {
+        match counts.iter_mut().find(|(id, _)| id == workspace_id) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((workspace_id.to_string(), 1)),
+        }
+    }
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
+        .into_iter()
+        .map(|(value, _)| Candidate {
+            value,
+            labels: Vec::new(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn calls() -> Vec<Value> {
+        vec![
+            json!({"metaData": {"id": "333", "title": "Acme Pricing", "workspaceId": "W2"}}),
+            json!({"metaData": {"id": "222", "title": "Globex Demo", "workspaceId": "W2"}}),
+            json!({"metaData": {"id": "111", "title": "Acme Discovery", "workspaceId": "W1"}}),
+        ]
+    }
+
+    #[test]
+    fn test_calls_match_title() {
+        let completion = complete(call_candidates(&calls()), "acme");
+        assert_eq!(completion.values, vec!["333", "111"]);
+        assert_eq!(completion.total, Some(2));
+        assert_eq!(completion.has_more, Some(false));
+
+        assert_eq!(
+            complete(call_candidates(&calls()), "pric").values,
+            vec!["333"]
+        );
+        assert_eq!(
+            complete(call_candidates(&calls()), "22").values,
+            vec!["222"]
+        );
+        assert_eq!(complete(call_candidates(&calls()), "").values.len(), 3);
+    }
+
+    #[test]
+    fn test_users_match_name_and_email() {
+        let users = vec![
+            json!({"id": "U1", "emailAddress": "jane.doe@example.com", "firstName": "Jane", "lastName": "Doe"}),
+            json!({"id": "U2", "emailAddress": "john@example.com", "firstName": "John", "lastName": "Smith"}),
+        ];
+        let ids = user_candidates(&users, CompletionKind::User);
+        assert_eq!(complete(ids.clone(), "doe").values, vec!["U1"]);
+        assert_eq!(complete(ids.clone(), "jane d").values, vec!["U1"]);
+        assert_eq!(complete(ids, "JOHN@").values, vec!["U2"]);
+
+        let emails = user_candidates(&users, CompletionKind::UserEmail);
+        assert_eq!(complete(emails, "smith").values, vec!["john@example.com"]);
+    }
+
+    #[test]
+    fn test_workspaces_by_activity() {
+        let completion = complete(workspace_candidates(&calls()), "w");
+        assert_eq!(completion.values, vec!["W2", "W1"]);
+    }
+
+    #[test]
+    fn test_completion_is_capped() {
+        let candidates = (0..150)
+            .map(|i| Candidate {
+                value: i.to_string(),
+                labels: Vec::new(),
+            })
+            .collect();
+        let completion = complete(candidates, "");
+        assert_eq!(completion.values.len(), CompletionInfo::MAX_VALUES);
+        assert_eq!(completion.total, Some(150));
+        assert_eq!(completion.has_more, Some(true));
+    }
+}
@@ -0,0 +1,467 @@
+//! Persistent on-disk cache for transcripts and call metadata.
+//!
+//! Transcripts never change once Gong has processed a call, so keeping them
+//! across restarts saves a large share of the daily API quota. Entries are
+//! stored as one JSON file per call under a directory per kind:
+//!
+//! ```text
+//! <dir>/transcripts/<callId>.json
+//! <dir>/calls/<callId>.json
+//! ```
+//!
+//! Every file wraps the Gong response in an envelope carrying
+//! [`DISK_CACHE_VERSION`]; entries written by an incompatible version are
+//! ignored and removed. Call metadata can still change after a call ends (Gong
+//! fills in parties and speakers while processing it), so call entries expire
+//! after [`CALLS_MAX_AGE`]; transcripts are kept until evicted. When the
+//! directory grows beyond its size limit the least recently used files are
+//! deleted.
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// Bump whenever the envelope or the cached response format changes
+pub const DISK_CACHE_VERSION: u32 = 1;
+
+/// Environment variable selecting the cache directory
+pub const CACHE_DIR_ENV: &str = "GONG_CACHE_DIR";
+
+/// Environment variable overriding the maximum cache size in bytes
+pub const CACHE_DIR_MAX_BYTES_ENV: &str = "GONG_CACHE_DIR_MAX_BYTES";
+
+/// Default maximum size of the cache directory
+pub const DEFAULT_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// How long call metadata is served from disk before it is fetched again
+pub const CALLS_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The kind of entry stored on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskKind {
+    Transcripts,
+    Calls,
+}
+
+impl DiskKind {
+    pub const ALL: [DiskKind; 2] = [DiskKind::Transcripts, DiskKind::Calls];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiskKind::Transcripts => "transcripts",
+            DiskKind::Calls => "calls",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+
+    /// Maximum age of an entry, or `None` if entries never expire
+    pub fn max_age(&self) -> Option<Duration> {
+        match self {
+            DiskKind::Transcripts => None,
+            DiskKind::Calls => Some(CALLS_MAX_AGE),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    version: u32,
+    key: String,
+    stored_at: String,
+    body: Value,
+}
+
+impl Envelope {
+    fn is_expired(&self, max_age: Option<Duration>) -> bool {
+        let Some(max_age) = max_age else {
+            return false;
+        };
+        let Ok(stored_at) = chrono::DateTime::parse_from_rfc3339(&self.stored_at) else {
+            return true;
+        };
+        let age = chrono::Utc::now().signed_duration_since(stored_at);
+        age.to_std().is_ok_and(|age| age > max_age)
+    }
+}
+
+/// Number of files and bytes removed by a purge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeStats {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// A size-bounded directory of cached Gong responses
+pub struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    // Running total of bytes on disk, initialised by scanning the directory
+    bytes: Mutex<u64>,
+}
+
+impl DiskCache {
+    /// Open (and create if needed) a cache directory
+    pub fn open(dir: impl Into<PathBuf>, max_bytes: u64) -> Result<Self> {
+        let dir = dir.into();
+        for kind in DiskKind::ALL {
+            fs::create_dir_all(dir.join(kind.as_str()))
+                .with_context(|| format!("failed to create cache directory {}", dir.display()))?;
+        }
+
+        let cache = Self {
+            dir,
+            max_bytes,
+            bytes: Mutex::new(0),
+        };
+        let total = cache.files(None).iter().map(|f| f.size).sum();
+        *cache.bytes.lock().unwrap() = total;
+        Ok(cache)
+    }
+
+    /// Open the cache configured through `GONG_CACHE_DIR`, if any
+    pub fn from_env() -> Option<Self> {
+        let dir = std::env::var_os(CACHE_DIR_ENV)?;
+
+        match Self::open(PathBuf::from(dir), max_bytes_from_env()) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                tracing::warn!("Disk cache disabled: {:#}", e);
+                None
+            }
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, kind: DiskKind, key: &str) -> PathBuf {
+        self.dir
+            .join(kind.as_str())
+            .join(format!("{}.json", file_stem(key)))
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, kind: DiskKind, key: &str) -> Option<T> {
+        let path = self.path(kind, key);
+        let contents = fs::read_to_string(&path).ok()?;
+
+        let envelope = serde_json::from_str::<Envelope>(&contents)
+            .ok()
+            .filter(|e| e.version == DISK_CACHE_VERSION && e.key == key)
+            .filter(|e| !e.is_expired(kind.max_age()));
+        let Some(envelope) = envelope else {
+            tracing::debug!("Discarding stale disk cache entry {}", path.display());
+            self.remove_file(&path);
+            return None;
+        };
+
+        let value = serde_json::from_value(envelope.body).ok()?;
+
+        // Touch the file so eviction keeps recently used entries
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        Some(value)
+    }
+
+    pub fn put<T: Serialize>(&self, kind: DiskKind, key: &str, value: &T) {
+        if let Err(e) = self.try_put(kind, key, value) {
+            tracing::warn!("Failed to write disk cache entry {}: {:#}", key, e);
+        }
+    }
+
+    /// Remove one entry, e.g. after Gong reported a change to the call
+    pub fn remove(&self, kind: DiskKind, key: &str) {
+        self.remove_file(&self.path(kind, key));
+    }
+
+    fn try_put<T: Serialize>(&self, kind: DiskKind, key: &str, value: &T) -> Result<()> {
+        let envelope = Envelope {
+            version: DISK_CACHE_VERSION,
+            key: key.to_string(),
+            stored_at: chrono::Utc::now().to_rfc3339(),
+            body: serde_json::to_value(value)?,
+        };
+        let contents = serde_json::to_vec(&envelope)?;
+
+        let path = self.path(kind, key);
+        let previous = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+
+        // Write to a temporary file first so readers never see partial entries
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, &contents)?;
+        fs::rename(&tmp, &path)?;
+
+        let over_limit = {
+            let mut bytes = self.bytes.lock().unwrap();
+            *bytes = (*bytes + contents.len() as u64).saturating_sub(previous);
+            *bytes > self.max_bytes
+        };
+        if over_limit {
+            self.evict();
+        }
+        Ok(())
+    }
+
+    /// Delete least recently used files until the cache is at 90% of its limit
+    fn evict(&self) {
+        let mut files = self.files(None);
+        files.sort_by_key(|f| f.modified);
+
+        let target = self.max_bytes / 10 * 9;
+        let mut bytes = self.bytes.lock().unwrap();
+        for file in files {
+            if *bytes <= target {
+                break;
+            }
+            if fs::remove_file(&file.path).is_ok() {
+                *bytes = bytes.saturating_sub(file.size);
+            }
+        }
+    }
+
+    fn remove_file(&self, path: &Path) {
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if fs::remove_file(path).is_ok() {
+            let mut bytes = self.bytes.lock().unwrap();
+            *bytes = bytes.saturating_sub(size);
+        }
+    }
+
+    /// Remove all entries, or only those of one kind
+    pub fn purge(&self, kind: Option<DiskKind>) -> Result<PurgeStats> {
+        let mut stats = PurgeStats::default();
+        for file in self.files(kind) {
+            fs::remove_file(&file.path)
+                .with_context(|| format!("failed to remove {}", file.path.display()))?;
+            stats.files += 1;
+            stats.bytes += file.size;
+        }
+
+        let mut bytes = self.bytes.lock().unwrap();
+        *bytes = bytes.saturating_sub(stats.bytes);
+        Ok(stats)
+    }
+
+    fn files(&self, kind: Option<DiskKind>) -> Vec<CachedFile> {
+        let kinds: Vec<DiskKind> = match kind {
+            Some(kind) => vec![kind],
+            None => DiskKind::ALL.to_vec(),
+        };
+
+        kinds
+            .into_iter()
+            .filter_map(|kind| fs::read_dir(self.dir.join(kind.as_str())).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                metadata.is_file().then(|| CachedFile {
+                    path: entry.path(),
+                    size: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                })
+            })
+            .collect()
+    }
+
+    /// Disk cache statistics for the `gong://status` resource
+    pub fn status(&self) -> Value {
+        let entries: serde_json::Map<String, Value> = DiskKind::ALL
+            .iter()
+            .map(|kind| {
+                (
+                    kind.as_str().to_string(),
+                    json!(self.files(Some(*kind)).len()),
+                )
+            })
+            .collect();
+
+        json!({
+            "dir": self.dir.display().to_string(),
+            "version": DISK_CACHE_VERSION,
+            "entries": entries,
+            "bytes": *self.bytes.lock().unwrap(),
+            "maxBytes": self.max_bytes,
+        })
+    }
+}
+
+/// Maximum cache size from `GONG_CACHE_DIR_MAX_BYTES`, or the default
+pub fn max_bytes_from_env() -> u64 {
+    std::env::var(CACHE_DIR_MAX_BYTES_ENV)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_BYTES)
+}
+
+struct CachedFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+/// Map a cache key to a safe file name
+///
+/// Gong call IDs are plain digits and are used as is; anything else is hex
+/// encoded so keys can never escape the cache directory.
+fn file_stem(key: &str) -> String {
+    let is_safe = !key.is_empty()
+        && key.len() <= 128
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if is_safe {
+        key.to_string()
+    } else {
+        let hex: String = key.bytes().map(|b| format!("{:02x}", b)).collect();
+        format!("x{}", hex)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "gong-mcp-{}-{}-{}",
+            name,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    #[test]
+    fn test_put_and_get() {
+        let dir = temp_dir("put-get");
+        let cache = DiskCache::open(&dir, DEFAULT_MAX_BYTES).unwrap();
+
+        assert_eq!(cache.get::<Value>(DiskKind::Transcripts, "123"), None);
+        cache.put(
+            DiskKind::Transcripts,
+            "123",
+            &json!({"callTranscripts": []}),
+        );
+        assert_eq!(
+            cache.get::<Value>(DiskKind::Transcripts, "123"),
+            Some(json!({"callTranscripts": []}))
+        );
+        assert_eq!(cache.get::<Value>(DiskKind::Calls, "123"), None);
+
+        // Entries survive reopening the cache
+        let reopened = DiskCache::open(&dir, DEFAULT_MAX_BYTES).unwrap();
+        assert!(
+            reopened
+                .get::<Value>(DiskKind::Transcripts, "123")
+                .is_some()
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_version_mismatch_is_a_miss() {
+        let dir = temp_dir("version");
+        let cache = DiskCache::open(&dir, DEFAULT_MAX_BYTES).unwrap();
+
+        let path = cache.path(DiskKind::Calls, "42");
+        fs::write(
+            &path,
+            json!({"version": 0, "key": "42", "storedAt": "", "body": {}}).to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(cache.get::<Value>(DiskKind::Calls, "42"), None);
+        assert!(!path.exists(), "stale entries should be removed");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_expired_calls_are_a_miss() {
+        let dir = temp_dir("expiry");
+        let cache = DiskCache::open(&dir, DEFAULT_MAX_BYTES).unwrap();
+
+        let stored_at = chrono::Utc::now() - chrono::Duration::hours(25);
+        for kind in DiskKind::ALL {
+            fs::write(
+                cache.path(kind, "42"),
+                json!({
+                    "version": DISK_CACHE_VERSION,
+                    "key": "42",
+                    "storedAt": stored_at.to_rfc3339(),
+                    "body": {"id": "42"},
+                })
+                .to_string(),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(cache.get::<Value>(DiskKind::Calls, "42"), None);
+        assert!(!cache.path(DiskKind::Calls, "42").exists());
+        assert!(cache.get::<Value>(DiskKind::Transcripts, "42").is_some());
+
+        // Fresh call entries are still served
+        cache.put(DiskKind::Calls, "42", &json!({"id": "42"}));
+        assert!(cache.get::<Value>(DiskKind::Calls, "42").is_some());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_eviction_keeps_cache_under_limit() {
+        let dir = temp_dir("evict");
+        let cache = DiskCache::open(&dir, 1_000).unwrap();
+
+        for i in 0..10 {
+            cache.put(DiskKind::Transcripts, &i.to_string(), &"x".repeat(200));
+        }
+
+        let bytes: u64 = cache.files(None).iter().map(|f| f.size).sum();
+        assert!(bytes <= 1_000, "cache uses {} bytes", bytes);
+        assert!(cache.get::<String>(DiskKind::Transcripts, "9").is_some());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_purge_by_kind() {
+        let dir = temp_dir("purge");
+        let cache = DiskCache::open(&dir, DEFAULT_MAX_BYTES).unwrap();
+        cache.put(DiskKind::Transcripts, "1", &json!(1));
+        cache.put(DiskKind::Calls, "1", &json!(1));
+
+        let stats = cache.purge(Some(DiskKind::Transcripts)).unwrap();
+        assert_eq!(stats.files, 1);
+        assert!(cache.get::<Value>(DiskKind::Calls, "1").is_some());
+
+        let stats = cache.purge(None).unwrap();
+        assert_eq!(stats.files, 1);
+        assert_eq!(cache.status()["bytes"], 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_stem_is_path_safe() {
+        assert_eq!(file_stem("7782342274025937895"), "7782342274025937895");
+        assert_eq!(file_stem("../etc"), "x2e2e2f657463");
+        assert!(!file_stem("a/b").contains('/'));
+    }
+}
@@ -0,0 +1,372 @@
+//! Classification of Gong API errors.
+//!
+//! gong-rs reports a failed request as `Error::ResponseError` carrying the
+//! HTTP status and raw body, or as a transport or deserialization error. This
+//! module turns them into distinct MCP errors: each kind has its own message
+//! and error code, from the range JSON-RPC leaves to servers, and the error
+//! data carries the HTTP status, the Gong request ID and error messages from
+//! the response body, and a hint on how to fix the problem.
+//!
+//! Tool failures are returned to the model as `is_error` results rather than
+//! protocol errors, which many clients don't show it; [`tool_error`] renders
+//! an error as a readable explanation with an example of a valid value.
+
+use crate::retry::RetryError;
+use gong_rs::apis::Error;
+use rmcp::ErrorData as McpError;
+use rmcp::model::{CallToolResult, Content, ErrorCode};
+use serde_json::{Value, json};
+
+/// Code of a request the client cancelled while it waited for the Gong API
+const REQUEST_CANCELLED: ErrorCode = ErrorCode(-32060);
+
+/// Code of a request not sent because the daily quota is used up
+const QUOTA_EXHAUSTED: ErrorCode = ErrorCode(-32030);
+
+/// What went wrong with a Gong API request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ApiErrorKind {
+    /// 400: Gong rejected the request parameters
+    BadRequest,
+    /// 401: the access key or secret is wrong
+    BadCredentials,
+    /// 403: the access key lacks a scope
+    MissingScope,
+    /// 404
+    NotFound,
+    /// 429
+    RateLimited,
+    /// 5xx
+    Unavailable,
+    Timeout,
+    Connection,
+    /// The response could not be deserialized
+    UnexpectedResponse,
+    Other,
+}
+
+impl ApiErrorKind {
+    pub fn classify<T>(error: &Error<T>) -> Self {
+        match error {
+            Error::ResponseError(response) => match response.status.as_u16() {
+                400 => Self::BadRequest,
+                401 => Self::BadCredentials,
+                403 => Self::MissingScope,
+                404 => Self::NotFound,
+                429 => Self::RateLimited,
+                500..=599 => Self::Unavailable,
+                _ => Self::Other,
+            },
+            Error::Reqwest(e) if e.is_timeout() => Self::Timeout,
+            Error::Reqwest(e) if e.is_connect() => Self::Connection,
+            Error::Reqwest(e) if e.is_decode() => Self::UnexpectedResponse,
+            Error::Serde(_) => Self::UnexpectedResponse,
+            _ => Self::Other,
+        }
+    }
+
+    /// Whether retrying the same request may succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited | Self::Unavailable | Self::Timeout | Self::Connection
+        )
+    }
+
+    /// A server-defined JSON-RPC code, so clients can tell kinds apart
+    ///
+    /// Codes follow the HTTP status where there is one; a 404 keeps MCP's
+    /// resource-not-found code.
+    fn code(&self) -> ErrorCode {
+        ErrorCode(match self {
+            Self::BadRequest => -32040,
+            Self::BadCredentials => -32041,
+            Self::MissingScope => -32043,
+            Self::NotFound => ErrorCode::RESOURCE_NOT_FOUND.0,
+            Self::RateLimited => -32029,
+            Self::Unavailable => -32050,
+            Self::Timeout => -32051,
+            Self::Connection => -32052,
+            Self::UnexpectedResponse => -32053,
+            Self::Other => -32000,
+        })
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            Self::BadRequest => "bad_request",
+            Self::BadCredentials => "bad_credentials",
+            Self::MissingScope => "missing_scope",
+            Self::NotFound => "not_found",
+            Self::RateLimited => "rate_limited",
+            Self::Unavailable => "gong_unavailable",
+            Self::Timeout => "timeout",
+            Self::Connection => "connection_failed",
+            Self::UnexpectedResponse => "unexpected_response",
+            Self::Other => "api_error",
+        }
+    }
+
+    fn hint(&self) -> &'static str {
+        match self {
+            Self::BadRequest => "Gong rejected the request parameters; see errors for details",
+            Self::BadCredentials => {
+                "Check GONG_ACCESS_KEY and GONG_ACCESS_KEY_SECRET; API keys are managed under Company Settings > Ecosystem > API in Gong"
+            }
+            Self::MissingScope => {
+                "The API key lacks a required scope; grant it api:calls:read:basic, api:calls:read:extensive, api:calls:read:transcript and api:users:read"
+            }
+            Self::NotFound => {
+                "Check the ID; calls Gong has not finished processing have no transcript yet"
+            }
+            Self::RateLimited => {
+                "Gong's rate limit was still exceeded after retrying; try again later or lower GONG_RATE_LIMIT_PER_SECOND"
+            }
+            Self::Unavailable => "Gong is having problems; try again later",
+            Self::Timeout => {
+                "The request to Gong timed out; try a narrower date range or fewer call IDs"
+            }
+            Self::Connection => "Check GONG_BASE_URL and the network connection",
+            Self::UnexpectedResponse => {
+                "Gong returned a response this server could not parse; check for a gong-mcp update"
+            }
+            Self::Other => "See error for details",
+        }
+    }
+}
+
+/// Map a failed Gong API request to an MCP error
+///
+/// `not_found` is the message used for a 404, e.g. `call_not_found`, and
+/// `context` (a JSON object) is merged into the error data.
+pub(crate) fn api_error<T: std::fmt::Debug>(
+    failure: RetryError<Error<T>>,
+    not_found: &'static str,
+    context: Value,
+) -> McpError {
+    let (error, retries) = match failure {
+        RetryError::Failed { error, retries } => (error, retries),
+        RetryError::Cancelled { retries } => {
+            return McpError::new(
+                REQUEST_CANCELLED,
+                "request_cancelled",
+                Some(json!({
+                    "message": "The request was cancelled while waiting for the Gong API",
+                    "retries": retries,
+                })),
+            );
+        }
+        RetryError::QuotaExhausted(exhausted) => {
+            return McpError::new(
+                QUOTA_EXHAUSTED,
+                "quota_exhausted",
+                Some(json!({
+                    "message": format!(
+                        "The daily Gong API quota of {} requests is used up; it resets at {}",
+                        exhausted.daily_quota, exhausted.resets_at
+                    ),
+                    "dailyQuota": exhausted.daily_quota,
+                    "resetsAt": exhausted.resets_at,
+                })),
+            );
+        }
+    };
+
+    let kind = ApiErrorKind::classify(&error);
+    let mut data = match context {
+        Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    data.insert("error".to_string(), json!(error.to_string()));
+    data.insert("hint".to_string(), json!(kind.hint()));
+    data.insert("retries".to_string(), json!(retries));
+
+    if let Error::ResponseError(response) = &error {
+        data.insert("status".to_string(), json!(response.status.as_u16()));
+        // Gong error bodies look like {"requestId": "...", "errors": ["..."]}
+        if let Ok(body) = serde_json::from_str::<Value>(&response.content) {
+            if let Some(request_id) = body.get("requestId") {
+                data.insert("requestId".to_string(), request_id.clone());
+            }
+            if let Some(errors) = body.get("errors") {
+                data.insert("errors".to_string(), errors.clone());
+            }
+        }
+    }
+
+    let message = match kind {
+        ApiErrorKind::NotFound => not_found,
+        kind => kind.message(),
+    };
+    McpError::new(kind.code(), message, Some(Value::Object(data)))
+}
+
+/// Report a failed tool call to the model
+pub(crate) fn tool_error(tool: &str, error: McpError) -> CallToolResult {
+    let data = error.data.unwrap_or(Value::Null);
+    let mut text = format!("{} failed: {}", tool, error.message);
+    if let Some(detail) = data
+        .get("message")
+        .or_else(|| data.get("error"))
+        .and_then(|v| v.as_str())
+    {
+        text.push_str(&format!("\n{}", detail));
+    }
+    if let Some(hint) = data.get("hint").and_then(|v| v.as_str()) {
+        text.push_str(&format!("\nHint: {}", hint));
+    }
+    if let Some(example) = example(&error.message, &data) {
+        text.push_str(&format!("\nExample: {}", example));
+    }
+    if !data.is_null() {
+        text.push_str(&format!("\nDetails: {}", data));
+    }
+
+    CallToolResult {
+        content: vec![Content::text(text)],
+        structured_content: None,
+        is_error: Some(true),
+        meta: None,
+    }
+}
+
+/// A valid argument for the mistake behind `message`, if there is a typical one
+fn example(message: &str, data: &Value) -> Option<String> {
+    // Paths like call_ids[1] point into the parameter
+    let parameter = data
+        .get("parameter")
+        .and_then(|v| v.as_str())
+        .and_then(|p| p.split('[').next());
+    let example = match (message, parameter) {
+        ("bad_request", _) | ("invalid_arguments", Some("from_date_time" | "to_date_time")) => {
+            r#"{"from_date_time": "2024-01-01T00:00:00Z", "to_date_time": "2024-02-01T00:00:00Z"}"#
+        }
+        ("missing_call_id" | "call_not_found" | "transcript_not_found", _)
+        | ("invalid_arguments", Some("call_id")) => {
+            r#"{"call_id": "7782342274025937895"} with an ID returned by search_calls"#
+        }
+        ("missing_filter", _) | ("invalid_arguments", Some("call_ids")) => {
+            r#"{"call_ids": ["7782342274025937895", "2281348192843573645"]}"#
+        }
+        ("invalid_arguments", Some("query")) => r#"{"query": "pricing objection"}"#,
+        ("invalid_query", _) | ("invalid_arguments", Some("sql")) => {
+            r#"{"sql": "SELECT id, title, started FROM calls ORDER BY started DESC LIMIT 10"}"#
+        }
+        _ => return None,
+    };
+    Some(example.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gong_rs::apis::ResponseContent;
+
+    fn response(status: u16, content: &str) -> RetryError<Error<()>> {
+        RetryError::Failed {
+            error: Error::ResponseError(ResponseContent {
+                status: status.try_into().unwrap(),
+                content: content.to_string(),
+                entity: None,
+            }),
+            retries: 0,
+        }
+    }
+
+    #[test]
+    fn test_status_codes_map_to_distinct_errors() {
+        let cases = [
+            (400, ErrorCode(-32040), "bad_request"),
+            (401, ErrorCode(-32041), "bad_credentials"),
+            (403, ErrorCode(-32043), "missing_scope"),
+            (404, ErrorCode::RESOURCE_NOT_FOUND, "not_found"),
+            (429, ErrorCode(-32029), "rate_limited"),
+            (503, ErrorCode(-32050), "gong_unavailable"),
+        ];
+        for (status, code, message) in cases {
+            let error = api_error(response(status, ""), "not_found", json!({}));
+            assert_eq!(error.code, code, "status {}", status);
+            assert_eq!(error.message, message);
+            assert_eq!(error.data.unwrap()["status"], status);
+        }
+    }
+
+    #[test]
+    fn test_kinds_have_unique_server_codes() {
+        let kinds = [
+            ApiErrorKind::BadRequest,
+            ApiErrorKind::BadCredentials,
+            ApiErrorKind::MissingScope,
+            ApiErrorKind::NotFound,
+            ApiErrorKind::RateLimited,
+            ApiErrorKind::Unavailable,
+            ApiErrorKind::Timeout,
+            ApiErrorKind::Connection,
+            ApiErrorKind::UnexpectedResponse,
+            ApiErrorKind::Other,
+        ];
+        let codes: std::collections::HashSet<i32> = kinds
+            .iter()
+            .map(|kind| kind.code().0)
+            .chain([REQUEST_CANCELLED.0, QUOTA_EXHAUSTED.0])
+            .collect();
+        assert_eq!(codes.len(), kinds.len() + 2);
+        assert!(codes.iter().all(|code| (-32099..=-32000).contains(code)));
+    }
+
+    #[test]
+    fn test_error_data_includes_request_id_and_context() {
+        let body = r#"{"requestId": "4al018gzaztcr8nbukw", "errors": ["No calls found"]}"#;
+        let error = api_error(
+            response(404, body),
+            "call_not_found",
+            json!({"callId": "123"}),
+        );
+
+        assert_eq!(error.message, "call_not_found");
+        let data = error.data.unwrap();
+        assert_eq!(data["requestId"], "4al018gzaztcr8nbukw");
+        assert_eq!(data["errors"][0], "No calls found");
+        assert_eq!(data["callId"], "123");
+        assert!(data["hint"].as_str().unwrap().contains("transcript"));
+    }
+
+    #[test]
+    fn test_deserialization_failure_is_unexpected_response() {
+        let serde_error = serde_json::from_str::<Value>("{").unwrap_err();
+        let failure: RetryError<Error<()>> = RetryError::Failed {
+            error: Error::Serde(serde_error),
+            retries: 2,
+        };
+        let error = api_error(failure, "not_found", json!({}));
+        assert_eq!(error.message, "unexpected_response");
+        assert_eq!(error.data.unwrap()["retries"], 2);
+    }
+
+    #[test]
+    fn test_tool_error_explains_and_gives_example() {
+        let error = McpError::invalid_params(
+            "invalid_arguments",
+            Some(json!({
+                "parameter": "from_date_time",
+                "message": "from_date_time: expected an ISO 8601 timestamp, got \"last week\"",
+            })),
+        );
+        let result = tool_error("search_index", error);
+
+        assert_eq!(result.is_error, Some(true));
+        let text = result.content[0].as_text().unwrap().text.clone();
+        assert!(text.starts_with("search_index failed: invalid_arguments"));
+        assert!(text.contains("expected an ISO 8601"));
+        assert!(text.contains("Example: {\"from_date_time\": \"2024-01-01T00:00:00Z\""));
+        assert!(text.contains("last week"));
+    }
+
+    #[test]
+    fn test_transient_kinds() {
+        assert!(ApiErrorKind::RateLimited.is_transient());
+        assert!(ApiErrorKind::Unavailable.is_transient());
+        assert!(!ApiErrorKind::BadCredentials.is_transient());
+        assert!(!ApiErrorKind::NotFound.is_transient());
+    }
+}
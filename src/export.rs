@@ -0,0 +1,670 @@
+//! Bulk export of Gong data for BI tools.
+//!
+//! `gong-mcp export` writes one flat row per call, party, transcript sentence
+//! or user as JSON Lines, CSV or Parquet. Rows are appended page by page and a
+//! checkpoint next to the output (`<out>.export-state.json`) records the
+//! cursor of the next page and the size of the output written so far. Running
+//! the same export again after an interruption truncates anything written
+//! after the last checkpoint and continues from there.
+//!
+//! Parquet files cannot be appended to, so Parquet exports are staged as JSON
+//! Lines (`<out>.partial.jsonl`) and converted once all pages are written.
+
+use anyhow::{Context, Result, bail};
+use arrow_array::{ArrayRef, BooleanArray, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Rows per Parquet row group
+const PARQUET_BATCH_ROWS: usize = 10_000;
+
+/// What to export; each entity has its own flat row schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportEntity {
+    /// One row per call
+    Calls,
+    /// One row per call participant
+    Parties,
+    /// One row per transcript sentence
+    Transcripts,
+    /// One row per Gong user
+    Users,
+}
+
+impl ExportEntity {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "calls" => Some(Self::Calls),
+            "parties" => Some(Self::Parties),
+            "transcripts" => Some(Self::Transcripts),
+            "users" => Some(Self::Users),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn columns(&self) -> &'static [(&'static str, ColumnType)] {
+        use ColumnType::*;
+        match self {
+            Self::Calls => &[
+                ("call_id", Text),
+                ("title", Text),
+                ("started", Text),
+                ("scheduled", Text),
+                ("duration", Integer),
+                ("direction", Text),
+                ("scope", Text),
+                ("system", Text),
+                ("media", Text),
+                ("language", Text),
+                ("url", Text),
+                ("primary_user_id", Text),
+                ("workspace_id", Text),
+                ("purpose", Text),
+                ("is_private", Boolean),
+                ("internal_participants", Integer),
+                ("external_participants", Integer),
+                ("brief", Text),
+            ],
+            Self::Parties => &[
+                ("call_id", Text),
+                ("party_id", Text),
+                ("name", Text),
+                ("email_address", Text),
+                ("title", Text),
+                ("affiliation", Text),
+                ("speaker_id", Text),
+                ("user_id", Text),
+                ("phone_number", Text),
+            ],
+            Self::Transcripts => &[
+                ("call_id", Text),
+                ("speaker_id", Text),
+                ("speaker_name", Text),
+                ("affiliation", Text),
+                ("topic", Text),
+                ("start_ms", Integer),
+                ("end_ms", Integer),
+                ("text", Text),
+            ],
+            Self::Users => &[
+                ("user_id", Text),
+                ("email_address", Text),
+                ("first_name", Text),
+                ("last_name", Text),
+                ("title", Text),
+                ("phone_number", Text),
+                ("manager_id", Text),
+                ("active", Boolean),
+                ("created", Text),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Jsonl,
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "jsonl" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            "parquet" => Some(Self::Parquet),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColumnType {
+    Text,
+    Integer,
+    Boolean,
+}
+
+/// Options of an export run; a checkpoint is only resumed with identical options
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    pub entity: ExportEntity,
+    pub format: ExportFormat,
+    pub from_date_time: Option<String>,
+    pub to_date_time: Option<String>,
+    pub out: PathBuf,
+}
+
+/// Result of an export run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportStats {
+    pub rows: usize,
+    /// The run continued an interrupted export
+    pub resumed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checkpoint {
+    options: ExportOptions,
+    /// Cursor of the next page, if any
+    cursor: Option<String>,
+    /// All pages have been written
+    complete: bool,
+    rows: usize,
+    /// Size of the (staging) output at the checkpoint
+    bytes: u64,
+}
+
+/// Appends pages of rows to an export and keeps its checkpoint up to date
+pub(crate) struct ExportWriter {
+    checkpoint: Checkpoint,
+    checkpoint_path: PathBuf,
+    staging_path: PathBuf,
+    file: File,
+    resumed: bool,
+}
+
+impl ExportWriter {
+    /// Start a new export, or resume the interrupted export with the same options
+    pub(crate) fn open(options: ExportOptions) -> Result<Self> {
+        let checkpoint_path = suffixed(&options.out, ".export-state.json");
+        let staging_path = match options.format {
+            ExportFormat::Parquet => suffixed(&options.out, ".partial.jsonl"),
+            ExportFormat::Jsonl | ExportFormat::Csv => options.out.clone(),
+        };
+
+        let previous = match fs::read_to_string(&checkpoint_path) {
+            Ok(contents) => Some(
+                serde_json::from_str::<Checkpoint>(&contents)
+                    .with_context(|| format!("invalid checkpoint {}", checkpoint_path.display()))?,
+            ),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let (checkpoint, file, resumed) = match previous {
+            Some(checkpoint) => {
+                if checkpoint.options != options {
+                    bail!(
+                        "an interrupted export with different options exists at {}; delete it to start over",
+                        checkpoint_path.display()
+                    );
+                }
+
+                // Drop anything written after the last checkpoint
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .open(&staging_path)
+                    .with_context(|| format!("failed to reopen {}", staging_path.display()))?;
+                file.set_len(checkpoint.bytes)?;
+                file.seek(SeekFrom::End(0))?;
+                (checkpoint, file, true)
+            }
+            None => {
+                let mut file = File::create(&staging_path)
+                    .with_context(|| format!("failed to create {}", staging_path.display()))?;
+                if options.format == ExportFormat::Csv {
+                    let mut header = csv::Writer::from_writer(Vec::new());
+                    header.write_record(options.entity.columns().iter().map(|(name, _)| *name))?;
+                    file.write_all(&header.into_inner()?)?;
+                }
+
+                let checkpoint = Checkpoint {
+                    options,
+                    cursor: None,
+                    complete: false,
+                    rows: 0,
+                    bytes: file.stream_position()?,
+                };
+                (checkpoint, file, false)
+            }
+        };
+
+        let writer = Self {
+            checkpoint,
+            checkpoint_path,
+            staging_path,
+            file,
+            resumed,
+        };
+        writer.save_checkpoint()?;
+        Ok(writer)
+    }
+
+    /// Cursor of the next page to fetch
+    pub(crate) fn cursor(&self) -> Option<String> {
+        self.checkpoint.cursor.clone()
+    }
+
+    pub(crate) fn is_complete(&self) -> bool {
+        self.checkpoint.complete
+    }
+
+    /// Append one page of rows and checkpoint the cursor of the next page
+    pub(crate) fn write_page(
+        &mut self,
+        rows: &[Vec<Value>],
+        next_cursor: Option<String>,
+    ) -> Result<()> {
+        let columns = self.checkpoint.options.entity.columns();
+        let mut buffer = Vec::new();
+
+        match self.checkpoint.options.format {
+            ExportFormat::Jsonl | ExportFormat::Parquet => {
+                for row in rows {
+                    let object: Map<String, Value> = columns
+                        .iter()
+                        .map(|(name, _)| name.to_string())
+                        .zip(row.iter().cloned())
+                        .collect();
+                    serde_json::to_writer(&mut buffer, &object)?;
+                    buffer.push(b'\n');
+                }
+            }
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(&mut buffer);
+                for row in rows {
+                    writer.write_record(row.iter().map(csv_field))?;
+                }
+                writer.flush()?;
+            }
+        }
+
+        self.file.write_all(&buffer)?;
+        self.file.sync_data()?;
+
+        self.checkpoint.rows += rows.len();
+        self.checkpoint.bytes += buffer.len() as u64;
+        self.checkpoint.complete = next_cursor.is_none();
+        self.checkpoint.cursor = next_cursor;
+        self.save_checkpoint()
+    }
+
+    /// Finish the export, converting staged Parquet rows, and remove the checkpoint
+    pub(crate) fn finish(self) -> Result<ExportStats> {
+        if self.checkpoint.options.format == ExportFormat::Parquet {
+            drop(self.file);
+            write_parquet(
+                &self.staging_path,
+                &self.checkpoint.options.out,
+                self.checkpoint.options.entity.columns(),
+            )?;
+            fs::remove_file(&self.staging_path)?;
+        }
+        fs::remove_file(&self.checkpoint_path)?;
+
+        Ok(ExportStats {
+            rows: self.checkpoint.rows,
+            resumed: self.resumed,
+        })
+    }
+
+    fn save_checkpoint(&self) -> Result<()> {
+        let tmp = suffixed(&self.checkpoint_path, ".tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&self.checkpoint)?)?;
+        fs::rename(&tmp, &self.checkpoint_path)?;
+        Ok(())
+    }
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn csv_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Convert staged JSON Lines into a Parquet file
+fn write_parquet(staging: &Path, out: &Path, columns: &[(&str, ColumnType)]) -> Result<()> {
+    let schema = Arc::new(Schema::new(
+        columns
+            .iter()
+            .map(|(name, column_type)| {
+                let data_type = match column_type {
+                    ColumnType::Text => DataType::Utf8,
+                    ColumnType::Integer => DataType::Int64,
+                    ColumnType::Boolean => DataType::Boolean,
+                };
+                Field::new(*name, data_type, true)
+            })
+            .collect::<Vec<_>>(),
+    ));
+
+    let mut writer = ArrowWriter::try_new(File::create(out)?, schema.clone(), None)?;
+    let mut rows: Vec<Map<String, Value>> = Vec::with_capacity(PARQUET_BATCH_ROWS);
+    let mut write_batch = |rows: &mut Vec<Map<String, Value>>| -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let arrays: Vec<ArrayRef> = columns
+            .iter()
+            .map(|(name, column_type)| {
+                let values = rows
+                    .iter()
+                    .map(|row| row.get(*name).unwrap_or(&Value::Null));
+                match column_type {
+                    ColumnType::Text => Arc::new(
+                        values
+                            .map(|v| match v {
+                                Value::Null => None,
+                                Value::String(s) => Some(s.clone()),
+                                other => Some(other.to_string()),
+                            })
+                            .collect::<StringArray>(),
+                    ) as ArrayRef,
+                    ColumnType::Integer => {
+                        Arc::new(values.map(|v| v.as_i64()).collect::<Int64Array>()) as ArrayRef
+                    }
+                    ColumnType::Boolean => {
+                        Arc::new(values.map(|v| v.as_bool()).collect::<BooleanArray>()) as ArrayRef
+                    }
+                }
+            })
+            .collect();
+        writer.write(&RecordBatch::try_new(schema.clone(), arrays)?)?;
+        rows.clear();
+        Ok(())
+    };
+
+    for line in BufReader::new(File::open(staging)?).lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        rows.push(serde_json::from_str(&line)?);
+        if rows.len() == PARQUET_BATCH_ROWS {
+            write_batch(&mut rows)?;
+        }
+    }
+    write_batch(&mut rows)?;
+
+    writer.close()?;
+    Ok(())
+}
+
+/// Flat row for a call as returned by `list_calls_extensive`
+pub(crate) fn call_row(call: &Value) -> Vec<Value> {
+    let meta = &call["metaData"];
+    let parties = call["parties"].as_array();
+    let count = |affiliation: &str| {
+        parties
+            .map(|p| p.iter().filter(|p| p["affiliation"] == affiliation).count())
+            .unwrap_or(0)
+    };
+
+    vec![
+        meta["id"].clone(),
+        meta["title"].clone(),
+        meta["started"].clone(),
+        meta["scheduled"].clone(),
+        meta["duration"].clone(),
+        meta["direction"].clone(),
+        meta["scope"].clone(),
+        meta["system"].clone(),
+        meta["media"].clone(),
+        meta["language"].clone(),
+        meta["url"].clone(),
+        meta["primaryUserId"].clone(),
+        meta["workspaceId"].clone(),
+        meta["purpose"].clone(),
+        meta["isPrivate"].clone(),
+        json!(count("Internal")),
+        json!(count("External")),
+        call["content"]["brief"].clone(),
+    ]
+}
+
+/// Flat rows for the participants of a call
+pub(crate) fn party_rows(call: &Value) -> Vec<Vec<Value>> {
+    let call_id = &call["metaData"]["id"];
+    call["parties"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|party| {
+            vec![
+                call_id.clone(),
+                party["id"].clone(),
+                party["name"].clone(),
+                party["emailAddress"].clone(),
+                party["title"].clone(),
+                party["affiliation"].clone(),
+                party["speakerId"].clone(),
+                party["userId"].clone(),
+                party["phoneNumber"].clone(),
+            ]
+        })
+        .collect()
+}
+
+/// Flat rows for each sentence of a call's transcript
+pub(crate) fn sentence_rows(call: &Value, transcript: &Value) -> Vec<Vec<Value>> {
+    let speakers: HashMap<&str, &Value> = call["parties"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|party| party["speakerId"].as_str().map(|id| (id, party)))
+        .collect();
+
+    let mut rows = Vec::new();
+    for call_transcript in transcript["callTranscripts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        for monologue in call_transcript["transcript"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            let speaker = monologue["speakerId"]
+                .as_str()
+                .and_then(|id| speakers.get(id))
+                .copied();
+            for sentence in monologue["sentences"].as_array().into_iter().flatten() {
+                rows.push(vec![
+                    call_transcript["callId"].clone(),
+                    monologue["speakerId"].clone(),
+                    speaker.map(|s| s["name"].clone()).unwrap_or(Value::Null),
+                    speaker
+                        .map(|s| s["affiliation"].clone())
+                        .unwrap_or(Value::Null),
+                    monologue["topic"].clone(),
+                    json!(sentence["start"].as_f64().map(|v| v as i64)),
+                    json!(sentence["end"].as_f64().map(|v| v as i64)),
+                    sentence["text"].clone(),
+                ]);
+            }
+        }
+    }
+    rows
+}
+
+/// Flat row for a user as returned by `list_users`
+pub(crate) fn user_row(user: &Value) -> Vec<Value> {
+    vec![
+        user["id"].clone(),
+        user["emailAddress"].clone(),
+        user["firstName"].clone(),
+        user["lastName"].clone(),
+        user["title"].clone(),
+        user["phoneNumber"].clone(),
+        user["managerId"].clone(),
+        user["active"].clone(),
+        user["created"].clone(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!(
+            "gong-mcp-export-{}-{}-{}",
+            name,
+            std::process::id(),
+            nanos
+        ))
+    }
+
+    fn options(format: ExportFormat, out: &Path) -> ExportOptions {
+        ExportOptions {
+            entity: ExportEntity::Users,
+            format,
+            from_date_time: None,
+            to_date_time: None,
+            out: out.to_path_buf(),
+        }
+    }
+
+    fn user(id: &str, active: bool) -> Vec<Value> {
+        user_row(
+            &json!({"id": id, "emailAddress": format!("{}@example.com", id), "active": active}),
+        )
+    }
+
+    fn call() -> Value {
+        json!({
+            "metaData": {"id": "1", "title": "Kickoff", "duration": 1800, "isPrivate": false},
+            "parties": [
+                {"id": "p1", "name": "Rep", "affiliation": "Internal", "speakerId": "s1"},
+                {"id": "p2", "name": "Buyer", "affiliation": "External", "speakerId": "s2"},
+                {"id": "p3", "name": "Champion", "affiliation": "External"}
+            ],
+            "content": {"brief": "Intro call"}
+        })
+    }
+
+    #[test]
+    fn test_call_and_party_rows() {
+        let row = call_row(&call());
+        assert_eq!(row.len(), ExportEntity::Calls.columns().len());
+        assert_eq!(row[0], "1");
+        assert_eq!(row[15], 1);
+        assert_eq!(row[16], 2);
+        assert_eq!(row[17], "Intro call");
+
+        let rows = party_rows(&call());
+        assert_eq!(rows.len(), 3);
+        assert!(
+            rows.iter()
+                .all(|r| r.len() == ExportEntity::Parties.columns().len())
+        );
+        assert_eq!(rows[1][5], "External");
+    }
+
+    #[test]
+    fn test_sentence_rows_resolve_speakers() {
+        let transcript = json!({"callTranscripts": [{"callId": "1", "transcript": [
+            {"speakerId": "s2", "topic": "Pricing", "sentences": [
+                {"start": 1000, "end": 2000, "text": "What does it cost?"},
+                {"start": 2000, "end": 3000, "text": "Roughly."}
+            ]}
+        ]}]});
+
+        let rows = sentence_rows(&call(), &transcript);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].len(), ExportEntity::Transcripts.columns().len());
+        assert_eq!(rows[0][2], "Buyer");
+        assert_eq!(rows[0][3], "External");
+        assert_eq!(rows[1][5], 2000);
+    }
+
+    #[test]
+    fn test_csv_export_resumes_after_interruption() {
+        let out = temp_path("users.csv");
+
+        let mut writer = ExportWriter::open(options(ExportFormat::Csv, &out)).unwrap();
+        writer
+            .write_page(&[user("u1", true)], Some("page2".to_string()))
+            .unwrap();
+        // Simulate a crash after writing part of the next page
+        writer.file.write_all(b"u2,partial").unwrap();
+        drop(writer);
+
+        let mut writer = ExportWriter::open(options(ExportFormat::Csv, &out)).unwrap();
+        assert_eq!(writer.cursor().as_deref(), Some("page2"));
+        writer.write_page(&[user("u2", false)], None).unwrap();
+        assert!(writer.is_complete());
+        let stats = writer.finish().unwrap();
+
+        assert_eq!(
+            stats,
+            ExportStats {
+                rows: 2,
+                resumed: true
+            }
+        );
+        let contents = fs::read_to_string(&out).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("user_id,email_address"));
+        assert!(lines[2].starts_with("u2,u2@example.com"));
+        assert!(!suffixed(&out, ".export-state.json").exists());
+
+        fs::remove_file(out).unwrap();
+    }
+
+    #[test]
+    fn test_resume_requires_same_options() {
+        let out = temp_path("users.jsonl");
+        let writer = ExportWriter::open(options(ExportFormat::Jsonl, &out)).unwrap();
+        drop(writer);
+
+        let mut other = options(ExportFormat::Jsonl, &out);
+        other.from_date_time = Some("2024-01-01".to_string());
+        assert!(ExportWriter::open(other).is_err());
+
+        fs::remove_file(suffixed(&out, ".export-state.json")).unwrap();
+        fs::remove_file(out).unwrap();
+    }
+
+    #[test]
+    fn test_parquet_export() {
+        let out = temp_path("users.parquet");
+
+        let mut writer = ExportWriter::open(options(ExportFormat::Parquet, &out)).unwrap();
+        writer
+            .write_page(&[user("u1", true), user("u2", false)], None)
+            .unwrap();
+        writer.finish().unwrap();
+
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+            File::open(&out).unwrap(),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0].schema().field(7).data_type(), &DataType::Boolean);
+        assert!(!suffixed(&out, ".partial.jsonl").exists());
+
+        fs::remove_file(out).unwrap();
+    }
+}
@@ -1,9 +1,110 @@
+mod analytics;
+mod api;
+mod args;
+mod cache;
+mod completion;
+pub mod disk_cache;
+mod error;
+pub mod export;
+pub mod logging;
+pub mod mirror;
+mod output;
+mod progress;
+mod prompts;
+mod rate_limit;
+mod retry;
+mod search_index;
+mod sql_query;
+mod trackers;
+mod transcript;
+mod watch;
+pub mod webhook;
+
+use analytics::{CallAnalytics, CallFacts, ParticipantSummary};
+use args::{
+    CallAnalyticsArgs, CompareCallsArgs, GetTranscriptsArgs, LocalTrackersArgs, QueryCallsSqlArgs,
+    SearchCallsArgs, SearchIndexArgs, TranscriptMetricsArgs,
+};
+use cache::{CacheConfig, CacheKind, ResponseCache};
+use completion::CompletionKind;
+use disk_cache::{DiskCache, DiskKind};
+use export::{ExportEntity, ExportOptions, ExportStats, ExportWriter};
 use gong_rs::apis::configuration::Configuration;
 use gong_rs::apis::{calls_api, users_api};
 use gong_rs::models;
-use rmcp::{ErrorData as McpError, RoleServer, ServerHandler, model::*, service::RequestContext};
+use logging::LogTarget;
+use mirror::{CallQuery, Mirror, SyncStats};
+use output::{
+    CallAnalyticsFilters, CallAnalyticsOutput, CallSummary, CompareCallsOutput, ComparedCall,
+    ComparedParticipant, GetTranscriptsOutput, LocalTrackersOutput, Participant,
+    ParticipantChanges, PassageContext, PassageResult, QueryCallsSqlOutput, SearchCallsFilters,
+    SearchCallsOutput, SearchIndexOutput, TrackerCallResult, TranscriptMetricsOutput,
+    TranscriptText,
+};
+use progress::Reporter;
+use prompts::WorkflowPrompt;
+use rate_limit::RateLimiter;
+use retry::RetryConfig;
+use rmcp::{
+    ErrorData as McpError, RoleServer, ServerHandler,
+    model::*,
+    service::{NotificationContext, RequestContext},
+};
+use search_index::IndexQuery;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 use serde_json::json;
+use std::future::Future;
 use std::sync::Arc;
+use trackers::LocalTracker;
+use watch::{PollState, Session, WatchConfig, Watcher};
+use webhook::WebhookLog;
+
+/// Maximum number of calls scanned by a single `run_local_trackers` request
+const MAX_TRACKER_CALLS: usize = 20;
+
+/// Maximum number of calls laid out side by side by `compare_calls`
+const MAX_COMPARE_CALLS: usize = 5;
+
+/// Default and maximum number of transcripts returned by `get_transcripts`
+const DEFAULT_TRANSCRIPT_CALLS: usize = 10;
+const MAX_TRANSCRIPT_CALLS: usize = 50;
+
+/// Default cap on the combined size of transcripts returned by `get_transcripts`
+const DEFAULT_TRANSCRIPT_MAX_TOKENS: usize = 25_000;
+
+/// Number of passages returned by `search_index`
+const DEFAULT_INDEX_RESULTS: usize = 10;
+const MAX_INDEX_RESULTS: usize = 50;
+
+/// Default number of days calls count as recent (GONG_LOOKBACK_DAYS)
+const DEFAULT_LOOKBACK_DAYS: i64 = 30;
+const LOOKBACK_DAYS_ENV: &str = "GONG_LOOKBACK_DAYS";
+
+/// Maximum number of pages of recent calls offered as completions
+const MAX_RECENT_CALL_PAGES: usize = 5;
+
+/// Optional call content to request from `list_calls_extensive`
+///
+/// Parties are always included; everything else increases the response size
+/// and is only requested when a tool needs it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ContentSelection {
+    structure: bool,
+    topics: bool,
+    trackers: bool,
+    brief: bool,
+    /// Outline, highlights, key points and call outcome
+    ai_summary: bool,
+    /// The CRM objects, such as accounts and opportunities, linked to the call
+    crm_context: bool,
+}
+
+impl ContentSelection {
+    fn is_empty(&self) -> bool {
+        !(self.structure || self.topics || self.trackers || self.brief || self.ai_summary)
+    }
+}
 
 /// Gong MCP Server
 ///
@@ -12,6 +113,30 @@ use std::sync::Arc;
 pub struct GongServer {
     // Gong API configuration
     config: Arc<Option<Configuration>>,
+    // Locally defined keyword trackers (GONG_TRACKERS_FILE)
+    trackers: Arc<Vec<LocalTracker>>,
+    // Response cache shared by all clones (one per HTTP session)
+    cache: Arc<ResponseCache>,
+    // Retry policy for transient Gong API failures (GONG_RETRY_*)
+    retry: RetryConfig,
+    // Rate limiter and daily quota shared by all clones
+    limiter: Arc<RateLimiter>,
+    // Optional persistent cache for transcripts and call metadata (GONG_CACHE_DIR)
+    disk_cache: Arc<Option<DiskCache>>,
+    // Local mirror holding the transcript search index (GONG_MIRROR_DB)
+    mirror: Arc<Option<Mirror>>,
+    // Serve calls and transcripts from the mirror instead of the Gong API
+    offline: bool,
+    // Days calls count as recent, for listing and completing them (GONG_LOOKBACK_DAYS)
+    lookback_days: i64,
+    // Resource subscriptions of this client session, see new_session
+    session: Arc<Session>,
+    // Sessions notified about new calls and transcripts, shared by all sessions
+    watcher: Arc<Watcher>,
+    // Poll interval for change notifications (GONG_WATCH_INTERVAL_SECS)
+    watch: WatchConfig,
+    // Calls received by the webhook endpoint, when it is enabled (GONG_WEBHOOK_PUBLIC_KEY)
+    webhooks: Arc<Option<WebhookLog>>,
 }
 
 impl GongServer {
@@ -35,7 +160,288 @@ impl GongServer {
 
         Self {
             config: Arc::new(config),
+            trackers: Arc::new(trackers::load_trackers_from_env()),
+            cache: Arc::new(ResponseCache::new(CacheConfig::from_env())),
+            retry: RetryConfig::from_env(),
+            limiter: Arc::new(RateLimiter::from_env()),
+            disk_cache: Arc::new(DiskCache::from_env()),
+            mirror: Arc::new(None),
+            offline: false,
+            lookback_days: lookback_days_from_env(),
+            session: Arc::new(Session::default()),
+            watcher: Arc::new(Watcher::default()),
+            watch: WatchConfig::from_env(),
+            webhooks: Arc::new(None),
+        }
+    }
+
+    /// A server for a new client session
+    ///
+    /// Sessions share caches, limits and the change poller, but each has
+    /// its own resource subscriptions.
+    pub fn new_session(&self) -> Self {
+        Self {
+            session: Arc::new(Session::default()),
+            ..self.clone()
+        }
+    }
+
+    /// Use the given persistent cache instead of the one configured by `GONG_CACHE_DIR`
+    pub fn with_disk_cache(mut self, disk_cache: DiskCache) -> Self {
+        self.disk_cache = Arc::new(Some(disk_cache));
+        self
+    }
+
+    /// Use a local mirror for `search_index`, and for all calls and transcripts when `offline`
+    pub fn with_mirror(mut self, mirror: Mirror, offline: bool) -> Self {
+        self.mirror = Arc::new(Some(mirror));
+        self.offline = offline;
+        self
+    }
+
+    /// Accept calls from the webhook endpoint, see `webhook::router`
+    ///
+    /// Enables subscriptions even when polling is disabled.
+    pub fn with_webhooks(mut self) -> Self {
+        self.webhooks = Arc::new(Some(WebhookLog::default()));
+        self
+    }
+
+    /// Mirror calls started since `since` (or since the last sync) and their transcripts
+    ///
+    /// The first sync of a database needs `since`; later runs continue from the
+    /// latest `started` timestamp already mirrored unless `since` is given, e.g.
+    /// to backfill older calls. Transcripts missing from
+    /// calls of the last week are fetched again, since Gong may not have
+    /// processed them during the previous run.
+    pub async fn sync_mirror(
+        &self,
+        mirror: &Mirror,
+        since: Option<&str>,
+    ) -> anyhow::Result<SyncStats> {
+        if self.config.is_none() {
+            anyhow::bail!(
+                "Gong API is not configured. Please set GONG_BASE_URL, GONG_ACCESS_KEY, and GONG_ACCESS_KEY_SECRET environment variables."
+            );
+        }
+
+        let since = since
+            .map(|s| {
+                mirror::normalize_timestamp(s).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "invalid --since '{}', expected a date or RFC 3339 timestamp",
+                        s
+                    )
+                })
+            })
+            .transpose()?;
+        let from_date_time = match since {
+            Some(since) => since,
+            None => mirror.high_water_mark()?.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "{} has not been synced before, pass --since",
+                    mirror.path().display()
+                )
+            })?,
+        };
+        tracing::info!("Syncing calls started since {}", from_date_time);
+
+        let mut stats = SyncStats::default();
+        let mut latest: Option<String> = None;
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = self
+                ._fetch_calls_with_filter(
+                    Some(from_date_time.clone()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    cursor.take(),
+                    ContentSelection {
+                        structure: true,
+                        topics: true,
+                        trackers: true,
+                        brief: true,
+                        ai_summary: true,
+                        crm_context: true,
+                    },
+                )
+                .await?;
+
+            let calls = serde_json::to_value(&page)?["calls"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            if let Some(started) = mirror.upsert_calls(&calls)? {
+                latest = latest.max(Some(started));
+            }
+            stats.calls += calls.len();
+            tracing::info!("Synced {} calls", stats.calls);
+
+            cursor = page.records.as_ref().and_then(|r| r.cursor.clone());
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        let week_ago = (chrono::Utc::now() - chrono::Duration::days(7))
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let pending = mirror.calls_without_transcript(&from_date_time.clone().min(week_ago))?;
+        for call_ids in pending.chunks(100) {
+            let filter = models::CallsFilter {
+                from_date_time: None,
+                to_date_time: None,
+                workspace_id: None,
+                call_ids: Some(call_ids.to_vec()),
+            };
+
+            let mut cursor: Option<String> = None;
+            loop {
+                let page = match self
+                    ._fetch_transcripts_page(filter.clone(), cursor.take())
+                    .await
+                {
+                    Ok(page) => page,
+                    // None of the calls in this batch has a transcript yet
+                    Err(e) if e.code == ErrorCode::RESOURCE_NOT_FOUND => break,
+                    Err(e) => return Err(e.into()),
+                };
+
+                for (call_id, transcript) in
+                    mirror::split_transcripts(&serde_json::to_value(&page)?)
+                {
+                    mirror.upsert_transcript(&call_id, &transcript)?;
+                    stats.transcripts += 1;
+                }
+
+                cursor = page.records.as_ref().and_then(|r| r.cursor.clone());
+                if cursor.is_none() {
+                    break;
+                }
+            }
+            tracing::info!("Synced {} transcripts", stats.transcripts);
+        }
+
+        if let Some(latest) = &latest {
+            mirror.advance_high_water_mark(latest)?;
+        }
+        stats.high_water_mark = mirror.high_water_mark()?;
+        Ok(stats)
+    }
+
+    /// Export calls, parties, transcript sentences or users to a flat file.
+    ///
+    /// Rerunning an interrupted export with the same options resumes it from
+    /// the last completed page.
+    pub async fn export(&self, options: ExportOptions) -> anyhow::Result<ExportStats> {
+        if !self._is_configured() {
+            anyhow::bail!(
+                "Gong API is not configured. Please set GONG_BASE_URL, GONG_ACCESS_KEY, and GONG_ACCESS_KEY_SECRET environment variables."
+            );
+        }
+
+        let date = |value: &Option<String>, flag: &str| {
+            value
+                .as_deref()
+                .map(|s| {
+                    mirror::normalize_timestamp(s).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "invalid {} '{}', expected a date or RFC 3339 timestamp",
+                            flag,
+                            s
+                        )
+                    })
+                })
+                .transpose()
+        };
+        let from_date_time = date(&options.from_date_time, "--from")?;
+        let to_date_time = date(&options.to_date_time, "--to")?;
+        let entity = options.entity;
+
+        let mut writer = ExportWriter::open(options)?;
+        while !writer.is_complete() {
+            let cursor = writer.cursor();
+
+            if entity == ExportEntity::Users {
+                let config =
+                    self.config.as_ref().as_ref().ok_or_else(|| {
+                        anyhow::anyhow!("users cannot be exported in offline mode")
+                    })?;
+                let key = ResponseCache::key(
+                    "users",
+                    &json!({"cursor": cursor, "includeAvatars": false}),
+                );
+                let params = users_api::ListUsersParams {
+                    cursor,
+                    include_avatars: Some(false),
+                };
+                let page = self
+                    ._cached(CacheKind::Users, key, async {
+                        retry::send(&self.retry, &self.limiter, || {
+                            api::list_users(config, params.clone())
+                        })
+                        .await
+                        .map_err(|failure| error::api_error(failure, "not_found", json!({})))
+                    })
+                    .await?;
+
+                let page = serde_json::to_value(&page)?;
+                let rows: Vec<_> = page["users"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(export::user_row)
+                    .collect();
+                writer.write_page(&rows, page["records"]["cursor"].as_str().map(String::from))?;
+                continue;
+            }
+
+            let page = self
+                ._fetch_calls_with_filter(
+                    from_date_time.clone(),
+                    to_date_time.clone(),
+                    None,
+                    None,
+                    None,
+                    cursor,
+                    ContentSelection {
+                        brief: entity == ExportEntity::Calls,
+                        ..ContentSelection::default()
+                    },
+                )
+                .await?;
+            let next_cursor = page.records.as_ref().and_then(|r| r.cursor.clone());
+            let calls = serde_json::to_value(&page)?["calls"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+
+            let mut rows = Vec::new();
+            for call in &calls {
+                match entity {
+                    ExportEntity::Calls => rows.push(export::call_row(call)),
+                    ExportEntity::Parties => rows.extend(export::party_rows(call)),
+                    ExportEntity::Transcripts => {
+                        let Some(call_id) = call["metaData"]["id"].as_str() else {
+                            continue;
+                        };
+                        let transcript = match self._fetch_transcript(call_id).await {
+                            Ok(data) => serde_json::to_value(&data)?,
+                            // The call has not been transcribed
+                            Err(e) if e.code == ErrorCode::RESOURCE_NOT_FOUND => continue,
+                            Err(e) => return Err(e.into()),
+                        };
+                        rows.extend(export::sentence_rows(call, &transcript));
+                    }
+                    ExportEntity::Users => unreachable!("users are exported above"),
+                }
+            }
+            writer.write_page(&rows, next_cursor)?;
+            tracing::info!("Exported {} calls", calls.len());
         }
+
+        writer.finish()
     }
 
     fn _create_resource(&self, uri: &str, name: &str, description: &str) -> Resource {
@@ -52,7 +458,525 @@ impl GongServer {
     }
 
     fn _is_configured(&self) -> bool {
-        self.config.is_some()
+        self.config.is_some() || self._offline_mirror().is_some()
+    }
+
+    /// Fail with `not_configured` unless the Gong API or an offline mirror is set up
+    fn require_configured(&self) -> Result<(), McpError> {
+        if self._is_configured() {
+            return Ok(());
+        }
+        Err(McpError::invalid_request(
+            "not_configured",
+            Some(json!({
+                "message": "Gong API is not configured. Please set GONG_BASE_URL, GONG_ACCESS_KEY, and GONG_ACCESS_KEY_SECRET environment variables.",
+                "required_env_vars": ["GONG_BASE_URL", "GONG_ACCESS_KEY", "GONG_ACCESS_KEY_SECRET"]
+            })),
+        ))
+    }
+
+    fn _offline_mirror(&self) -> Option<&Mirror> {
+        self.mirror.as_ref().as_ref().filter(|_| self.offline)
+    }
+
+    fn _mirror_error(e: anyhow::Error) -> McpError {
+        McpError::internal_error("mirror_error", Some(json!({"error": format!("{:#}", e)})))
+    }
+
+    /// Run a query against the local mirror on a blocking thread
+    ///
+    /// SQLite calls block, and a search over a large mirror would otherwise
+    /// hold up an async worker serving other sessions.
+    async fn _query_mirror<T, F>(&self, query: F) -> anyhow::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Mirror) -> anyhow::Result<T> + Send + 'static,
+    {
+        let mirror = self.mirror.clone();
+        tokio::task::spawn_blocking(move || match mirror.as_ref() {
+            Some(mirror) => query(mirror),
+            None => Err(anyhow::anyhow!("no local mirror is configured")),
+        })
+        .await?
+    }
+
+    /// Run an operation on the disk cache on a blocking thread
+    ///
+    /// Returns `None` when no disk cache is configured.
+    async fn _with_disk_cache<T, F>(&self, operation: F) -> Option<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DiskCache) -> T + Send + 'static,
+    {
+        self.disk_cache.as_ref().as_ref()?;
+        let disk_cache = self.disk_cache.clone();
+        tokio::task::spawn_blocking(move || disk_cache.as_ref().as_ref().map(operation))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Disk cache operation failed: {}", e);
+                None
+            })
+    }
+
+    /// Serve a Gong API response from the cache, fetching and storing it on a miss
+    async fn _cached<T, F>(&self, kind: CacheKind, key: String, fetch: F) -> Result<T, McpError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, McpError>>,
+    {
+        self._cached_if(kind, key, |_| true, fetch).await
+    }
+
+    /// Like [`Self::_cached`], but only store responses `keep` accepts
+    async fn _cached_if<T, F>(
+        &self,
+        kind: CacheKind,
+        key: String,
+        keep: impl FnOnce(&T) -> bool,
+        fetch: F,
+    ) -> Result<T, McpError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, McpError>>,
+    {
+        if let Some(value) = self.cache.get(kind, &key) {
+            tracing::debug!("Cache hit for {}", key);
+            return Ok(value);
+        }
+
+        tracing::debug!("Fetching {} from the Gong API", key);
+        let value = fetch.await?;
+        if keep(&value) {
+            self.cache.insert(kind, key, &value);
+        }
+        Ok(value)
+    }
+
+    /// Run a request handler with the request's cancellation token, log target
+    /// and progress reporter
+    async fn _in_request<F: Future>(
+        &self,
+        context: &RequestContext<RoleServer>,
+        future: F,
+    ) -> F::Output {
+        let target = LogTarget {
+            peer: context.peer.clone(),
+            session: self.session.clone(),
+        };
+        let reporter = context
+            .meta
+            .get_progress_token()
+            .map(|token| Reporter::new(context.peer.clone(), token));
+        let future = retry::with_cancellation(context.ct.clone(), future);
+        logging::with_target(target, progress::with_reporter(reporter, future)).await
+    }
+
+    fn _capabilities(&self) -> ServerCapabilities {
+        let mut capabilities = ServerCapabilities::builder()
+            .enable_completions()
+            .enable_logging()
+            .enable_prompts()
+            .enable_resources()
+            .enable_tools()
+            .build();
+        // Subscriptions and list changes rely on the change poller or webhooks
+        if self._notifications_enabled()
+            && let Some(resources) = capabilities.resources.as_mut()
+        {
+            resources.subscribe = Some(true);
+            resources.list_changed = Some(true);
+        }
+        capabilities
+    }
+
+    /// The tools offered once the Gong API is configured
+    fn _tools() -> Vec<Tool> {
+        vec![
+            Tool::new(
+                "search_calls",
+                "Search Gong calls with flexible filters. Returns basic call metadata (id, title, started, duration, \
+                 direction, parties, url) by default. Use include_structure to add call agenda data. \
+                 Supports pagination for large result sets - use limit to reduce response size. \
+                 All parameters are optional - returns recent calls if no filters provided.",
+                args::schema::<SearchCallsArgs>(),
+            )
+            .with_output_schema::<SearchCallsOutput>()
+            .annotate(read_only_tool("Search Calls", true)),
+            Tool::new(
+                "call_analytics",
+                "Aggregate call volume and duration metrics over a date range. Returns call counts, total and \
+                 average duration (seconds), inbound/outbound split and internal-only vs customer-facing counts, \
+                 overall, per time bucket (day, week or month) and per primary user. Pages through all calls \
+                 in the range up to max_pages.",
+                args::schema::<CallAnalyticsArgs>(),
+            )
+            .with_output_schema::<CallAnalyticsOutput>()
+            .annotate(read_only_tool("Call Analytics", true)),
+            Tool::new(
+                "transcript_metrics",
+                "Compute coaching metrics for a call from its transcript: per-speaker talk time and share, \
+                 internal vs external talk ratio, longest monologue, questions asked by each side and \
+                 speaker switches per minute. Works even when Gong interaction stats are unavailable.",
+                args::schema::<TranscriptMetricsArgs>(),
+            )
+            .with_output_schema::<TranscriptMetricsOutput>()
+            .annotate(read_only_tool("Transcript Metrics", true)),
+            Tool::new(
+                "run_local_trackers",
+                "Run the server's locally configured keyword trackers (phrases or regexes, optionally limited to \
+                 internal or external speakers) against call transcripts. Returns every hit with tracker name, \
+                 timestamp, speaker and sentence, plus per-tracker counts for each call.",
+                args::schema::<LocalTrackersArgs>(),
+            )
+            .with_output_schema::<LocalTrackersOutput>()
+            .annotate(read_only_tool("Run Local Trackers", true)),
+            Tool::new(
+                "compare_calls",
+                "Compare 2-5 calls side by side: metadata, durations, participants (including who joined or left \
+                 since the previous call), transcript talk ratios and questions, Gong topics and trackers, and \
+                 call briefs. Useful for contrasting a rep's successive meetings with the same prospect.",
+                args::schema::<CompareCallsArgs>(),
+            )
+            .with_output_schema::<CompareCallsOutput>()
+            .annotate(read_only_tool("Compare Calls", true)),
+            Tool::new(
+                "get_transcripts",
+                "Retrieve transcripts for several calls at once, either by call_ids or with the same filters as \
+                 search_calls. Transcripts are fetched in batched requests and returned in a compact text format \
+                 (one line per speaker turn with timestamp, name and affiliation) with a token estimate per call. \
+                 The combined size is capped by max_tokens; transcripts that don't fit, even a single long one, \
+                 are listed in omittedCallIds.",
+                args::schema::<GetTranscriptsArgs>(),
+            )
+            .with_output_schema::<GetTranscriptsOutput>()
+            .annotate(read_only_tool("Get Transcripts", true)),
+            Tool::new(
+                "search_index",
+                "Ranked full-text search across all transcripts in the local mirror (see 'gong-mcp sync'). \
+                 Returns the best matching passages, ranked by BM25 relevance, with call ID, call title, timestamp, \
+                 speaker and the surrounding passages for context. Use it to find where topics came up across many \
+                 calls, e.g. customers complaining about onboarding over the last year.",
+                args::schema::<SearchIndexArgs>(),
+            )
+            .with_output_schema::<SearchIndexOutput>()
+            .annotate(read_only_tool("Search Transcript Index", false)),
+            Tool::new(
+                "query_calls_sql",
+                "Answer ad hoc questions with SQL over the local mirror of calls, participants and transcript \
+                 sentences (see 'gong-mcp sync'). Runs a single read-only SQLite SELECT with row and time limits \
+                 and returns columns and rows. Read the gong://schema resource first for the tables and examples.",
+                args::schema::<QueryCallsSqlArgs>(),
+            )
+            .with_output_schema::<QueryCallsSqlOutput>()
+            .annotate(read_only_tool("Query Calls with SQL", false)),
+        ]
+    }
+
+    fn _notifications_enabled(&self) -> bool {
+        self.watch.interval.is_some() || self.webhooks.is_some()
+    }
+
+    /// Forget cached data of a call Gong reported a change to, and notify sessions
+    pub(crate) async fn call_changed(&self, call_id: &str) {
+        if let Some(webhooks) = self.webhooks.as_ref() {
+            webhooks.record(call_id);
+        }
+
+        // Listings may now include the call; transcript keys hold the call ID
+        self.cache.invalidate(CacheKind::Calls, None);
+        self.cache
+            .invalidate(CacheKind::Transcripts, Some(&format!("\"{}\"", call_id)));
+        let key = call_id.to_string();
+        self._with_disk_cache(move |disk_cache| {
+            disk_cache.remove(DiskKind::Calls, &key);
+            disk_cache.remove(DiskKind::Transcripts, &key);
+        })
+        .await;
+
+        self.watcher.notify_list_changed().await;
+        self.watcher
+            .notify_updated(&format!("gong://calls/{}/transcript", call_id))
+            .await;
+        self.watcher
+            .notify_updated(&format!("gong://calls/{}", call_id))
+            .await;
+    }
+
+    /// Poll for new calls and transcripts while sessions are connected
+    async fn _watch(self, interval: std::time::Duration) {
+        let mut state = PollState::default();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if !self.watcher.has_sessions() {
+                continue;
+            }
+            if let Err(e) = self._poll_changes(&mut state).await {
+                tracing::warn!("Polling for new calls failed: {:?}", e);
+            }
+        }
+    }
+
+    /// Notify sessions of calls and transcripts that appeared since the last poll
+    async fn _poll_changes(&self, state: &mut PollState) -> Result<(), McpError> {
+        let since = chrono::Utc::now() - chrono::Duration::hours(watch::WATCH_WINDOW_HOURS);
+        let from_date_time = since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+        let mut call_ids = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let page = self
+                ._fetch_calls_with_filter(
+                    Some(from_date_time.clone()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    cursor.take(),
+                    ContentSelection::default(),
+                )
+                .await?;
+            call_ids.extend(
+                page.calls
+                    .iter()
+                    .flatten()
+                    .filter_map(|call| call.meta_data.as_ref().and_then(|m| m.id.clone())),
+            );
+            cursor = page.records.as_ref().and_then(|r| r.cursor.clone());
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        let new_calls = state.new_calls(call_ids);
+        if !new_calls.is_empty() {
+            tracing::info!("Found {} new calls", new_calls.len());
+            self.watcher.notify_list_changed().await;
+        }
+
+        // Transcripts are only checked for calls a session subscribed to
+        for call_id in state.awaited_transcripts(&self.watcher.subscriptions()) {
+            // An earlier read may have cached the response from before Gong
+            // processed the call; ask Gong again
+            self.cache
+                .invalidate(CacheKind::Transcripts, Some(&format!("\"{}\"", call_id)));
+            let available = match self._fetch_transcript(&call_id).await {
+                Ok(data) => transcript::flatten_transcripts(&data)
+                    .iter()
+                    .any(|(_, sentences)| !sentences.is_empty()),
+                Err(e) => {
+                    tracing::debug!("No transcript for call {} yet: {:?}", call_id, e);
+                    false
+                }
+            };
+            if state.transcript_checked(&call_id, available) {
+                tracing::info!("Transcript of call {} is available", call_id);
+                self.watcher
+                    .notify_updated(&format!("gong://calls/{}/transcript", call_id))
+                    .await;
+                self.watcher
+                    .notify_updated(&format!("gong://calls/{}", call_id))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    /// All users of the Gong account, as returned by the API
+    async fn _fetch_users(&self) -> Result<Vec<serde_json::Value>, McpError> {
+        let config = self
+            .config
+            .as_ref()
+            .as_ref()
+            .ok_or_else(|| McpError::invalid_request("not_configured", None))?;
+
+        let mut users = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let key =
+                ResponseCache::key("users", &json!({"cursor": cursor, "includeAvatars": false}));
+            let params = users_api::ListUsersParams {
+                cursor: cursor.take(),
+                include_avatars: Some(false),
+            };
+            let page = self
+                ._cached(CacheKind::Users, key, async {
+                    retry::send(&self.retry, &self.limiter, || {
+                        api::list_users(config, params.clone())
+                    })
+                    .await
+                    .map_err(|failure| error::api_error(failure, "not_found", json!({})))
+                })
+                .await?;
+
+            let page = serde_json::to_value(&page).map_err(|e| {
+                McpError::internal_error(
+                    "serialization_error",
+                    Some(json!({"error": e.to_string()})),
+                )
+            })?;
+            users.extend(page["users"].as_array().into_iter().flatten().cloned());
+            cursor = page["records"]["cursor"].as_str().map(String::from);
+            if cursor.is_none() {
+                return Ok(users);
+            }
+        }
+    }
+
+    /// Start of the lookback period
+    ///
+    /// Starting at midnight keeps the request, and with it the cache key and
+    /// Gong's cursors, the same all day.
+    fn _lookback_start(&self) -> String {
+        (chrono::Utc::now() - chrono::Duration::days(self.lookback_days))
+            .date_naive()
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }
+
+    /// One page of the calls of the lookback period, in Gong's order
+    async fn _recent_calls_page(
+        &self,
+        workspace_id: Option<String>,
+        cursor: Option<String>,
+    ) -> Result<models::Calls, McpError> {
+        self._fetch_calls_with_filter(
+            Some(self._lookback_start()),
+            None,
+            workspace_id,
+            None,
+            None,
+            cursor,
+            ContentSelection::default(),
+        )
+        .await
+    }
+
+    /// Calls of the lookback period linked to a CRM opportunity, newest first
+    async fn _opportunity_calls(&self, opportunity_id: &str) -> Result<Vec<String>, McpError> {
+        let mut calls = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_RECENT_CALL_PAGES {
+            let page = self
+                ._fetch_calls_with_filter(
+                    Some(self._lookback_start()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    cursor.take(),
+                    ContentSelection {
+                        crm_context: true,
+                        ..ContentSelection::default()
+                    },
+                )
+                .await?;
+            cursor = page.records.as_ref().and_then(|r| r.cursor.clone());
+            let page = serde_json::to_value(&page).map_err(|e| {
+                McpError::internal_error(
+                    "serialization_error",
+                    Some(json!({"error": e.to_string()})),
+                )
+            })?;
+            calls.extend(page["calls"].as_array().into_iter().flatten().cloned());
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(prompts::opportunity_calls(&calls, opportunity_id))
+    }
+
+    /// Calls of the lookback period, newest first
+    async fn _recent_calls(
+        &self,
+        workspace_id: Option<String>,
+    ) -> Result<Vec<serde_json::Value>, McpError> {
+        let mut calls = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_RECENT_CALL_PAGES {
+            let page = self
+                ._recent_calls_page(workspace_id.clone(), cursor.take())
+                .await?;
+            cursor = page.records.as_ref().and_then(|r| r.cursor.clone());
+            let page = serde_json::to_value(&page).map_err(|e| {
+                McpError::internal_error(
+                    "serialization_error",
+                    Some(json!({"error": e.to_string()})),
+                )
+            })?;
+            calls.extend(page["calls"].as_array().into_iter().flatten().cloned());
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        calls.sort_by_cached_key(|call| {
+            std::cmp::Reverse(
+                call["metaData"]["started"]
+                    .as_str()
+                    .and_then(mirror::normalize_timestamp),
+            )
+        });
+        Ok(calls)
+    }
+
+    /// The user `user_id`, formatted like the entries of `gong://users`
+    async fn _read_user(&self, user_id: &str) -> Result<serde_json::Value, McpError> {
+        let users = self._fetch_users().await?;
+        let user = users
+            .iter()
+            .find(|user| user["id"].as_str() == Some(user_id))
+            .ok_or_else(|| {
+                McpError::resource_not_found(
+                    "user_not_found",
+                    Some(json!({
+                        "userId": user_id,
+                        "message": "No Gong user with this ID"
+                    })),
+                )
+            })?;
+
+        Ok(json!({
+            "id": user["id"],
+            "email": user["emailAddress"],
+            "firstName": user["firstName"],
+            "lastName": user["lastName"],
+            "title": user["title"],
+            "managerId": user["managerId"],
+            "active": user["active"].as_bool().unwrap_or(false),
+        }))
+    }
+
+    /// Recent calls of the workspace `workspace_id`, newest first
+    async fn _read_workspace_calls(
+        &self,
+        workspace_id: &str,
+    ) -> Result<serde_json::Value, McpError> {
+        let calls: Vec<serde_json::Value> = self
+            ._recent_calls(Some(workspace_id.to_string()))
+            .await?
+            .iter()
+            .map(|call| {
+                let meta = &call["metaData"];
+                json!({
+                    "id": meta["id"],
+                    "title": meta["title"],
+                    "started": meta["started"],
+                    "duration": meta["duration"],
+                    "primaryUserId": meta["primaryUserId"],
+                    "url": meta["url"],
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "workspaceId": workspace_id,
+            "calls": calls,
+            "count": calls.len(),
+            "days": self.lookback_days,
+        }))
     }
 
     /// Fetch list of calls from Gong API with optional filters and cursor for pagination
@@ -64,69 +988,88 @@ impl GongServer {
         call_ids: Option<Vec<String>>,
         primary_user_ids: Option<Vec<String>>,
         cursor: Option<String>,
-        include_structure: bool,
+        content: ContentSelection,
     ) -> Result<models::Calls, McpError> {
+        // The mirror stores all call content, so the selection doesn't apply offline
+        if self._offline_mirror().is_some() {
+            let query = CallQuery {
+                from_date_time,
+                to_date_time,
+                workspace_id,
+                call_ids,
+                primary_user_ids,
+            };
+            let page = self
+                ._query_mirror(move |mirror| mirror.search_calls(&query, cursor.as_deref()))
+                .await
+                .map_err(Self::_mirror_error)?;
+            return serde_json::from_value(page).map_err(|e| Self::_mirror_error(e.into()));
+        }
+
         let config = self
             .config
             .as_ref()
             .as_ref()
             .ok_or_else(|| McpError::invalid_request("not_configured", None))?;
 
+        let request =
+            models::PublicApiBaseRequestWithDataV2CallsRequestFilterWithOwnersContentSelector {
+                cursor,
+                filter: Box::new(models::CallsRequestFilterWithOwners {
+                    from_date_time,
+                    to_date_time,
+                    workspace_id,
+                    call_ids,
+                    primary_user_ids,
+                }),
+                content_selector: Some(Box::new(models::ContentSelector {
+                    context: content
+                        .crm_context
+                        .then_some(models::content_selector::Context::Extended),
+                    context_timing: None,
+                    exposed_fields: Some(Box::new(models::ExposedFields {
+                        collaboration: None,
+                        content: if content.is_empty() {
+                            None
+                        } else {
+                            Some(Box::new(models::CallContent {
+                                structure: content.structure.then_some(true),
+                                topics: content.topics.then_some(true),
+                                trackers: content.trackers.then_some(true),
+                                tracker_occurrences: None,
+                                points_of_interest: None,
+                                brief: content.brief.then_some(true),
+                                outline: content.ai_summary.then_some(true),
+                                highlights: content.ai_summary.then_some(true),
+                                call_outcome: content.ai_summary.then_some(true),
+                                key_points: content.ai_summary.then_some(true),
+                            }))
+                        },
+                        parties: Some(true),
+                        interaction: None,
+                        media: None,
+                    })),
+                })),
+            };
+
+        let key = ResponseCache::key("calls/extensive", &request);
         let params = calls_api::ListCallsExtensiveParams {
             public_api_base_request_with_data_v2_calls_request_filter_with_owners_content_selector:
-                models::PublicApiBaseRequestWithDataV2CallsRequestFilterWithOwnersContentSelector {
-                    cursor,
-                    filter: Box::new(models::CallsRequestFilterWithOwners {
-                        from_date_time,
-                        to_date_time,
-                        workspace_id,
-                        call_ids,
-                        primary_user_ids,
-                    }),
-                    content_selector: Some(Box::new(models::ContentSelector {
-                        context: None,
-                        context_timing: None,
-                        exposed_fields: Some(Box::new(models::ExposedFields {
-                            collaboration: None,
-                            content: if include_structure {
-                                Some(Box::new(models::CallContent {
-                                    structure: Some(true),
-                                    topics: None,
-                                    trackers: None,
-                                    tracker_occurrences: None,
-                                    points_of_interest: None,
-                                    brief: None,
-                                    outline: None,
-                                    highlights: None,
-                                    call_outcome: None,
-                                    key_points: None,
-                                }))
-                            } else {
-                                None
-                            },
-                            parties: Some(true),
-                            interaction: None,
-                            media: None,
-                        })),
-                    })),
-                },
+                request,
         };
 
-        calls_api::list_calls_extensive(config, params)
-            .await
-            .map_err(|e| {
-                McpError::internal_error("api_error", Some(json!({"error": e.to_string()})))
+        self._cached(CacheKind::Calls, key, async {
+            retry::send(&self.retry, &self.limiter, || {
+                api::list_calls_extensive(config, params.clone())
             })
+            .await
+            .map_err(|failure| error::api_error(failure, "not_found", json!({})))
+        })
+        .await
     }
 
     /// Fetch transcript for a specific call by ID
     async fn _fetch_transcript(&self, call_id: &str) -> Result<models::CallTranscripts, McpError> {
-        let config = self
-            .config
-            .as_ref()
-            .as_ref()
-            .ok_or_else(|| McpError::invalid_request("not_configured", None))?;
-
         let filter = models::CallsFilter {
             from_date_time: None,
             to_date_time: None,
@@ -134,215 +1077,1105 @@ impl GongServer {
             call_ids: Some(vec![call_id.to_string()]),
         };
 
-        let params = calls_api::GetCallTranscriptsParams {
-            public_api_base_request_v2_calls_filter: models::PublicApiBaseRequestV2CallsFilter {
-                cursor: None,
-                filter: Box::new(filter),
-            },
+        let key = call_id.to_string();
+        if let Some(data) = self
+            ._with_disk_cache(move |disk_cache| disk_cache.get(DiskKind::Transcripts, &key))
+            .await
+            .flatten()
+        {
+            tracing::debug!("Disk cache hit for transcript {}", call_id);
+            return Ok(data);
+        }
+
+        let data = self._fetch_transcripts_page(filter, None).await?;
+        self._persist_transcripts(&data).await;
+        Ok(data)
+    }
+
+    /// Store each transcript of a response in the disk cache, one entry per call
+    ///
+    /// Calls without transcript (not yet processed by Gong) are not stored so
+    /// they are fetched again later.
+    async fn _persist_transcripts(&self, data: &models::CallTranscripts) {
+        if self.disk_cache.is_none() {
+            return;
+        }
+        let Ok(response) = serde_json::to_value(data) else {
+            return;
         };
 
-        calls_api::get_call_transcripts(config, params)
-            .await
-            .map_err(|e| {
-                let error_str = e.to_string();
-                if error_str.contains("404") || error_str.contains("not found") {
-                    McpError::resource_not_found(
-                        "call_not_found",
-                        Some(json!({"callId": call_id, "error": error_str})),
-                    )
-                } else {
-                    McpError::internal_error("api_error", Some(json!({"error": error_str})))
-                }
-            })
+        self._with_disk_cache(move |disk_cache| {
+            for (call_id, transcript) in mirror::split_transcripts(&response) {
+                disk_cache.put(DiskKind::Transcripts, &call_id, &transcript);
+            }
+        })
+        .await;
     }
 
-    /// Fetch metadata for a specific call by ID
-    async fn _fetch_call(&self, call_id: &str) -> Result<models::SpecificCall, McpError> {
+    /// Fetch one page of transcripts matching a filter
+    async fn _fetch_transcripts_page(
+        &self,
+        filter: models::CallsFilter,
+        cursor: Option<String>,
+    ) -> Result<models::CallTranscripts, McpError> {
+        if self._offline_mirror().is_some() {
+            let query = CallQuery {
+                from_date_time: filter.from_date_time,
+                to_date_time: filter.to_date_time,
+                workspace_id: filter.workspace_id,
+                call_ids: filter.call_ids,
+                primary_user_ids: None,
+            };
+            let page = self
+                ._query_mirror(move |mirror| mirror.transcripts_page(&query, cursor.as_deref()))
+                .await
+                .map_err(Self::_mirror_error)?;
+            return serde_json::from_value(page).map_err(|e| Self::_mirror_error(e.into()));
+        }
+
         let config = self
             .config
             .as_ref()
             .as_ref()
             .ok_or_else(|| McpError::invalid_request("not_configured", None))?;
 
-        let params = calls_api::GetCallParams {
-            id: call_id.to_string(),
+        let call_ids = filter.call_ids.clone();
+        let request = models::PublicApiBaseRequestV2CallsFilter {
+            cursor,
+            filter: Box::new(filter),
         };
 
-        calls_api::get_call(config, params)
-            .await
-            .map_err(|e| {
-                let error_str = e.to_string();
-                if error_str.contains("404") || error_str.contains("not found") {
-                    McpError::resource_not_found(
-                        "call_not_found",
-                        Some(json!({"callId": call_id, "error": error_str})),
-                    )
-                } else {
-                    McpError::internal_error("api_error", Some(json!({"error": error_str})))
-                }
-            })
-    }
-}
-
-impl Default for GongServer {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+        let key = ResponseCache::key("calls/transcript", &request);
+        let params = calls_api::GetCallTranscriptsParams {
+            public_api_base_request_v2_calls_filter: request,
+        };
 
-impl ServerHandler for GongServer {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder()
-                .enable_resources()
-                .enable_tools()
-                .build(),
-            server_info: Implementation {
-                name: "gong-mcp".to_string(),
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                title: None,
-                icons: None,
-                website_url: None,
+        // Like the disk cache, keep calls without transcript out of the cache
+        // so they are fetched again once Gong has processed them
+        self._cached_if(
+            CacheKind::Transcripts,
+            key,
+            transcript::has_all_transcripts,
+            async {
+                retry::send(&self.retry, &self.limiter, || {
+                    api::get_call_transcripts(config, params.clone())
+                })
+                .await
+                .map_err(|failure| {
+                    error::api_error(failure, "call_not_found", json!({"callIds": call_ids}))
+                })
             },
-            instructions: Some(
-                "Gong MCP Server - Access Gong calls via resources. \
-                Configure using environment variables: GONG_BASE_URL, GONG_ACCESS_KEY, GONG_ACCESS_KEY_SECRET."
-                    .to_string(),
-            ),
-        }
+        )
+        .await
     }
 
-    async fn list_resources(
+    /// Fetch transcripts for many calls in batched requests
+    ///
+    /// Follows the transcript cursor until `max_calls` transcripts have been
+    /// collected or no more pages are available. Returns the flattened
+    /// transcripts and whether more were available.
+    async fn _fetch_transcripts(
         &self,
-        _request: Option<PaginatedRequestParam>,
-        _: RequestContext<RoleServer>,
-    ) -> Result<ListResourcesResult, McpError> {
-        if !self._is_configured() {
-            return Ok(ListResourcesResult {
-                resources: vec![self._create_resource(
-                    "gong://status",
-                    "Configuration Status",
-                    "Check if the Gong API is configured correctly",
-                )],
-                next_cursor: None,
-            });
+        filter: models::CallsFilter,
+        max_calls: usize,
+    ) -> Result<(Vec<(String, Vec<transcript::TranscriptSentence>)>, bool), McpError> {
+        let mut filter = filter;
+        let mut transcripts = Vec::new();
+        let mut cursor: Option<String> = None;
+        let total = filter
+            .call_ids
+            .as_ref()
+            .map_or(max_calls, |ids| ids.len().min(max_calls));
+
+        // Serve explicitly requested calls from the disk cache and only ask
+        // Gong for the rest
+        if self.disk_cache.is_some()
+            && let Some(call_ids) = filter.call_ids.take()
+        {
+            let (cached, missing) = self
+                ._with_disk_cache(move |disk_cache| {
+                    let mut cached = Vec::new();
+                    let mut missing = Vec::new();
+                    for call_id in call_ids {
+                        match disk_cache
+                            .get::<models::CallTranscripts>(DiskKind::Transcripts, &call_id)
+                        {
+                            Some(data) => cached.push(data),
+                            None => missing.push(call_id),
+                        }
+                    }
+                    (cached, missing)
+                })
+                .await
+                .unwrap_or_default();
+            for data in &cached {
+                transcripts.extend(transcript::flatten_transcripts(data));
+            }
+
+            if missing.is_empty() || transcripts.len() >= max_calls {
+                let has_more = transcripts.len() > max_calls || !missing.is_empty();
+                transcripts.truncate(max_calls);
+                return Ok((transcripts, has_more));
+            }
+            filter.call_ids = Some(missing);
         }
 
-        Ok(ListResourcesResult {
-            resources: vec![
-                self._create_resource(
-                    "gong://status",
-                    "Configuration Status",
-                    "Check if the Gong API is configured correctly",
-                ),
-                self._create_resource(
-                    "gong://users",
-                    "Gong Users",
-                    "List of users in your Gong workspace",
-                ),
-            ],
-            next_cursor: None,
-        })
+        loop {
+            let first_page = cursor.is_none();
+            let page = match self
+                ._fetch_transcripts_page(filter.clone(), cursor.take())
+                .await
+            {
+                Ok(page) => page,
+                // Gong answers 404 when no call in the filter has a transcript
+                Err(e) if e.code == ErrorCode::RESOURCE_NOT_FOUND && first_page => {
+                    return Ok((transcripts, false));
+                }
+                // Return the transcripts fetched before the client cancelled
+                Err(_) if retry::is_cancelled() => return Ok((transcripts, true)),
+                Err(e) => return Err(e),
+            };
+
+            self._persist_transcripts(&page).await;
+            transcripts.extend(transcript::flatten_transcripts(&page));
+            cursor = page.records.as_ref().and_then(|r| r.cursor.clone());
+            progress::report(
+                transcripts.len(),
+                Some(total),
+                format!("Fetched {} transcripts", transcripts.len()),
+            )
+            .await;
+
+            if transcripts.len() >= max_calls {
+                let has_more = transcripts.len() > max_calls || cursor.is_some();
+                transcripts.truncate(max_calls);
+                return Ok((transcripts, has_more));
+            }
+            if cursor.is_none() {
+                return Ok((transcripts, false));
+            }
+        }
     }
 
-    async fn read_resource(
+    /// Fetch metadata and parties of a specific call by ID
+    ///
+    /// Uses `list_calls_extensive` rather than `get_call`, whose
+    /// `CallBasicData` doesn't include parties.
+    async fn _fetch_call(&self, call_id: &str) -> Result<models::Calls, McpError> {
+        let key = call_id.to_string();
+        if let Some(calls) = self
+            ._with_disk_cache(move |disk_cache| disk_cache.get(DiskKind::Calls, &key))
+            .await
+            .flatten()
+        {
+            tracing::debug!("Disk cache hit for call {}", call_id);
+            return Ok(calls);
+        }
+
+        let calls = self
+            ._fetch_calls_with_filter(
+                None,
+                None,
+                None,
+                Some(vec![call_id.to_string()]),
+                None,
+                None,
+                ContentSelection::default(),
+            )
+            .await?;
+
+        if calls.calls.as_ref().is_some_and(|calls| !calls.is_empty()) {
+            let key = call_id.to_string();
+            let entry = calls.clone();
+            self._with_disk_cache(move |disk_cache| disk_cache.put(DiskKind::Calls, &key, &entry))
+                .await;
+        }
+        Ok(calls)
+    }
+
+    /// Aggregate call metrics over all pages of a date range
+    async fn _call_analytics(
         &self,
-        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
-        _: RequestContext<RoleServer>,
-    ) -> Result<ReadResourceResult, McpError> {
-        match uri.as_str() {
-            "gong://status" => {
-                let status = if self._is_configured() {
-                    let base_url = self
-                        .config
-                        .as_ref()
-                        .as_ref()
-                        .map(|c| c.base_path.as_str())
-                        .unwrap_or("unknown");
-                    json!({
-                        "configured": true,
-                        "base_url": base_url,
-                        "message": "Gong API is configured and ready to use"
-                    })
-                } else {
-                    json!({
-                        "configured": false,
-                        "message": "Gong API is not configured. Please set GONG_BASE_URL, GONG_ACCESS_KEY, and GONG_ACCESS_KEY_SECRET environment variables."
-                    })
-                };
+        arguments: Option<&JsonObject>,
+    ) -> Result<CallToolResult, McpError> {
+        let CallAnalyticsArgs {
+            from_date_time,
+            to_date_time,
+            workspace_id,
+            primary_user_ids,
+            bucket,
+            max_pages,
+        } = args::parse(arguments)?;
+        let from_date_time = String::from(from_date_time);
+        let to_date_time = to_date_time.map(String::from);
+        let max_pages = max_pages.unwrap_or(analytics::DEFAULT_MAX_PAGES);
+
+        let mut analytics = CallAnalytics::new(bucket);
+        let mut cursor: Option<String> = None;
+        let mut pages_fetched = 0;
+        let mut calls_processed = 0;
+        let mut cancelled = false;
+
+        loop {
+            let calls_data = match self
+                ._fetch_calls_with_filter(
+                    Some(from_date_time.clone()),
+                    to_date_time.clone(),
+                    workspace_id.clone(),
+                    None,
+                    primary_user_ids.clone(),
+                    cursor.clone(),
+                    ContentSelection::default(),
+                )
+                .await
+            {
+                Ok(calls_data) => calls_data,
+                // Return what was aggregated before the client cancelled
+                Err(_) if retry::is_cancelled() => {
+                    cancelled = true;
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            pages_fetched += 1;
+
+            for call in calls_data.calls.iter().flatten() {
+                let meta = call.meta_data.as_ref().map(|m| m.as_ref());
+                analytics.add(&CallFacts {
+                    started: meta.and_then(|m| m.started.clone()),
+                    duration: meta.and_then(|m| m.duration).unwrap_or(0),
+                    direction: meta
+                        .and_then(|m| m.direction.as_ref())
+                        .map(|d| format!("{:?}", d)),
+                    primary_user_id: meta.and_then(|m| m.primary_user_id.clone()),
+                    participants: ParticipantSummary::from_parties(call.parties.as_ref()),
+                });
+            }
 
-                Ok(ReadResourceResult {
-                    contents: vec![ResourceContents::text(
-                        serde_json::to_string_pretty(&status).unwrap(),
-                        uri,
-                    )],
-                })
+            cursor = calls_data.records.as_ref().and_then(|r| r.cursor.clone());
+            calls_processed += calls_data.calls.as_ref().map_or(0, Vec::len);
+            let total_calls = calls_data
+                .records
+                .as_ref()
+                .and_then(|r| r.total_records)
+                .map(|total| total as usize);
+            progress::report(
+                calls_processed,
+                total_calls,
+                format!("Fetched page {} ({} calls)", pages_fetched, calls_processed),
+            )
+            .await;
+            if cursor.is_none() || pages_fetched >= max_pages {
+                break;
             }
-            "gong://users" => {
-                if !self._is_configured() {
-                    return Err(McpError::invalid_request(
-                        "not_configured",
+        }
+
+        Ok(output::result(&CallAnalyticsOutput {
+            analytics: analytics.to_json(),
+            pages_fetched,
+            complete: cursor.is_none() && !cancelled,
+            cancelled,
+            filters: CallAnalyticsFilters {
+                from_date_time,
+                to_date_time,
+                workspace_id,
+                primary_user_ids,
+                bucket,
+                max_pages,
+            },
+        }))
+    }
+
+    /// Derive talk-ratio and question metrics for one call from its transcript
+    async fn _transcript_metrics(
+        &self,
+        arguments: Option<&JsonObject>,
+    ) -> Result<CallToolResult, McpError> {
+        let TranscriptMetricsArgs { call_id } = args::parse(arguments)?;
+
+        // Parties are needed to tell internal from external speakers
+        let (calls_data, transcript_data) = tokio::try_join!(
+            self._fetch_calls_with_filter(
+                None,
+                None,
+                None,
+                Some(vec![call_id.clone()]),
+                None,
+                None,
+                ContentSelection::default(),
+            ),
+            self._fetch_transcript(&call_id),
+        )?;
+
+        let parties = calls_data
+            .calls
+            .as_ref()
+            .and_then(|calls| calls.first())
+            .and_then(|call| call.parties.as_ref());
+        let directory = transcript::speaker_directory(parties);
+
+        let sentences = transcript::flatten_transcripts(&transcript_data)
+            .into_iter()
+            .find(|(id, _)| id == &call_id || id.is_empty())
+            .map(|(_, sentences)| sentences)
+            .filter(|sentences| !sentences.is_empty())
+            .ok_or_else(|| {
+                McpError::resource_not_found(
+                    "transcript_not_found",
+                    Some(json!({
+                        "callId": call_id,
+                        "message": "No transcript found for this call"
+                    })),
+                )
+            })?;
+
+        Ok(output::result(&TranscriptMetricsOutput {
+            metrics: transcript::TranscriptMetrics::compute(&sentences).to_json(&directory),
+            call_id,
+        }))
+    }
+
+    /// Evaluate local trackers against the transcripts of a set of calls
+    async fn _run_local_trackers(
+        &self,
+        arguments: Option<&JsonObject>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.trackers.is_empty() {
+            return Err(McpError::invalid_request(
+                "no_local_trackers",
+                Some(json!({
+                    "message": format!(
+                        "No local trackers configured. Point {} to a JSON file with tracker definitions.",
+                        trackers::TRACKERS_FILE_ENV
+                    )
+                })),
+            ));
+        }
+
+        let LocalTrackersArgs {
+            call_ids,
+            trackers: requested,
+        } = args::parse(arguments)?;
+
+        let selected: Vec<&LocalTracker> = match &requested {
+            Some(names) => {
+                let unknown: Vec<&String> = names
+                    .iter()
+                    .filter(|name| !self.trackers.iter().any(|t| &t.name == *name))
+                    .collect();
+                if !unknown.is_empty() {
+                    return Err(McpError::invalid_params(
+                        "unknown_tracker",
                         Some(json!({
-                            "message": "Gong API is not configured. Please set environment variables."
+                            "unknown": unknown,
+                            "available": self.trackers.iter().map(|t| &t.name).collect::<Vec<_>>()
                         })),
                     ));
                 }
+                self.trackers
+                    .iter()
+                    .filter(|t| names.contains(&t.name))
+                    .collect()
+            }
+            None => self.trackers.iter().collect(),
+        };
 
-                // Fetch users from Gong API
-                let config = self
-                    .config
-                    .as_ref()
-                    .as_ref()
-                    .ok_or_else(|| McpError::invalid_request("not_configured", None))?;
+        // One request for the parties of all calls, then one per transcript
+        let calls_data = self
+            ._fetch_calls_with_filter(
+                None,
+                None,
+                None,
+                Some(call_ids.clone()),
+                None,
+                None,
+                ContentSelection::default(),
+            )
+            .await?;
+
+        let mut results = Vec::with_capacity(call_ids.len());
+        let mut total_hits = 0;
+        for call_id in &call_ids {
+            let parties = calls_data
+                .calls
+                .iter()
+                .flatten()
+                .find(|call| {
+                    call.meta_data
+                        .as_ref()
+                        .and_then(|m| m.id.as_ref())
+                        .is_some_and(|id| id == call_id)
+                })
+                .and_then(|call| call.parties.as_ref());
+            let directory = transcript::speaker_directory(parties);
+
+            let transcript_data = match self._fetch_transcript(call_id).await {
+                Ok(transcript_data) => transcript_data,
+                // Return the calls evaluated before the client cancelled
+                Err(_) if retry::is_cancelled() => break,
+                Err(e) => return Err(e),
+            };
+            let sentences = transcript::flatten_transcripts(&transcript_data)
+                .into_iter()
+                .next()
+                .map(|(_, sentences)| sentences)
+                .unwrap_or_default();
+
+            let hits = trackers::run_trackers(&selected, &sentences, &directory);
+            total_hits += hits.len();
+            results.push(TrackerCallResult {
+                call_id: call_id.clone(),
+                has_transcript: !sentences.is_empty(),
+                hits: trackers::hits_to_json(&hits, &directory),
+            });
+            progress::report(
+                results.len(),
+                Some(call_ids.len()),
+                format!("Evaluated call {}", call_id),
+            )
+            .await;
+        }
 
-                let params = users_api::ListUsersParams {
-                    cursor: None,
-                    include_avatars: Some(false),
-                };
+        Ok(output::result(&LocalTrackersOutput {
+            trackers: selected.iter().map(|t| t.name.clone()).collect(),
+            calls: results,
+            total_hits,
+            cancelled: retry::is_cancelled(),
+        }))
+    }
+
+    /// Lay out several calls side by side, fetched in a single batched request
+    async fn _compare_calls(
+        &self,
+        arguments: Option<&JsonObject>,
+    ) -> Result<CallToolResult, McpError> {
+        let call_ids = args::parse::<CompareCallsArgs>(arguments)?.distinct_call_ids();
+
+        let calls_data = self
+            ._fetch_calls_with_filter(
+                None,
+                None,
+                None,
+                Some(call_ids.clone()),
+                None,
+                None,
+                ContentSelection {
+                    topics: true,
+                    trackers: true,
+                    brief: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
 
-                let users_data = users_api::list_users(config, params).await.map_err(|e| {
-                    McpError::internal_error("api_error", Some(json!({"error": e.to_string()})))
-                })?;
+        let mut compared = Vec::with_capacity(call_ids.len());
+        let mut not_found = Vec::new();
+        let mut previous_participants: Option<Vec<String>> = None;
 
-                // Format the users response
-                let formatted_response = if let Some(users) = users_data.users {
-                    let formatted_users: Vec<serde_json::Value> = users
+        for (index, call_id) in call_ids.iter().enumerate() {
+            // Transcript errors are tolerated below, so check for cancellation here
+            if retry::is_cancelled() {
+                break;
+            }
+            progress::report(
+                index,
+                Some(call_ids.len()),
+                format!("Comparing call {}", call_id),
+            )
+            .await;
+            let Some(call) = calls_data.calls.iter().flatten().find(|call| {
+                call.meta_data
+                    .as_ref()
+                    .and_then(|m| m.id.as_ref())
+                    .is_some_and(|id| id == call_id)
+            }) else {
+                not_found.push(call_id.clone());
+                continue;
+            };
+
+            let meta = call.meta_data.as_ref().map(|m| m.as_ref());
+            let content = call.content.as_ref();
+
+            let participants = call
+                .parties
+                .as_ref()
+                .map(|parties| {
+                    parties
                         .iter()
-                        .map(|user| {
-                            json!({
-                                "id": user.id.as_ref().unwrap_or(&String::new()),
-                                "email": user.email_address.as_ref().unwrap_or(&String::new()),
-                                "firstName": user.first_name.as_ref().unwrap_or(&String::new()),
-                                "lastName": user.last_name.as_ref().unwrap_or(&String::new()),
-                                "active": user.active.unwrap_or(false),
-                            })
+                        .map(|party| ComparedParticipant {
+                            name: party.name.clone(),
+                            email_address: party.email_address.clone(),
+                            title: party.title.clone(),
+                            affiliation: party.affiliation.as_ref().map(|a| format!("{:?}", a)),
                         })
-                        .collect();
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            let participant_keys: Vec<String> = call
+                .parties
+                .iter()
+                .flatten()
+                .filter_map(|party| {
+                    analytics::participant_key(party.email_address.as_ref(), party.name.as_ref())
+                })
+                .collect();
+            let participant_changes = previous_participants.as_ref().map(|previous| {
+                let (joined, left) = analytics::participant_changes(previous, &participant_keys);
+                ParticipantChanges { joined, left }
+            });
+            previous_participants = Some(participant_keys);
+
+            // Talk ratios come from the transcript; calls without one are still compared
+            let directory = transcript::speaker_directory(call.parties.as_ref());
+            let conversation = match self._fetch_transcript(call_id).await {
+                Ok(data) => transcript::flatten_transcripts(&data)
+                    .into_iter()
+                    .next()
+                    .map(|(_, sentences)| sentences)
+                    .filter(|sentences| !sentences.is_empty())
+                    .map(|sentences| {
+                        transcript::TranscriptMetrics::compute(&sentences).to_json(&directory)
+                    }),
+                Err(e) => {
+                    tracing::debug!("No transcript for call {}: {:?}", call_id, e);
+                    None
+                }
+            };
+
+            compared.push(ComparedCall {
+                id: call_id.clone(),
+                title: meta.and_then(|m| m.title.clone()),
+                started: meta.and_then(|m| m.started.clone()),
+                duration: meta.and_then(|m| m.duration),
+                direction: meta
+                    .and_then(|m| m.direction.as_ref())
+                    .map(|d| format!("{:?}", d)),
+                primary_user_id: meta.and_then(|m| m.primary_user_id.clone()),
+                url: meta.and_then(|m| m.url.clone()),
+                participants,
+                participant_summary: ParticipantSummary::from_parties(call.parties.as_ref())
+                    .to_json(),
+                participant_changes,
+                talk_ratio: conversation.as_ref().map(|c| c["talkRatio"].clone()),
+                questions: conversation.as_ref().map(|c| c["questions"].clone()),
+                longest_monologue: conversation.as_ref().map(|c| c["longestMonologue"].clone()),
+                topics: content
+                    .and_then(|c| c.topics.as_ref())
+                    .map(|topics| json!(topics)),
+                trackers: content
+                    .and_then(|c| c.trackers.as_ref())
+                    .map(|trackers| json!(trackers)),
+                brief: content
+                    .and_then(|c| c.brief.as_ref())
+                    .map(|brief| json!(brief)),
+            });
+        }
 
-                    json!({
-                        "users": formatted_users,
-                        "count": formatted_users.len(),
-                        "message": format!("Retrieved {} users", formatted_users.len())
-                    })
-                } else {
-                    json!({
-                        "users": [],
-                        "count": 0,
-                        "message": "No users found"
-                    })
-                };
+        Ok(output::result(&CompareCallsOutput {
+            call_ids,
+            calls: compared,
+            not_found,
+            cancelled: retry::is_cancelled(),
+        }))
+    }
+
+    /// Ranked full-text search over transcripts in the local mirror
+    async fn _search_index(
+        &self,
+        arguments: Option<&JsonObject>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.mirror.is_none() {
+            return Err(McpError::invalid_request(
+                "index_unavailable",
+                Some(json!({
+                    "message": "The transcript index lives in the local mirror. Run 'gong-mcp sync' and start the server with --mirror-db or GONG_MIRROR_DB."
+                })),
+            ));
+        }
+
+        let SearchIndexArgs {
+            query,
+            match_all,
+            speaker_affiliation,
+            from_date_time,
+            to_date_time,
+            call_ids,
+            limit,
+        } = args::parse(arguments)?;
+        let text = query.trim();
+
+        // Passages store the call start in UTC, which the parsed bounds already are
+        let query = IndexQuery {
+            text: text.to_string(),
+            match_all,
+            affiliation: speaker_affiliation.map(|a| a.as_str().to_string()),
+            from_date_time: from_date_time.map(String::from),
+            to_date_time: to_date_time.map(String::from),
+            call_ids: call_ids.filter(|ids| !ids.is_empty()),
+            limit: limit.unwrap_or(DEFAULT_INDEX_RESULTS),
+        };
+
+        let hits = self
+            ._query_mirror(move |mirror| mirror.search_passages(&query))
+            .await
+            .map_err(Self::_mirror_error)?;
+        let results: Vec<PassageResult> = hits
+            .into_iter()
+            .map(|hit| PassageResult {
+                timestamp: transcript::format_timestamp(hit.start_ms),
+                affiliation: hit.affiliation.unwrap_or_else(|| "Unknown".to_string()),
+                score: (hit.score * 1000.0).round() / 1000.0,
+                call_id: hit.call_id,
+                call_title: hit.call_title,
+                call_started: hit.call_started,
+                start_ms: hit.start_ms,
+                speaker_id: hit.speaker_id,
+                speaker_name: hit.speaker_name,
+                text: hit.text,
+                context: PassageContext {
+                    before: hit.before,
+                    after: hit.after,
+                },
+            })
+            .collect();
+
+        Ok(output::result(&SearchIndexOutput {
+            query: text.to_string(),
+            count: results.len(),
+            results,
+        }))
+    }
+
+    /// Run a read-only SELECT against the local mirror
+    async fn _query_calls_sql(
+        &self,
+        arguments: Option<&JsonObject>,
+    ) -> Result<CallToolResult, McpError> {
+        if self.mirror.is_none() {
+            return Err(McpError::invalid_request(
+                "mirror_unavailable",
+                Some(json!({
+                    "message": "SQL queries run against the local mirror. Run 'gong-mcp sync' and start the server with --mirror-db or GONG_MIRROR_DB."
+                })),
+            ));
+        }
+
+        let QueryCallsSqlArgs { sql, max_rows } = args::parse(arguments)?;
+        let max_rows = max_rows.unwrap_or(sql_query::DEFAULT_MAX_ROWS);
+
+        let result = self
+            ._query_mirror(move |mirror| mirror.query_sql(&sql, max_rows))
+            .await
+            .map_err(|e| {
+                McpError::invalid_params(
+                    "invalid_query",
+                    Some(json!({
+                        "message": format!("{:#}", e),
+                        "hint": "See the gong://schema resource for tables and columns"
+                    })),
+                )
+            })?;
+
+        Ok(output::result(&QueryCallsSqlOutput {
+            row_count: result.rows.len(),
+            columns: result.columns,
+            rows: result.rows,
+            truncated: result.truncated,
+            elapsed_ms: result.elapsed.as_millis() as u64,
+        }))
+    }
+
+    /// Fetch several transcripts in batched requests and render them compactly
+    async fn _get_transcripts(
+        &self,
+        arguments: Option<&JsonObject>,
+    ) -> Result<CallToolResult, McpError> {
+        let GetTranscriptsArgs {
+            call_ids,
+            from_date_time,
+            to_date_time,
+            workspace_id,
+            primary_user_ids,
+            max_calls,
+            max_tokens,
+        } = args::parse(arguments)?;
+        let call_ids = call_ids.filter(|ids| !ids.is_empty());
+        let from_date_time = from_date_time.map(String::from);
+        let to_date_time = to_date_time.map(String::from);
+        let primary_user_ids = primary_user_ids.filter(|ids| !ids.is_empty());
+        let max_calls = max_calls.unwrap_or(DEFAULT_TRANSCRIPT_CALLS);
+        let max_tokens = max_tokens.unwrap_or(DEFAULT_TRANSCRIPT_MAX_TOKENS);
+
+        // The transcript endpoint cannot filter by host, so resolve those calls first
+        let mut omitted_call_ids: Vec<String> = Vec::new();
+        let call_ids =
+            match primary_user_ids {
+                Some(primary_user_ids) => {
+                    let mut resolved = Vec::new();
+                    let mut cursor: Option<String> = None;
+                    loop {
+                        let calls_data = match self
+                            ._fetch_calls_with_filter(
+                                from_date_time.clone(),
+                                to_date_time.clone(),
+                                workspace_id.clone(),
+                                call_ids.clone(),
+                                Some(primary_user_ids.clone()),
+                                cursor.take(),
+                                ContentSelection::default(),
+                            )
+                            .await
+                        {
+                            Ok(calls_data) => calls_data,
+                            // Fetch transcripts for the calls resolved before the client cancelled
+                            Err(_) if retry::is_cancelled() => break,
+                            Err(e) => return Err(e),
+                        };
+                        resolved.extend(
+                            calls_data.calls.iter().flatten().filter_map(|call| {
+                                call.meta_data.as_ref().and_then(|m| m.id.clone())
+                            }),
+                        );
+                        cursor = calls_data.records.as_ref().and_then(|r| r.cursor.clone());
+                        if cursor.is_none() || resolved.len() > max_calls {
+                            break;
+                        }
+                    }
+                    if resolved.is_empty() {
+                        return Ok(output::result(&GetTranscriptsOutput {
+                            cancelled: retry::is_cancelled(),
+                            ..Default::default()
+                        }));
+                    }
+                    Some(resolved)
+                }
+                None => call_ids,
+            };
+
+        let call_ids = call_ids.map(|mut ids| {
+            if ids.len() > max_calls {
+                omitted_call_ids.extend(ids.split_off(max_calls));
+            }
+            ids
+        });
+
+        let filter = models::CallsFilter {
+            from_date_time: from_date_time.clone(),
+            to_date_time: to_date_time.clone(),
+            workspace_id: workspace_id.clone(),
+            call_ids: call_ids.clone(),
+        };
+        let (transcripts, has_more) = self._fetch_transcripts(filter, max_calls).await?;
 
-                Ok(ReadResourceResult {
-                    contents: vec![ResourceContents::text(
-                        serde_json::to_string_pretty(&formatted_response).unwrap(),
-                        uri,
-                    )],
+        // Speaker names and titles for all returned calls in one batched request
+        let returned_ids: Vec<String> = transcripts.iter().map(|(id, _)| id.clone()).collect();
+        let calls_data = if returned_ids.is_empty() {
+            None
+        } else {
+            match self
+                ._fetch_calls_with_filter(
+                    None,
+                    None,
+                    None,
+                    Some(returned_ids.clone()),
+                    None,
+                    None,
+                    ContentSelection::default(),
+                )
+                .await
+            {
+                Ok(calls_data) => Some(calls_data),
+                // Transcripts fetched before the client cancelled come without speaker names
+                Err(_) if retry::is_cancelled() => None,
+                Err(e) => return Err(e),
+            }
+        };
+        let find_call = |call_id: &str| {
+            calls_data
+                .as_ref()
+                .and_then(|data| data.calls.as_ref())
+                .and_then(|calls| {
+                    calls.iter().find(|call| {
+                        call.meta_data.as_ref().and_then(|m| m.id.as_deref()) == Some(call_id)
+                    })
                 })
+        };
+
+        let mut results = Vec::new();
+        let mut total_tokens = 0;
+        for (call_id, sentences) in &transcripts {
+            let call = find_call(call_id);
+            let directory = transcript::speaker_directory(call.and_then(|c| c.parties.as_ref()));
+            let text = transcript::compact_transcript(sentences, &directory);
+            let tokens = transcript::estimate_tokens(&text);
+
+            if total_tokens + tokens > max_tokens {
+                omitted_call_ids.push(call_id.clone());
+                continue;
             }
-            _ => {
-                // Check if it matches the participants pattern: gong://calls/{callId}/participants
-                if uri.starts_with("gong://calls/") && uri.ends_with("/participants") {
+            total_tokens += tokens;
+
+            let meta = call.and_then(|c| c.meta_data.as_ref()).map(|m| m.as_ref());
+            results.push(TranscriptText {
+                call_id: call_id.clone(),
+                title: meta.and_then(|m| m.title.clone()),
+                started: meta.and_then(|m| m.started.clone()),
+                sentence_count: sentences.len(),
+                token_estimate: tokens,
+                transcript: text,
+            });
+        }
+
+        let mut missing_call_ids: Vec<String> = call_ids
+            .into_iter()
+            .flatten()
+            .filter(|id| !returned_ids.contains(id))
+            .collect();
+        // Calls not fetched before the client cancelled may well have a transcript
+        let cancelled = retry::is_cancelled();
+        if cancelled {
+            omitted_call_ids.append(&mut missing_call_ids);
+        }
+
+        Ok(output::result(&GetTranscriptsOutput {
+            count: results.len(),
+            transcripts: results,
+            total_token_estimate: total_tokens,
+            truncated: has_more || !omitted_call_ids.is_empty(),
+            omitted_call_ids,
+            missing_call_ids,
+            cancelled,
+        }))
+    }
+}
+
+/// Annotations for a tool that only reads Gong data or the local mirror
+///
+/// `open_world` is true for tools calling the Gong API. A tool that writes
+/// must instead be annotated with `read_only(false)` and `destructive(true)`.
+fn read_only_tool(title: &str, open_world: bool) -> ToolAnnotations {
+    ToolAnnotations::with_title(title)
+        .read_only(true)
+        .destructive(false)
+        .idempotent(true)
+        .open_world(open_world)
+}
+
+/// Read the lookback period for recent calls from `GONG_LOOKBACK_DAYS`
+fn lookback_days_from_env() -> i64 {
+    parse_lookback_days(std::env::var(LOOKBACK_DAYS_ENV).ok().as_deref())
+}
+
+/// Parse a lookback period in days, falling back to the default when unset or invalid
+fn parse_lookback_days(value: Option<&str>) -> i64 {
+    let Some(value) = value else {
+        return DEFAULT_LOOKBACK_DAYS;
+    };
+    match value.parse() {
+        Ok(days) if days > 0 => days,
+        _ => {
+            tracing::warn!(
+                "Ignoring invalid value for {}: {}",
+                LOOKBACK_DAYS_ENV,
+                value
+            );
+            DEFAULT_LOOKBACK_DAYS
+        }
+    }
+}
+
+impl Default for GongServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServerHandler for GongServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2025_06_18,
+            capabilities: self._capabilities(),
+            server_info: Implementation {
+                name: "gong-mcp".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                title: None,
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(
+                "Gong MCP Server - Access Gong calls via resources. \
+                Configure using environment variables: GONG_BASE_URL, GONG_ACCESS_KEY, GONG_ACCESS_KEY_SECRET."
+                    .to_string(),
+            ),
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        if !self._is_configured() {
+            return Ok(ListResourcesResult {
+                resources: vec![self._create_resource(
+                    "gong://status",
+                    "Configuration Status",
+                    "Check if the Gong API is configured correctly",
+                )],
+                next_cursor: None,
+            });
+        }
+
+        // The MCP cursor is Gong's cursor for the next page of recent calls;
+        // the static resources come first, on the first page only
+        let cursor = request.and_then(|request| request.cursor);
+        let mut resources = Vec::new();
+        if cursor.is_none() {
+            resources.push(self._create_resource(
+                "gong://status",
+                "Configuration Status",
+                "Check if the Gong API is configured correctly",
+            ));
+            resources.push(self._create_resource(
+                "gong://users",
+                "Gong Users",
+                "List of users in your Gong workspace",
+            ));
+            if self.mirror.is_some() {
+                resources.push(self._create_resource(
+                    "gong://schema",
+                    "Local Mirror Schema",
+                    "Tables, columns and example queries for the query_calls_sql tool",
+                ));
+            }
+        }
+
+        let first_page = cursor.is_none();
+        let page = match self
+            ._in_request(&context, self._recent_calls_page(None, cursor))
+            .await
+        {
+            Ok(page) => page,
+            // gong://status explains what is wrong, so keep it listed
+            Err(e) if first_page => {
+                tracing::warn!("Could not list recent calls: {:?}", e);
+                return Ok(ListResourcesResult {
+                    resources,
+                    next_cursor: None,
+                });
+            }
+            Err(e) => return Err(e),
+        };
+        resources.extend(page.calls.iter().flatten().filter_map(|call| {
+            let meta = call.meta_data.as_ref()?;
+            let id = meta.id.as_ref()?;
+            let title = meta.title.clone().unwrap_or_else(|| "Untitled".to_string());
+            let mut description = format!(
+                "{} call started {}",
+                meta.direction
+                    .as_ref()
+                    .map(|d| format!("{:?}", d))
+                    .unwrap_or_else(|| "Gong".to_string()),
+                meta.started.as_deref().unwrap_or("at an unknown time"),
+            );
+            if let Some(duration) = meta.duration {
+                description.push_str(&format!(", {} min", duration / 60));
+            }
+            if let Some(parties) = call.parties.as_ref() {
+                description.push_str(&format!(", {} participants", parties.len()));
+            }
+
+            Some(
+                RawResource {
+                    uri: format!("gong://calls/{}", id),
+                    name: title.clone(),
+                    title: Some(title),
+                    description: Some(description),
+                    mime_type: Some("application/json".to_string()),
+                    size: None,
+                    icons: None,
+                }
+                .no_annotation(),
+            )
+        }));
+
+        Ok(ListResourcesResult {
+            resources,
+            next_cursor: page.records.as_ref().and_then(|r| r.cursor.clone()),
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        self._in_request(&context, async move {
+            match uri.as_str() {
+                "gong://status" => {
+                    let status = if self._is_configured() {
+                        let base_url = self
+                            .config
+                            .as_ref()
+                            .as_ref()
+                            .map(|c| c.base_path.as_str())
+                            .unwrap_or("unknown");
+                        let message = if self.offline {
+                            "Offline mode: calls and transcripts are served from the local mirror"
+                        } else {
+                            "Gong API is configured and ready to use"
+                        };
+                        let disk_cache = self
+                            ._with_disk_cache(|disk_cache| disk_cache.status())
+                            .await;
+                        json!({
+                            "configured": true,
+                            "base_url": base_url,
+                            "message": message,
+                            "cache": self.cache.status(),
+                            "quota": self.limiter.status(),
+                            "diskCache": disk_cache,
+                            "mirror": self.mirror.as_ref().as_ref().map(|m| {
+                                let mut status = m.status();
+                                status["offline"] = json!(self.offline);
+                                status
+                            }),
+                            "webhooks": self.webhooks.as_ref().as_ref().map(|w| w.status()),
+                        })
+                    } else {
+                        json!({
+                            "configured": false,
+                            "message": "Gong API is not configured. Please set GONG_BASE_URL, GONG_ACCESS_KEY, and GONG_ACCESS_KEY_SECRET environment variables."
+                        })
+                    };
+
+                    Ok(ReadResourceResult {
+                        contents: vec![ResourceContents::text(
+                            serde_json::to_string_pretty(&status).unwrap(),
+                            uri,
+                        )],
+                    })
+                }
+                "gong://schema" => {
+                    if self.mirror.is_none() {
+                        return Err(McpError::resource_not_found(
+                            "mirror_unavailable",
+                            Some(json!({
+                                "message": "The schema describes the local mirror. Run 'gong-mcp sync' and start the server with --mirror-db or GONG_MIRROR_DB.",
+                                "uri": uri
+                            })),
+                        ));
+                    }
+
+                    Ok(ReadResourceResult {
+                        contents: vec![ResourceContents::text(
+                            serde_json::to_string_pretty(&sql_query::schema()).unwrap(),
+                            uri,
+                        )],
+                    })
+                }
+                "gong://users" => {
                     if !self._is_configured() {
                         return Err(McpError::invalid_request(
                             "not_configured",
@@ -352,382 +2185,488 @@ impl ServerHandler for GongServer {
                         ));
                     }
 
-                    // Extract call ID from URI
-                    let call_id = uri
-                        .strip_prefix("gong://calls/")
-                        .and_then(|s| s.strip_suffix("/participants"))
-                        .ok_or_else(|| {
-                            McpError::invalid_params(
-                                "invalid_uri",
+                    // Fetch users from Gong API
+                    let config = self
+                        .config
+                        .as_ref()
+                        .as_ref()
+                        .ok_or_else(|| McpError::invalid_request("not_configured", None))?;
+
+                    let params = users_api::ListUsersParams {
+                        cursor: None,
+                        include_avatars: Some(false),
+                    };
+
+                    let key = ResponseCache::key("users", &json!({"cursor": null, "includeAvatars": false}));
+                    let users_data = self
+                        ._cached(CacheKind::Users, key, async {
+                            retry::send(&self.retry, &self.limiter, || api::list_users(config, params.clone()))
+                                .await
+                                .map_err(|failure| error::api_error(failure, "not_found", json!({})))
+                        })
+                        .await?;
+
+                    // Format the users response
+                    let formatted_response = if let Some(users) = users_data.users {
+                        let formatted_users: Vec<serde_json::Value> = users
+                            .iter()
+                            .map(|user| {
+                                json!({
+                                    "id": user.id.as_ref().unwrap_or(&String::new()),
+                                    "email": user.email_address.as_ref().unwrap_or(&String::new()),
+                                    "firstName": user.first_name.as_ref().unwrap_or(&String::new()),
+                                    "lastName": user.last_name.as_ref().unwrap_or(&String::new()),
+                                    "active": user.active.unwrap_or(false),
+                                })
+                            })
+                            .collect();
+
+                        json!({
+                            "users": formatted_users,
+                            "count": formatted_users.len(),
+                            "message": format!("Retrieved {} users", formatted_users.len())
+                        })
+                    } else {
+                        json!({
+                            "users": [],
+                            "count": 0,
+                            "message": "No users found"
+                        })
+                    };
+
+                    Ok(ReadResourceResult {
+                        contents: vec![ResourceContents::text(
+                            serde_json::to_string_pretty(&formatted_response).unwrap(),
+                            uri,
+                        )],
+                    })
+                }
+                _ => {
+                    // Check if it matches the participants pattern: gong://calls/{callId}/participants
+                    if uri.starts_with("gong://calls/") && uri.ends_with("/participants") {
+                        if !self._is_configured() {
+                            return Err(McpError::invalid_request(
+                                "not_configured",
                                 Some(json!({
-                                    "message": "Invalid URI format. Expected: gong://calls/{callId}/participants",
-                                    "uri": uri
+                                    "message": "Gong API is not configured. Please set environment variables."
                                 })),
-                            )
-                        })?;
+                            ));
+                        }
 
-                    // Validate call ID is not empty
-                    if call_id.is_empty() {
-                        return Err(McpError::invalid_params(
-                            "missing_call_id",
-                            Some(json!({
-                                "message": "Call ID cannot be empty"
-                            })),
-                        ));
-                    }
+                        // Extract call ID from URI
+                        let call_id = uri
+                            .strip_prefix("gong://calls/")
+                            .and_then(|s| s.strip_suffix("/participants"))
+                            .ok_or_else(|| {
+                                McpError::invalid_params(
+                                    "invalid_uri",
+                                    Some(json!({
+                                        "message": "Invalid URI format. Expected: gong://calls/{callId}/participants",
+                                        "uri": uri
+                                    })),
+                                )
+                            })?;
+
+                        // Validate call ID is not empty
+                        if call_id.is_empty() {
+                            return Err(McpError::invalid_params(
+                                "missing_call_id",
+                                Some(json!({
+                                    "message": "Call ID cannot be empty"
+                                })),
+                            ));
+                        }
+
+                        let calls_data = self._fetch_call(call_id).await?;
+
+                        // Format the participants response
+                        let formatted_response = if let Some(calls) = calls_data.calls {
+                            if let Some(call) = calls.first() {
+                                // Extract and transform participants
+                                let participants = call.parties.as_ref().map(|parties| {
+                                    parties.iter().map(|party| {
+                                        json!({
+                                            "id": party.id,
+                                            "name": party.name,
+                                            "emailAddress": party.email_address,
+                                            "title": party.title,
+                                            "affiliation": party.affiliation.as_ref().map(|a| format!("{:?}", a)),
+                                            "speakerId": party.speaker_id,
+                                            "userId": party.user_id,
+                                            "phoneNumber": party.phone_number,
+                                            "methods": party.methods.as_ref().map(|m| {
+                                                m.iter().map(|method| format!("{:?}", method)).collect::<Vec<_>>()
+                                            }),
+                                            "context": party.context.as_ref().map(|ctx| {
+                                                ctx.iter().map(|c| {
+                                                    json!({
+                                                        "system": c.system.as_ref().map(|s| format!("{:?}", s)),
+                                                        "objects": c.objects
+                                                    })
+                                                }).collect::<Vec<_>>()
+                                            }),
+                                        })
+                                    }).collect::<Vec<_>>()
+                                }).unwrap_or_default();
+
+                                // Calculate summary statistics
+                                let counts = ParticipantSummary::from_parties(call.parties.as_ref());
+                                let mut summary = counts.to_json();
+                                summary["speakers"] = json!(counts.speakers);
+
+                                // Create speaker-to-name mapping table
+                                let speaker_map = call.parties.as_ref().map(|parties| {
+                                    parties.iter()
+                                        .filter_map(|party| {
+                                            party.speaker_id.as_ref().map(|speaker_id| {
+                                                let name = party.name.as_deref().unwrap_or("Unknown");
+                                                let affiliation = party.affiliation.as_ref()
+                                                    .map(|a| format!("{:?}", a))
+                                                    .unwrap_or_else(|| "Unknown".to_string());
+                                                (speaker_id.clone(), format!("{} ({})", name, affiliation))
+                                            })
+                                        })
+                                        .collect::<std::collections::HashMap<_, _>>()
+                                }).unwrap_or_default();
+
+                                let call_id_value = call.meta_data.as_ref()
+                                    .and_then(|m| m.as_ref().id.as_ref())
+                                    .cloned()
+                                    .unwrap_or_else(|| call_id.to_string());
 
-                    // Fetch call data from Gong API using list_calls_extensive
-                    // (get_call returns CallBasicData which doesn't include parties)
-                    let calls_data = self._fetch_calls_with_filter(
-                        None,
-                        None,
-                        None,
-                        Some(vec![call_id.to_string()]),
-                        None,
-                        None,
-                        false, // Don't need structure for participants
-                    ).await?;
-
-                    // Format the participants response
-                    let formatted_response = if let Some(calls) = calls_data.calls {
-                        if let Some(call) = calls.first() {
-                            // Extract and transform participants
-                            let participants = call.parties.as_ref().map(|parties| {
-                                parties.iter().map(|party| {
-                                    json!({
-                                        "id": party.id,
-                                        "name": party.name,
-                                        "emailAddress": party.email_address,
-                                        "title": party.title,
-                                        "affiliation": party.affiliation.as_ref().map(|a| format!("{:?}", a)),
-                                        "speakerId": party.speaker_id,
-                                        "userId": party.user_id,
-                                        "phoneNumber": party.phone_number,
-                                        "methods": party.methods.as_ref().map(|m| {
-                                            m.iter().map(|method| format!("{:?}", method)).collect::<Vec<_>>()
-                                        }),
-                                        "context": party.context.as_ref().map(|ctx| {
-                                            ctx.iter().map(|c| {
-                                                json!({
-                                                    "system": c.system.as_ref().map(|s| format!("{:?}", s)),
-                                                    "objects": c.objects
-                                                })
-                                            }).collect::<Vec<_>>()
-                                        }),
-                                    })
-                                }).collect::<Vec<_>>()
-                            }).unwrap_or_default();
-
-                            // Calculate summary statistics
-                            let summary = call.parties.as_ref().map(|parties| {
-                                let internal_count = parties.iter().filter(|p| {
-                                    matches!(p.affiliation, Some(ref a) if format!("{:?}", a) == "Internal")
-                                }).count();
-                                let external_count = parties.iter().filter(|p| {
-                                    matches!(p.affiliation, Some(ref a) if format!("{:?}", a) == "External")
-                                }).count();
-                                let speakers_count = parties.iter().filter(|p| p.speaker_id.is_some()).count();
                                 json!({
-                                    "total": parties.len(),
-                                    "internal": internal_count,
-                                    "external": external_count,
-                                    "speakers": speakers_count,
+                                    "callId": call_id_value,
+                                    "participants": participants,
+                                    "summary": summary,
+                                    "speakerMap": speaker_map,
                                 })
-                            }).unwrap_or(json!({"total": 0, "internal": 0, "external": 0, "speakers": 0}));
-
-                            // Create speaker-to-name mapping table
-                            let speaker_map = call.parties.as_ref().map(|parties| {
-                                parties.iter()
-                                    .filter_map(|party| {
-                                        party.speaker_id.as_ref().map(|speaker_id| {
-                                            let name = party.name.as_ref().map(|n| n.as_str()).unwrap_or("Unknown");
-                                            let affiliation = party.affiliation.as_ref()
-                                                .map(|a| format!("{:?}", a))
-                                                .unwrap_or_else(|| "Unknown".to_string());
-                                            (speaker_id.clone(), format!("{} ({})", name, affiliation))
-                                        })
-                                    })
-                                    .collect::<std::collections::HashMap<_, _>>()
-                            }).unwrap_or_default();
-
-                            let call_id_value = call.meta_data.as_ref()
-                                .and_then(|m| m.as_ref().id.as_ref())
-                                .cloned()
-                                .unwrap_or_else(|| call_id.to_string());
-
-                            json!({
-                                "callId": call_id_value,
-                                "participants": participants,
-                                "summary": summary,
-                                "speakerMap": speaker_map,
-                            })
+                            } else {
+                                return Err(McpError::resource_not_found(
+                                    "call_not_found",
+                                    Some(json!({
+                                        "callId": call_id,
+                                        "message": "Call not found in API response"
+                                    })),
+                                ));
+                            }
                         } else {
                             return Err(McpError::resource_not_found(
                                 "call_not_found",
                                 Some(json!({
                                     "callId": call_id,
-                                    "message": "Call not found in API response"
+                                    "message": "No call data returned from API"
+                                })),
+                            ));
+                        };
+
+                        Ok(ReadResourceResult {
+                            contents: vec![ResourceContents::text(
+                                serde_json::to_string_pretty(&formatted_response).unwrap(),
+                                uri,
+                            )],
+                        })
+                    } else if uri.starts_with("gong://calls/") && uri.ends_with("/transcript") {
+                        // Check if it matches the transcript pattern: gong://calls/{callId}/transcript
+                        if !self._is_configured() {
+                            return Err(McpError::invalid_request(
+                                "not_configured",
+                                Some(json!({
+                                    "message": "Gong API is not configured. Please set environment variables."
                                 })),
                             ));
                         }
-                    } else {
-                        return Err(McpError::resource_not_found(
-                            "call_not_found",
-                            Some(json!({
-                                "callId": call_id,
-                                "message": "No call data returned from API"
-                            })),
-                        ));
-                    };
 
-                    Ok(ReadResourceResult {
-                        contents: vec![ResourceContents::text(
-                            serde_json::to_string_pretty(&formatted_response).unwrap(),
-                            uri,
-                        )],
-                    })
-                } else if uri.starts_with("gong://calls/") && uri.ends_with("/transcript") {
-                    // Check if it matches the transcript pattern: gong://calls/{callId}/transcript
-                    if !self._is_configured() {
-                        return Err(McpError::invalid_request(
-                            "not_configured",
-                            Some(json!({
-                                "message": "Gong API is not configured. Please set environment variables."
-                            })),
-                        ));
-                    }
+                        // Extract call ID from URI
+                        let call_id = uri
+                            .strip_prefix("gong://calls/")
+                            .and_then(|s| s.strip_suffix("/transcript"))
+                            .ok_or_else(|| {
+                                McpError::invalid_params(
+                                    "invalid_uri",
+                                    Some(json!({
+                                        "message": "Invalid URI format. Expected: gong://calls/{callId}/transcript",
+                                        "uri": uri
+                                    })),
+                                )
+                            })?;
+
+                        // Validate call ID is not empty
+                        if call_id.is_empty() {
+                            return Err(McpError::invalid_params(
+                                "missing_call_id",
+                                Some(json!({
+                                    "message": "Call ID cannot be empty"
+                                })),
+                            ));
+                        }
+
+                        // Fetch transcript from Gong API
+                        let transcript_data = self._fetch_transcript(call_id).await?;
+
+                        // Format the transcript response with metadata
+                        let formatted_response =
+                            if let Some(transcripts) = transcript_data.call_transcripts {
+                                if let Some(transcript) = transcripts.first() {
+                                    let empty_string = String::new();
+                                    let retrieved_call_id =
+                                        transcript.call_id.as_ref().unwrap_or(&empty_string);
+                                    let monologues = transcript.transcript.as_ref();
+
+                                    // Extract sentences and speaker information from monologues
+                                    let (all_sentences, speaker_ids): (Vec<_>, Vec<_>) = monologues
+                                        .map(|m| {
+                                            m.iter()
+                                                .flat_map(|monologue| {
+                                                    let speaker_id = monologue.speaker_id.clone();
+                                                    monologue
+                                                        .sentences
+                                                        .as_ref()
+                                                        .map(|sentences| {
+                                                            sentences
+                                                                .iter()
+                                                                .map(|s| {
+                                                                    (
+                                                                        json!({
+                                                                            "speakerId": speaker_id,
+                                                                            "start": s.start,
+                                                                            "end": s.end,
+                                                                            "text": s.text,
+                                                                        }),
+                                                                        speaker_id.clone(),
+                                                                    )
+                                                                })
+                                                                .collect::<Vec<_>>()
+                                                        })
+                                                        .unwrap_or_default()
+                                                })
+                                                .collect::<Vec<_>>()
+                                        })
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .unzip();
+
+                                    // Get unique speakers
+                                    let unique_speakers: std::collections::HashSet<_> =
+                                        speaker_ids.into_iter().flatten().collect();
+
+                                    json!({
+                                        "callId": retrieved_call_id,
+                                        "monologues": monologues,
+                                        "sentences": all_sentences,
+                                        "metadata": {
+                                            "sentenceCount": all_sentences.len(),
+                                            "speakerCount": unique_speakers.len(),
+                                            "monologueCount": monologues.map(|m| m.len()).unwrap_or(0),
+                                        }
+                                    })
+                                } else {
+                                    return Err(McpError::resource_not_found(
+                                        "transcript_not_found",
+                                        Some(json!({
+                                            "callId": call_id,
+                                            "message": "No transcript found for this call"
+                                        })),
+                                    ));
+                                }
+                            } else {
+                                return Err(McpError::resource_not_found(
+                                    "transcript_not_found",
+                                    Some(json!({
+                                        "callId": call_id,
+                                        "message": "No transcript data returned from API"
+                                    })),
+                                ));
+                            };
+
+                        Ok(ReadResourceResult {
+                            contents: vec![ResourceContents::text(
+                                serde_json::to_string_pretty(&formatted_response).unwrap(),
+                                uri,
+                            )],
+                        })
+                    } else if uri.starts_with("gong://calls/") {
+                        // Check if it matches the call metadata pattern: gong://calls/{callId}
+                        if !self._is_configured() {
+                            return Err(McpError::invalid_request(
+                                "not_configured",
+                                Some(json!({
+                                    "message": "Gong API is not configured. Please set environment variables."
+                                })),
+                            ));
+                        }
 
-                    // Extract call ID from URI
-                    let call_id = uri
-                        .strip_prefix("gong://calls/")
-                        .and_then(|s| s.strip_suffix("/transcript"))
-                        .ok_or_else(|| {
+                        // Extract call ID from URI
+                        let call_id = uri.strip_prefix("gong://calls/").ok_or_else(|| {
                             McpError::invalid_params(
                                 "invalid_uri",
                                 Some(json!({
-                                    "message": "Invalid URI format. Expected: gong://calls/{callId}/transcript",
+                                    "message": "Invalid URI format. Expected: gong://calls/{callId}",
                                     "uri": uri
                                 })),
                             )
                         })?;
 
-                    // Validate call ID is not empty
-                    if call_id.is_empty() {
-                        return Err(McpError::invalid_params(
-                            "missing_call_id",
-                            Some(json!({
-                                "message": "Call ID cannot be empty"
-                            })),
-                        ));
-                    }
+                        // Validate call ID is not empty
+                        if call_id.is_empty() {
+                            return Err(McpError::invalid_params(
+                                "missing_call_id",
+                                Some(json!({
+                                    "message": "Call ID cannot be empty"
+                                })),
+                            ));
+                        }
 
-                    // Fetch transcript from Gong API
-                    let transcript_data = self._fetch_transcript(call_id).await?;
-
-                    // Format the transcript response with metadata
-                    let formatted_response =
-                        if let Some(transcripts) = transcript_data.call_transcripts {
-                            if let Some(transcript) = transcripts.first() {
-                                let empty_string = String::new();
-                                let retrieved_call_id =
-                                    transcript.call_id.as_ref().unwrap_or(&empty_string);
-                                let monologues = transcript.transcript.as_ref();
-
-                                // Extract sentences and speaker information from monologues
-                                let (all_sentences, speaker_ids): (Vec<_>, Vec<_>) = monologues
-                                    .map(|m| {
-                                        m.iter()
-                                            .flat_map(|monologue| {
-                                                let speaker_id = monologue.speaker_id.clone();
-                                                monologue
-                                                    .sentences
-                                                    .as_ref()
-                                                    .map(|sentences| {
-                                                        sentences
-                                                            .iter()
-                                                            .map(|s| {
-                                                                (
-                                                                    json!({
-                                                                        "speakerId": speaker_id,
-                                                                        "start": s.start,
-                                                                        "end": s.end,
-                                                                        "text": s.text,
-                                                                    }),
-                                                                    speaker_id.clone(),
-                                                                )
-                                                            })
-                                                            .collect::<Vec<_>>()
-                                                    })
-                                                    .unwrap_or_default()
-                                            })
-                                            .collect::<Vec<_>>()
-                                    })
-                                    .unwrap_or_default()
-                                    .into_iter()
-                                    .unzip();
+                        let calls_data = self._fetch_call(call_id).await?;
 
-                                // Get unique speakers
-                                let unique_speakers: std::collections::HashSet<_> =
-                                    speaker_ids.into_iter().flatten().collect();
+                        // Format the call metadata response
+                        let formatted_response = if let Some(calls) = calls_data.calls {
+                            if let Some(call) = calls.first() {
+                                let meta = call.meta_data.as_ref().map(|m| m.as_ref());
+
+                                // Calculate participant summary
+                                let participant_summary =
+                                    ParticipantSummary::from_parties(call.parties.as_ref()).to_json();
 
                                 json!({
-                                    "callId": retrieved_call_id,
-                                    "monologues": monologues,
-                                    "sentences": all_sentences,
-                                    "metadata": {
-                                        "sentenceCount": all_sentences.len(),
-                                        "speakerCount": unique_speakers.len(),
-                                        "monologueCount": monologues.map(|m| m.len()).unwrap_or(0),
-                                    }
+                                    "id": meta.and_then(|m| m.id.as_ref()),
+                                    "url": meta.and_then(|m| m.url.as_ref()),
+                                    "title": meta.and_then(|m| m.title.as_ref()),
+                                    "scheduled": meta.and_then(|m| m.scheduled.as_ref()),
+                                    "started": meta.and_then(|m| m.started.as_ref()),
+                                    "duration": meta.and_then(|m| m.duration),
+                                    "direction": meta.and_then(|m| m.direction.as_ref()).map(|d| format!("{:?}", d)),
+                                    "primaryUserId": meta.and_then(|m| m.primary_user_id.as_ref()),
+                                    "system": meta.and_then(|m| m.system.as_ref()),
+                                    "scope": meta.and_then(|m| m.scope.as_ref()).map(|s| format!("{:?}", s)),
+                                    "media": meta.and_then(|m| m.media.as_ref()).map(|m| format!("{:?}", m)),
+                                    "language": meta.and_then(|m| m.language.as_ref()),
+                                    "workspaceId": meta.and_then(|m| m.workspace_id.as_ref()),
+                                    "sdrDisposition": meta.and_then(|m| m.sdr_disposition.as_ref()),
+                                    "clientUniqueId": meta.and_then(|m| m.client_unique_id.as_ref()),
+                                    "customData": meta.and_then(|m| m.custom_data.as_ref()),
+                                    "purpose": meta.and_then(|m| m.purpose.as_ref()),
+                                    "meetingUrl": meta.and_then(|m| m.meeting_url.as_ref()),
+                                    "isPrivate": meta.and_then(|m| m.is_private),
+                                    "calendarEventId": meta.and_then(|m| m.calendar_event_id.as_ref()),
+                                    "participantCount": call.parties.as_ref().map(|p| p.len()).unwrap_or(0),
+                                    "participantSummary": participant_summary,
                                 })
                             } else {
                                 return Err(McpError::resource_not_found(
-                                    "transcript_not_found",
+                                    "call_not_found",
                                     Some(json!({
                                         "callId": call_id,
-                                        "message": "No transcript found for this call"
+                                        "message": "Call not found in API response"
                                     })),
                                 ));
                             }
                         } else {
                             return Err(McpError::resource_not_found(
-                                "transcript_not_found",
+                                "call_not_found",
                                 Some(json!({
                                     "callId": call_id,
-                                    "message": "No transcript data returned from API"
+                                    "message": "No call data returned from API"
                                 })),
                             ));
                         };
 
-                    Ok(ReadResourceResult {
-                        contents: vec![ResourceContents::text(
-                            serde_json::to_string_pretty(&formatted_response).unwrap(),
-                            uri,
-                        )],
-                    })
-                } else if uri.starts_with("gong://calls/") {
-                    // Check if it matches the call metadata pattern: gong://calls/{callId}
-                    if !self._is_configured() {
-                        return Err(McpError::invalid_request(
-                            "not_configured",
+                        Ok(ReadResourceResult {
+                            contents: vec![ResourceContents::text(
+                                serde_json::to_string_pretty(&formatted_response).unwrap(),
+                                uri,
+                            )],
+                        })
+                    } else if let Some(user_id) = uri.strip_prefix("gong://users/") {
+                        let user = self._read_user(user_id).await?;
+                        Ok(ReadResourceResult {
+                            contents: vec![ResourceContents::text(
+                                serde_json::to_string_pretty(&user).unwrap(),
+                                uri,
+                            )],
+                        })
+                    } else if let Some(workspace_id) = uri
+                        .strip_prefix("gong://workspaces/")
+                        .and_then(|rest| rest.strip_suffix("/calls"))
+                    {
+                        let calls = self._read_workspace_calls(workspace_id).await?;
+                        Ok(ReadResourceResult {
+                            contents: vec![ResourceContents::text(
+                                serde_json::to_string_pretty(&calls).unwrap(),
+                                uri,
+                            )],
+                        })
+                    } else {
+                        // Unknown resource
+                        Err(McpError::resource_not_found(
+                            "resource_not_found",
                             Some(json!({
-                                "message": "Gong API is not configured. Please set environment variables."
+                                "uri": uri
                             })),
-                        ));
+                        ))
                     }
+                }
+            }
+        })
+        .await
+    }
 
-                    // Extract call ID from URI
-                    let call_id = uri.strip_prefix("gong://calls/").ok_or_else(|| {
-                        McpError::invalid_params(
-                            "invalid_uri",
-                            Some(json!({
-                                "message": "Invalid URI format. Expected: gong://calls/{callId}",
-                                "uri": uri
-                            })),
-                        )
-                    })?;
+    async fn subscribe(
+        &self,
+        SubscribeRequestParam { uri }: SubscribeRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if !self._notifications_enabled() {
+            return Err(McpError::invalid_request(
+                "subscriptions_disabled",
+                Some(json!({
+                    "message": "Change notifications are disabled (GONG_WATCH_INTERVAL_SECS=0 and no webhook key configured)"
+                })),
+            ));
+        }
+        if !uri.starts_with("gong://") {
+            return Err(McpError::resource_not_found(
+                "resource_not_found",
+                Some(json!({"uri": uri})),
+            ));
+        }
 
-                    // Validate call ID is not empty
-                    if call_id.is_empty() {
-                        return Err(McpError::invalid_params(
-                            "missing_call_id",
-                            Some(json!({
-                                "message": "Call ID cannot be empty"
-                            })),
-                        ));
-                    }
+        self.session.subscribe(uri);
+        Ok(())
+    }
 
-                    // Fetch call metadata from Gong API using list_calls_extensive
-                    // (get_call returns CallBasicData which doesn't include parties)
-                    let calls_data = self._fetch_calls_with_filter(
-                        None,
-                        None,
-                        None,
-                        Some(vec![call_id.to_string()]),
-                        None,
-                        None,
-                        false, // Don't need structure for metadata
-                    ).await?;
-
-                    // Format the call metadata response
-                    let formatted_response = if let Some(calls) = calls_data.calls {
-                        if let Some(call) = calls.first() {
-                            let meta = call.meta_data.as_ref().map(|m| m.as_ref());
-
-                            // Calculate participant summary
-                            let participant_summary = call.parties.as_ref().map(|parties| {
-                                let internal_count = parties.iter().filter(|p| {
-                                    matches!(p.affiliation, Some(ref a) if format!("{:?}", a) == "Internal")
-                                }).count();
-                                let external_count = parties.iter().filter(|p| {
-                                    matches!(p.affiliation, Some(ref a) if format!("{:?}", a) == "External")
-                                }).count();
-                                json!({
-                                    "total": parties.len(),
-                                    "internal": internal_count,
-                                    "external": external_count,
-                                })
-                            }).unwrap_or(json!({"total": 0, "internal": 0, "external": 0}));
-
-                            json!({
-                                "id": meta.and_then(|m| m.id.as_ref()),
-                                "url": meta.and_then(|m| m.url.as_ref()),
-                                "title": meta.and_then(|m| m.title.as_ref()),
-                                "scheduled": meta.and_then(|m| m.scheduled.as_ref()),
-                                "started": meta.and_then(|m| m.started.as_ref()),
-                                "duration": meta.and_then(|m| m.duration),
-                                "direction": meta.and_then(|m| m.direction.as_ref()).map(|d| format!("{:?}", d)),
-                                "primaryUserId": meta.and_then(|m| m.primary_user_id.as_ref()),
-                                "system": meta.and_then(|m| m.system.as_ref()),
-                                "scope": meta.and_then(|m| m.scope.as_ref()).map(|s| format!("{:?}", s)),
-                                "media": meta.and_then(|m| m.media.as_ref()).map(|m| format!("{:?}", m)),
-                                "language": meta.and_then(|m| m.language.as_ref()),
-                                "workspaceId": meta.and_then(|m| m.workspace_id.as_ref()),
-                                "sdrDisposition": meta.and_then(|m| m.sdr_disposition.as_ref()),
-                                "clientUniqueId": meta.and_then(|m| m.client_unique_id.as_ref()),
-                                "customData": meta.and_then(|m| m.custom_data.as_ref()),
-                                "purpose": meta.and_then(|m| m.purpose.as_ref()),
-                                "meetingUrl": meta.and_then(|m| m.meeting_url.as_ref()),
-                                "isPrivate": meta.and_then(|m| m.is_private),
-                                "calendarEventId": meta.and_then(|m| m.calendar_event_id.as_ref()),
-                                "participantCount": call.parties.as_ref().map(|p| p.len()).unwrap_or(0),
-                                "participantSummary": participant_summary,
-                            })
-                        } else {
-                            return Err(McpError::resource_not_found(
-                                "call_not_found",
-                                Some(json!({
-                                    "callId": call_id,
-                                    "message": "Call not found in API response"
-                                })),
-                            ));
-                        }
-                    } else {
-                        return Err(McpError::resource_not_found(
-                            "call_not_found",
-                            Some(json!({
-                                "callId": call_id,
-                                "message": "No call data returned from API"
-                            })),
-                        ));
-                    };
+    async fn unsubscribe(
+        &self,
+        UnsubscribeRequestParam { uri }: UnsubscribeRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.session.unsubscribe(&uri);
+        Ok(())
+    }
 
-                    Ok(ReadResourceResult {
-                        contents: vec![ResourceContents::text(
-                            serde_json::to_string_pretty(&formatted_response).unwrap(),
-                            uri,
-                        )],
-                    })
-                } else {
-                    // Unknown resource
-                    Err(McpError::resource_not_found(
-                        "resource_not_found",
-                        Some(json!({
-                            "uri": uri
-                        })),
-                    ))
-                }
-            }
+    async fn set_level(
+        &self,
+        SetLevelRequestParam { level }: SetLevelRequestParam,
+        _: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.session.set_log_level(level);
+        Ok(())
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        tracing::info!("client initialized");
+        logging::register(LogTarget {
+            peer: context.peer.clone(),
+            session: self.session.clone(),
+        });
+        if !self._notifications_enabled() || !self._is_configured() {
+            return;
+        }
+        let first = self.watcher.register(self.session.clone(), context.peer);
+        if let Some(interval) = self.watch.interval
+            && first
+        {
+            tracing::info!("Polling for new calls every {:?}", interval);
+            tokio::spawn(self.clone()._watch(interval));
         }
     }
 
@@ -774,6 +2713,25 @@ impl ServerHandler for GongServer {
                 mime_type: Some("application/json".to_string()),
             }
             .no_annotation(),
+            RawResourceTemplate {
+                uri_template: "gong://users/{userId}".to_string(),
+                name: "User".to_string(),
+                title: None,
+                description: Some("Retrieve a Gong user by ID".to_string()),
+                mime_type: Some("application/json".to_string()),
+            }
+            .no_annotation(),
+            RawResourceTemplate {
+                uri_template: "gong://workspaces/{workspaceId}/calls".to_string(),
+                name: "Workspace Calls".to_string(),
+                title: None,
+                description: Some(format!(
+                    "List the calls of the last {} days in a Gong workspace",
+                    self.lookback_days
+                )),
+                mime_type: Some("application/json".to_string()),
+            }
+            .no_annotation(),
         ];
 
         Ok(ListResourceTemplatesResult {
@@ -782,281 +2740,333 @@ impl ServerHandler for GongServer {
         })
     }
 
-    async fn list_tools(
+    async fn list_prompts(
         &self,
         _request: Option<PaginatedRequestParam>,
         _: RequestContext<RoleServer>,
-    ) -> Result<ListToolsResult, McpError> {
+    ) -> Result<ListPromptsResult, McpError> {
         if !self._is_configured() {
-            return Ok(ListToolsResult {
+            return Ok(ListPromptsResult {
                 next_cursor: None,
-                tools: Vec::new(),
+                prompts: Vec::new(),
             });
         }
 
-        let schema = json!({
-            "type": "object",
-            "properties": {
-                "from_date_time": {
-                    "type": "string",
-                    "format": "date-time",
-                    "description": "Start of time range in ISO 8601 format (e.g., '2024-01-01T00:00:00Z' or '2024-01-01T02:30:00-07:00'). Returns calls that started on or after this time."
-                },
-                "to_date_time": {
-                    "type": "string",
-                    "format": "date-time",
-                    "description": "End of time range in ISO 8601 format. Returns calls that started before this time (exclusive)."
-                },
-                "workspace_id": {
-                    "type": "string",
-                    "description": "Filter by workspace ID. Returns only calls belonging to this workspace."
-                },
-                "call_ids": {
-                    "type": "array",
-                    "items": {"type": "string"},
-                    "description": "List of specific call IDs to retrieve. If provided, only these calls are returned (within date range if specified)."
-                },
-                "primary_user_ids": {
-                    "type": "array",
-                    "items": {"type": "string"},
-                    "description": "Filter by user IDs. Returns calls where these users are the primary participant/host."
-                },
-                "cursor": {
-                    "type": "string",
-                    "description": "Pagination cursor from a previous response. Use this to get the next page of results."
-                },
-                "limit": {
-                    "type": "number",
-                    "description": "Maximum number of calls to return from the current page. Without this, returns all calls from the API page (typically 100). Response includes 'truncated: true' if limited. Use this to reduce response size."
-                },
-                "include_structure": {
-                    "type": "boolean",
-                    "description": "Include call agenda/structure data (segments and their durations). Default: false. Basic call metadata (id, title, started, duration, direction, parties, url) is always included. Increases response size moderately."
+        Ok(ListPromptsResult {
+            next_cursor: None,
+            prompts: prompts::list(),
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        GetPromptRequestParam { name, arguments }: GetPromptRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        let mut prompt = WorkflowPrompt::parse(&name, arguments.as_ref())?;
+
+        // Without call IDs, a deal review embeds the calls Gong links to the
+        // opportunity
+        if let WorkflowPrompt::DealReview {
+            crm_opportunity_id,
+            call_ids,
+        } = &mut prompt
+            && call_ids.is_empty()
+            && self._is_configured()
+        {
+            match self._opportunity_calls(crm_opportunity_id).await {
+                Ok(found) => *call_ids = found,
+                Err(e) => tracing::warn!(
+                    "Could not look up the calls of opportunity {}: {:?}",
+                    crm_opportunity_id,
+                    e
+                ),
+            }
+        }
+        let mut uris = prompt.resources();
+
+        // Competitor mentions can only be found up front in the local index;
+        // without it the model is asked to search for them
+        let mentions = match &prompt {
+            WorkflowPrompt::CompetitorBrief {
+                competitor,
+                from_date_time,
+                to_date_time,
+            } if self.mirror.is_some() => {
+                let query = IndexQuery {
+                    text: competitor.clone(),
+                    match_all: true,
+                    from_date_time: Some(from_date_time.clone()),
+                    to_date_time: Some(to_date_time.clone()),
+                    limit: DEFAULT_INDEX_RESULTS,
+                    ..Default::default()
+                };
+                let hits = self
+                    ._query_mirror(move |mirror| mirror.search_passages(&query))
+                    .await
+                    .map_err(Self::_mirror_error)?;
+                uris.extend(
+                    prompts::mentioned_calls(&hits)
+                        .into_iter()
+                        .map(|call_id| format!("gong://calls/{}", call_id)),
+                );
+                Some(hits)
+            }
+            _ => None,
+        };
+
+        let mut messages = vec![PromptMessage::new_text(
+            PromptMessageRole::User,
+            prompt.instructions(mentions.as_deref()),
+        )];
+        for uri in uris {
+            let resource = self
+                .read_resource(ReadResourceRequestParam { uri }, context.clone())
+                .await?;
+            messages.extend(resource.contents.into_iter().map(|contents| {
+                PromptMessage {
+                    role: PromptMessageRole::User,
+                    content: PromptMessageContent::Resource {
+                        resource: RawEmbeddedResource {
+                            meta: None,
+                            resource: contents,
+                        }
+                        .no_annotation(),
+                    },
                 }
-            },
-            "additionalProperties": false
-        });
+            }));
+        }
 
-        let schema_obj = schema.as_object().unwrap().clone();
+        Ok(GetPromptResult {
+            description: Some(prompt.description()),
+            messages,
+        })
+    }
 
-        let tools = vec![Tool::new(
-            "search_calls",
-            "Search Gong calls with flexible filters. Returns basic call metadata (id, title, started, duration, \
-             direction, parties, url) by default. Use include_structure to add call agenda data. \
-             Supports pagination for large result sets - use limit to reduce response size. \
-             All parameters are optional - returns recent calls if no filters provided.",
-            std::sync::Arc::new(schema_obj),
-        )
-        .annotate(ToolAnnotations::new().read_only(true))];
+    async fn complete(
+        &self,
+        CompleteRequestParam { argument, .. }: CompleteRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, McpError> {
+        let Some(kind) = CompletionKind::for_argument(&argument.name) else {
+            return Ok(CompleteResult::default());
+        };
+        if !self._is_configured() {
+            return Ok(CompleteResult::default());
+        }
+
+        self._in_request(&context, async {
+            let candidates = match kind {
+                CompletionKind::Call => {
+                    completion::call_candidates(&self._recent_calls(None).await?)
+                }
+                CompletionKind::Workspace => {
+                    completion::workspace_candidates(&self._recent_calls(None).await?)
+                }
+                // Users aren't mirrored, so there is nothing to offer offline
+                CompletionKind::User | CompletionKind::UserEmail if self.config.is_none() => {
+                    Vec::new()
+                }
+                CompletionKind::User | CompletionKind::UserEmail => {
+                    completion::user_candidates(&self._fetch_users().await?, kind)
+                }
+            };
+            Ok(CompleteResult {
+                completion: completion::complete(candidates, &argument.value),
+            })
+        })
+        .await
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        if !self._is_configured() {
+            return Ok(ListToolsResult {
+                next_cursor: None,
+                tools: Vec::new(),
+            });
+        }
 
         Ok(ListToolsResult {
             next_cursor: None,
-            tools,
+            tools: Self::_tools(),
         })
     }
 
     async fn call_tool(
         &self,
         CallToolRequestParam { name, arguments }: CallToolRequestParam,
-        _: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        match name.as_ref() {
-            "search_calls" => {
-                if !self._is_configured() {
-                    return Err(McpError::invalid_request(
-                        "not_configured",
-                        Some(json!({
-                            "message": "Gong API is not configured. Please set GONG_BASE_URL, GONG_ACCESS_KEY, and GONG_ACCESS_KEY_SECRET environment variables.",
-                            "required_env_vars": ["GONG_BASE_URL", "GONG_ACCESS_KEY", "GONG_ACCESS_KEY_SECRET"]
-                        })),
-                    ));
-                }
-
-                // Get arguments or use empty map if None
-                let args = arguments.as_ref();
-
-                // Extract parameters from arguments
-                let from_date_time = args
-                    .and_then(|a| a.get("from_date_time"))
-                    .and_then(|v| v.as_str())
-                    .map(String::from);
-
-                let to_date_time = args
-                    .and_then(|a| a.get("to_date_time"))
-                    .and_then(|v| v.as_str())
-                    .map(String::from);
-
-                let workspace_id = args
-                    .and_then(|a| a.get("workspace_id"))
-                    .and_then(|v| v.as_str())
-                    .map(String::from);
-
-                let call_ids = args
-                    .and_then(|a| a.get("call_ids"))
-                    .and_then(|v| v.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|v| v.as_str().map(String::from))
-                            .collect::<Vec<String>>()
-                    });
-
-                let primary_user_ids = args
-                    .and_then(|a| a.get("primary_user_ids"))
-                    .and_then(|v| v.as_array())
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|v| v.as_str().map(String::from))
-                            .collect::<Vec<String>>()
-                    });
-
-                let cursor = args
-                    .and_then(|a| a.get("cursor"))
-                    .and_then(|v| v.as_str())
-                    .map(String::from);
-
-                let limit = args
-                    .and_then(|a| a.get("limit"))
-                    .and_then(|v| v.as_u64())
-                    .map(|v| v as usize);
-
-                let include_structure = args
-                    .and_then(|a| a.get("include_structure"))
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false);
-
-                // Fetch calls from Gong API
-                let calls_data = self
-                    ._fetch_calls_with_filter(
-                        from_date_time.clone(),
-                        to_date_time.clone(),
-                        workspace_id.clone(),
-                        call_ids.clone(),
-                        primary_user_ids.clone(),
-                        cursor.clone(),
-                        include_structure,
-                    )
-                    .await?;
-
-                // Extract and format the calls for easier consumption
-                // Response format:
-                // - calls: Array of call objects with basic metadata
-                // - count: Number of calls returned (after limit applied)
-                // - totalAvailable: Total calls in current API page before limiting (typically 100)
-                // - truncated: true if limit parameter was applied and reduced the result set
-                // - hasMore: true if more pages available (use nextCursor to fetch)
-                // - nextCursor: Pagination cursor for retrieving the next page
-                // - filters: Echo of all filter parameters used in the request
-                let formatted_response = if let Some(calls) = calls_data.calls {
-                    let all_formatted_calls: Vec<serde_json::Value> = calls
-                        .iter()
-                        .map(|call| {
-                            let meta = call.meta_data.as_ref().map(|m| m.as_ref());
+        // An unknown tool is a malformed request; every other failure goes back
+        // to the model as a tool result so it can correct its arguments
+        if !Self::_tools().iter().any(|tool| tool.name == name) {
+            return Err(McpError::invalid_params(
+                "unknown_tool",
+                Some(json!({"tool": name})),
+            ));
+        }
 
-                            // Transform participants to LLM-friendly format with key fields
-                            let participants = call.parties.as_ref().map(|parties| {
-                                parties.iter().map(|party| {
-                                    json!({
-                                        "id": party.id,
-                                        "name": party.name,
-                                        "emailAddress": party.email_address,
-                                        "title": party.title,
-                                        "affiliation": party.affiliation.as_ref().map(|a| format!("{:?}", a)),
-                                        "speakerId": party.speaker_id,
-                                        "userId": party.user_id,
+        let result = self
+            ._in_request(&context, async {
+                match name.as_ref() {
+                    "search_calls" => {
+                        self.require_configured()?;
+
+                        let SearchCallsArgs {
+                            from_date_time,
+                            to_date_time,
+                            workspace_id,
+                            call_ids,
+                            primary_user_ids,
+                            cursor,
+                            limit,
+                            include_structure,
+                        } = args::parse(arguments.as_ref())?;
+                        let from_date_time = from_date_time.map(String::from);
+                        let to_date_time = to_date_time.map(String::from);
+
+                        // Fetch calls from Gong API
+                        let calls_data = self
+                            ._fetch_calls_with_filter(
+                                from_date_time.clone(),
+                                to_date_time.clone(),
+                                workspace_id.clone(),
+                                call_ids.clone(),
+                                primary_user_ids.clone(),
+                                cursor.clone(),
+                                ContentSelection {
+                                    structure: include_structure,
+                                    ..Default::default()
+                                },
+                            )
+                            .await?;
+
+                        // Extract and format the calls for easier consumption
+                        // Response format:
+                        // - calls: Array of call objects with basic metadata
+                        // - count: Number of calls returned (after limit applied)
+                        // - totalAvailable: Total calls in current API page before limiting (typically 100)
+                        // - truncated: true if limit parameter was applied and reduced the result set
+                        // - hasMore: true if more pages available (use nextCursor to fetch)
+                        // - nextCursor: Pagination cursor for retrieving the next page
+                        // - filters: Echo of all filter parameters used in the request
+                        let mut calls: Vec<CallSummary> = calls_data
+                            .calls
+                            .iter()
+                            .flatten()
+                            .map(|call| {
+                                let meta = call.meta_data.as_ref().map(|m| m.as_ref());
+
+                                // Transform participants to LLM-friendly format with key fields
+                                let participants = call
+                                    .parties
+                                    .as_ref()
+                                    .map(|parties| {
+                                        parties
+                                            .iter()
+                                            .map(|party| Participant {
+                                                id: party.id.clone(),
+                                                name: party.name.clone(),
+                                                email_address: party.email_address.clone(),
+                                                title: party.title.clone(),
+                                                affiliation: party
+                                                    .affiliation
+                                                    .as_ref()
+                                                    .map(|a| format!("{:?}", a)),
+                                                speaker_id: party.speaker_id.clone(),
+                                                user_id: party.user_id.clone(),
+                                            })
+                                            .collect::<Vec<_>>()
                                     })
-                                }).collect::<Vec<_>>()
-                            }).unwrap_or_default();
-
-                            // Calculate participant summary statistics
-                            let participant_summary = call.parties.as_ref().map(|parties| {
-                                let internal_count = parties.iter().filter(|p| {
-                                    matches!(p.affiliation, Some(ref a) if format!("{:?}", a) == "Internal")
-                                }).count();
-                                let external_count = parties.iter().filter(|p| {
-                                    matches!(p.affiliation, Some(ref a) if format!("{:?}", a) == "External")
-                                }).count();
-                                json!({
-                                    "total": parties.len(),
-                                    "internal": internal_count,
-                                    "external": external_count,
-                                })
-                            }).unwrap_or(json!({"total": 0, "internal": 0, "external": 0}));
-
-                            json!({
-                                "id": meta.and_then(|m| m.id.as_ref()).unwrap_or(&String::new()),
-                                "title": meta.and_then(|m| m.title.as_ref()).unwrap_or(&"Untitled".to_string()),
-                                "started": meta.and_then(|m| m.started.as_ref()).unwrap_or(&String::new()),
-                                "duration": meta.and_then(|m| m.duration).unwrap_or(0),
-                                "direction": meta.and_then(|m| m.direction.as_ref()).map(|d| format!("{:?}", d)).unwrap_or_default(),
-                                "participants": participants,
-                                "participantSummary": participant_summary,
-                                "url": meta.and_then(|m| m.url.as_ref()).unwrap_or(&String::new()),
+                                    .unwrap_or_default();
+
+                                CallSummary {
+                                    id: meta.and_then(|m| m.id.clone()).unwrap_or_default(),
+                                    title: meta
+                                        .and_then(|m| m.title.clone())
+                                        .unwrap_or_else(|| "Untitled".to_string()),
+                                    started: meta
+                                        .and_then(|m| m.started.clone())
+                                        .unwrap_or_default(),
+                                    duration: meta.and_then(|m| m.duration).unwrap_or(0),
+                                    direction: meta
+                                        .and_then(|m| m.direction.as_ref())
+                                        .map(|d| format!("{:?}", d))
+                                        .unwrap_or_default(),
+                                    participants,
+                                    // Calculate participant summary statistics
+                                    participant_summary: ParticipantSummary::from_parties(
+                                        call.parties.as_ref(),
+                                    )
+                                    .to_json(),
+                                    url: meta.and_then(|m| m.url.clone()).unwrap_or_default(),
+                                }
                             })
-                        })
-                        .collect();
+                            .collect();
 
-                    let total_available = all_formatted_calls.len();
-                    let (formatted_calls, truncated) = if let Some(limit_value) = limit {
-                        if all_formatted_calls.len() > limit_value {
-                            (all_formatted_calls.into_iter().take(limit_value).collect(), true)
-                        } else {
-                            (all_formatted_calls, false)
+                        let total_available = calls.len();
+                        let truncated = limit.is_some_and(|limit| calls.len() > limit);
+                        if let Some(limit) = limit {
+                            calls.truncate(limit);
                         }
-                    } else {
-                        (all_formatted_calls, false)
-                    };
 
-                    json!({
-                        "calls": formatted_calls,
-                        "count": formatted_calls.len(),
-                        "totalAvailable": total_available,
-                        "truncated": truncated,
-                        "nextCursor": calls_data.records.as_ref().and_then(|r| r.cursor.clone()),
-                        "hasMore": calls_data.records.as_ref().and_then(|r| r.cursor.as_ref()).is_some(),
-                        "filters": {
-                            "from_date_time": from_date_time,
-                            "to_date_time": to_date_time,
-                            "workspace_id": workspace_id,
-                            "call_ids": call_ids,
-                            "primary_user_ids": primary_user_ids,
-                            "limit": limit,
-                            "include_structure": include_structure,
-                        }
-                    })
-                } else {
-                    json!({
-                        "calls": [],
-                        "count": 0,
-                        "totalAvailable": 0,
-                        "truncated": false,
-                        "nextCursor": null,
-                        "hasMore": false,
-                        "filters": {
-                            "from_date_time": from_date_time,
-                            "to_date_time": to_date_time,
-                            "workspace_id": workspace_id,
-                            "call_ids": call_ids,
-                            "primary_user_ids": primary_user_ids,
-                            "limit": limit,
-                            "include_structure": include_structure,
-                        }
-                    })
-                };
+                        let next_cursor =
+                            calls_data.records.as_ref().and_then(|r| r.cursor.clone());
+                        Ok(output::result(&SearchCallsOutput {
+                            count: calls.len(),
+                            calls,
+                            total_available,
+                            truncated,
+                            has_more: next_cursor.is_some(),
+                            next_cursor,
+                            filters: SearchCallsFilters {
+                                from_date_time,
+                                to_date_time,
+                                workspace_id,
+                                call_ids,
+                                primary_user_ids,
+                                limit,
+                                include_structure,
+                            },
+                        }))
+                    }
+                    "call_analytics" => {
+                        self.require_configured()?;
 
-                Ok(CallToolResult {
-                    content: vec![Content::text(
-                        serde_json::to_string_pretty(&formatted_response).unwrap(),
-                    )],
-                    structured_content: None,
-                    is_error: None,
-                    meta: None,
-                })
-            }
-            _ => Err(McpError::invalid_params(
-                "unknown_tool",
-                Some(json!({"tool": name})),
-            )),
-        }
+                        self._call_analytics(arguments.as_ref()).await
+                    }
+                    "transcript_metrics" => {
+                        self.require_configured()?;
+
+                        self._transcript_metrics(arguments.as_ref()).await
+                    }
+                    "run_local_trackers" => {
+                        self.require_configured()?;
+
+                        self._run_local_trackers(arguments.as_ref()).await
+                    }
+                    "compare_calls" => {
+                        self.require_configured()?;
+
+                        self._compare_calls(arguments.as_ref()).await
+                    }
+                    "get_transcripts" => {
+                        self.require_configured()?;
+
+                        self._get_transcripts(arguments.as_ref()).await
+                    }
+                    "search_index" => self._search_index(arguments.as_ref()).await,
+                    "query_calls_sql" => self._query_calls_sql(arguments.as_ref()).await,
+                    _ => unreachable!("{} is listed without a handler", name),
+                }
+            })
+            .await;
+
+        Ok(result.unwrap_or_else(|e| error::tool_error(&name, e)))
     }
 }
 
@@ -1076,6 +3086,8 @@ mod tests {
         let info = server.get_info();
         assert_eq!(info.server_info.name, "gong-mcp");
         assert!(info.capabilities.resources.is_some());
+        assert!(info.capabilities.prompts.is_some());
+        assert!(info.capabilities.logging.is_some());
     }
 
     #[test]
@@ -1135,6 +3147,63 @@ mod tests {
         let _ = is_configured;
     }
 
+    #[test]
+    fn test_sessions_have_own_subscriptions() {
+        let server = GongServer::new();
+        let session = server.new_session();
+        assert!(!Arc::ptr_eq(&server.session, &session.session));
+        assert!(Arc::ptr_eq(&server.watcher, &session.watcher));
+        assert!(Arc::ptr_eq(&server.cache, &session.cache));
+
+        session.session.set_log_level(LoggingLevel::Debug);
+        assert_eq!(session.session.log_level(), LoggingLevel::Debug);
+        assert_eq!(server.session.log_level(), logging::DEFAULT_LEVEL);
+    }
+
+    #[test]
+    fn test_tools_are_annotated() {
+        let tools = GongServer::_tools();
+        assert_eq!(tools.len(), 8);
+        for tool in tools {
+            let annotations = tool
+                .annotations
+                .as_ref()
+                .unwrap_or_else(|| panic!("{} has no annotations", tool.name));
+            assert!(annotations.title.is_some(), "{} has no title", tool.name);
+            assert!(annotations.idempotent_hint.is_some(), "{}", tool.name);
+            assert!(annotations.open_world_hint.is_some(), "{}", tool.name);
+            // Clients rely on the hints to skip confirmation, so writes must say so
+            let expected_destructive = annotations.read_only_hint != Some(true);
+            assert_eq!(
+                annotations.destructive_hint,
+                Some(expected_destructive),
+                "{} must be read-only or marked destructive",
+                tool.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_webhooks_enable_subscriptions() {
+        let mut server = GongServer::new();
+        server.watch = WatchConfig { interval: None };
+        let resources = server._capabilities().resources.unwrap();
+        assert_eq!(resources.subscribe, None);
+
+        let server = server.with_webhooks();
+        let resources = server.new_session()._capabilities().resources.unwrap();
+        assert_eq!(resources.subscribe, Some(true));
+        assert_eq!(resources.list_changed, Some(true));
+    }
+
+    #[test]
+    fn test_parse_lookback_days() {
+        assert_eq!(parse_lookback_days(Some("7")), 7);
+        assert_eq!(parse_lookback_days(Some("-1")), DEFAULT_LOOKBACK_DAYS);
+        assert_eq!(parse_lookback_days(Some("soon")), DEFAULT_LOOKBACK_DAYS);
+        assert_eq!(parse_lookback_days(None), DEFAULT_LOOKBACK_DAYS);
+    }
+
     #[test]
     fn test_server_with_mock_config() {
         // Set up environment variables
@@ -1159,8 +3228,14 @@ mod tests {
     fn test_server_capabilities_include_tools() {
         let server = GongServer::new();
         let info = server.get_info();
-        assert!(info.capabilities.tools.is_some(), "Server should support tools");
-        assert!(info.capabilities.resources.is_some(), "Server should support resources");
+        assert!(
+            info.capabilities.tools.is_some(),
+            "Server should support tools"
+        );
+        assert!(
+            info.capabilities.resources.is_some(),
+            "Server should support resources"
+        );
     }
 
     #[test]
@@ -1174,24 +3249,16 @@ mod tests {
             "primary_user_ids": ["user1", "user2"]
         });
 
-        let args_map = json_args.as_object();
-
-        // Extract from_date_time
-        let from_date = args_map
-            .and_then(|a| a.get("from_date_time"))
-            .and_then(|v| v.as_str());
-        assert_eq!(from_date, Some("2024-01-01T00:00:00Z"));
-
-        // Extract call_ids array
-        let call_ids = args_map
-            .and_then(|a| a.get("call_ids"))
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(String::from))
-                    .collect::<Vec<String>>()
-            });
-        assert_eq!(call_ids, Some(vec!["call1".to_string(), "call2".to_string()]));
+        let args: SearchCallsArgs = args::parse(json_args.as_object()).unwrap();
+
+        assert_eq!(
+            args.from_date_time.map(String::from),
+            Some("2024-01-01T00:00:00Z".to_string())
+        );
+        assert_eq!(
+            args.call_ids,
+            Some(vec!["call1".to_string(), "call2".to_string()])
+        );
     }
 
     #[test]
@@ -1206,24 +3273,36 @@ mod tests {
         for uri in valid_uris {
             let call_id = uri.strip_prefix("gong://calls/");
             assert!(call_id.is_some(), "Failed to parse URI: {}", uri);
-            assert!(!call_id.unwrap().is_empty(), "Call ID is empty for URI: {}", uri);
+            assert!(
+                !call_id.unwrap().is_empty(),
+                "Call ID is empty for URI: {}",
+                uri
+            );
             // Should NOT contain /transcript
-            assert!(!call_id.unwrap().contains("/transcript"), "Call metadata URI should not contain /transcript: {}", uri);
+            assert!(
+                !call_id.unwrap().contains("/transcript"),
+                "Call metadata URI should not contain /transcript: {}",
+                uri
+            );
         }
     }
 
     #[test]
     fn test_invalid_call_metadata_uri_parsing() {
         let invalid_uris = vec![
-            "gong://calls/",         // empty call ID
-            "gong://calls",          // missing call ID separator
-            "gong://call/123",       // wrong format (call vs calls)
+            "gong://calls/",   // empty call ID
+            "gong://calls",    // missing call ID separator
+            "gong://call/123", // wrong format (call vs calls)
         ];
 
         for uri in invalid_uris {
             let call_id = uri.strip_prefix("gong://calls/");
             if let Some(id) = call_id {
-                assert!(id.is_empty(), "Should have empty call ID for invalid URI: {}", uri);
+                assert!(
+                    id.is_empty(),
+                    "Should have empty call ID for invalid URI: {}",
+                    uri
+                );
             }
         }
     }
@@ -1244,7 +3323,10 @@ mod tests {
 
         let (truncated_calls, is_truncated) = if let Some(limit_value) = limit {
             if calls.len() > limit_value {
-                (calls.into_iter().take(limit_value).collect::<Vec<_>>(), true)
+                (
+                    calls.into_iter().take(limit_value).collect::<Vec<_>>(),
+                    true,
+                )
             } else {
                 (calls, false)
             }
@@ -1252,17 +3334,18 @@ mod tests {
             (calls, false)
         };
 
-        assert_eq!(truncated_calls.len(), 3, "Should have 3 calls after truncation");
+        assert_eq!(
+            truncated_calls.len(),
+            3,
+            "Should have 3 calls after truncation"
+        );
         assert_eq!(total, 5, "Total should be 5 before truncation");
         assert!(is_truncated, "Should be marked as truncated");
     }
 
     #[test]
     fn test_result_no_truncation_when_under_limit() {
-        let calls = vec![
-            json!({"id": "1"}),
-            json!({"id": "2"}),
-        ];
+        let calls = vec![json!({"id": "1"}), json!({"id": "2"})];
 
         // Test with limit higher than actual count
         let limit = Some(5);
@@ -1270,7 +3353,10 @@ mod tests {
 
         let (truncated_calls, is_truncated) = if let Some(limit_value) = limit {
             if calls.len() > limit_value {
-                (calls.into_iter().take(limit_value).collect::<Vec<_>>(), true)
+                (
+                    calls.into_iter().take(limit_value).collect::<Vec<_>>(),
+                    true,
+                )
             } else {
                 (calls, false)
             }
@@ -1280,7 +3366,10 @@ mod tests {
 
         assert_eq!(truncated_calls.len(), 2, "Should have all 2 calls");
         assert_eq!(total, 2, "Total should be 2");
-        assert!(!is_truncated, "Should NOT be marked as truncated when under limit");
+        assert!(
+            !is_truncated,
+            "Should NOT be marked as truncated when under limit"
+        );
     }
 
     #[test]
@@ -1291,21 +3380,10 @@ mod tests {
             "include_structure": true
         });
 
-        let args_map = json_args.as_object();
+        let args: SearchCallsArgs = args::parse(json_args.as_object()).unwrap();
 
-        // Extract limit
-        let limit = args_map
-            .and_then(|a| a.get("limit"))
-            .and_then(|v| v.as_u64())
-            .map(|v| v as usize);
-        assert_eq!(limit, Some(10), "Limit should be 10");
-
-        // Extract include_structure
-        let include_structure = args_map
-            .and_then(|a| a.get("include_structure"))
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        assert!(include_structure, "include_structure should be true");
+        assert_eq!(args.limit, Some(10), "Limit should be 10");
+        assert!(args.include_structure, "include_structure should be true");
     }
 
     #[test]
@@ -1314,14 +3392,12 @@ mod tests {
             "from_date_time": "2024-01-01T00:00:00Z"
         });
 
-        let args_map = json_args.as_object();
+        let args: SearchCallsArgs = args::parse(json_args.as_object()).unwrap();
 
-        // Extract include_structure (should default to false)
-        let include_structure = args_map
-            .and_then(|a| a.get("include_structure"))
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        assert!(!include_structure, "include_structure should default to false when not provided");
+        assert!(
+            !args.include_structure,
+            "include_structure should default to false when not provided"
+        );
     }
 
     #[test]
@@ -1331,12 +3407,24 @@ mod tests {
         let transcript_uri = "gong://calls/123456/transcript";
 
         // Metadata should not end with /transcript
-        assert!(!metadata_uri.ends_with("/transcript"), "Metadata URI should not end with /transcript");
-        assert!(metadata_uri.starts_with("gong://calls/"), "Metadata URI should start with gong://calls/");
+        assert!(
+            !metadata_uri.ends_with("/transcript"),
+            "Metadata URI should not end with /transcript"
+        );
+        assert!(
+            metadata_uri.starts_with("gong://calls/"),
+            "Metadata URI should start with gong://calls/"
+        );
 
         // Transcript should end with /transcript
-        assert!(transcript_uri.ends_with("/transcript"), "Transcript URI should end with /transcript");
-        assert!(transcript_uri.starts_with("gong://calls/"), "Transcript URI should start with gong://calls/");
+        assert!(
+            transcript_uri.ends_with("/transcript"),
+            "Transcript URI should end with /transcript"
+        );
+        assert!(
+            transcript_uri.starts_with("gong://calls/"),
+            "Transcript URI should start with gong://calls/"
+        );
 
         // Extract call IDs
         let metadata_call_id = metadata_uri.strip_prefix("gong://calls/");
@@ -1344,8 +3432,16 @@ mod tests {
             .strip_prefix("gong://calls/")
             .and_then(|s| s.strip_suffix("/transcript"));
 
-        assert_eq!(metadata_call_id, Some("123456"), "Metadata URI should extract call ID");
-        assert_eq!(transcript_call_id, Some("123456"), "Transcript URI should extract call ID");
+        assert_eq!(
+            metadata_call_id,
+            Some("123456"),
+            "Metadata URI should extract call ID"
+        );
+        assert_eq!(
+            transcript_call_id,
+            Some("123456"),
+            "Transcript URI should extract call ID"
+        );
     }
 
     #[test]
@@ -1362,19 +3458,27 @@ mod tests {
                 .strip_prefix("gong://calls/")
                 .and_then(|s| s.strip_suffix("/participants"));
             assert!(call_id.is_some(), "Failed to parse URI: {}", uri);
-            assert!(!call_id.unwrap().is_empty(), "Call ID is empty for URI: {}", uri);
+            assert!(
+                !call_id.unwrap().is_empty(),
+                "Call ID is empty for URI: {}",
+                uri
+            );
             // Should NOT contain /transcript
-            assert!(!call_id.unwrap().contains("/transcript"), "Participants URI should not contain /transcript: {}", uri);
+            assert!(
+                !call_id.unwrap().contains("/transcript"),
+                "Participants URI should not contain /transcript: {}",
+                uri
+            );
         }
     }
 
     #[test]
     fn test_invalid_participants_uri_parsing() {
         let invalid_uris = vec![
-            "gong://calls//participants",         // empty call ID
-            "gong://calls/participants",          // missing call ID
-            "gong://participants/123",            // wrong format
-            "gong://calls/123/participant",       // wrong suffix (singular)
+            "gong://calls//participants",   // empty call ID
+            "gong://calls/participants",    // missing call ID
+            "gong://participants/123",      // wrong format
+            "gong://calls/123/participant", // wrong suffix (singular)
         ];
 
         for uri in invalid_uris {
@@ -1383,7 +3487,11 @@ mod tests {
                 .and_then(|s| s.strip_suffix("/participants"));
 
             if let Some(id) = call_id {
-                assert!(id.is_empty() || id == "participants", "Should have empty or invalid call ID for invalid URI: {}", uri);
+                assert!(
+                    id.is_empty() || id == "participants",
+                    "Should have empty or invalid call ID for invalid URI: {}",
+                    uri
+                );
             }
         }
     }
@@ -1396,16 +3504,34 @@ mod tests {
         let transcript_uri = "gong://calls/123456/transcript";
 
         // Metadata should not end with /participants or /transcript
-        assert!(!metadata_uri.ends_with("/participants"), "Metadata URI should not end with /participants");
-        assert!(!metadata_uri.ends_with("/transcript"), "Metadata URI should not end with /transcript");
+        assert!(
+            !metadata_uri.ends_with("/participants"),
+            "Metadata URI should not end with /participants"
+        );
+        assert!(
+            !metadata_uri.ends_with("/transcript"),
+            "Metadata URI should not end with /transcript"
+        );
 
         // Participants should end with /participants
-        assert!(participants_uri.ends_with("/participants"), "Participants URI should end with /participants");
-        assert!(!participants_uri.ends_with("/transcript"), "Participants URI should not end with /transcript");
+        assert!(
+            participants_uri.ends_with("/participants"),
+            "Participants URI should end with /participants"
+        );
+        assert!(
+            !participants_uri.ends_with("/transcript"),
+            "Participants URI should not end with /transcript"
+        );
 
         // Transcript should end with /transcript
-        assert!(transcript_uri.ends_with("/transcript"), "Transcript URI should end with /transcript");
-        assert!(!transcript_uri.ends_with("/participants"), "Transcript URI should not end with /participants");
+        assert!(
+            transcript_uri.ends_with("/transcript"),
+            "Transcript URI should end with /transcript"
+        );
+        assert!(
+            !transcript_uri.ends_with("/participants"),
+            "Transcript URI should not end with /participants"
+        );
 
         // Extract call IDs
         let metadata_call_id = metadata_uri.strip_prefix("gong://calls/");
@@ -1416,9 +3542,21 @@ mod tests {
             .strip_prefix("gong://calls/")
             .and_then(|s| s.strip_suffix("/transcript"));
 
-        assert_eq!(metadata_call_id, Some("123456"), "Metadata URI should extract call ID");
-        assert_eq!(participants_call_id, Some("123456"), "Participants URI should extract call ID");
-        assert_eq!(transcript_call_id, Some("123456"), "Transcript URI should extract call ID");
+        assert_eq!(
+            metadata_call_id,
+            Some("123456"),
+            "Metadata URI should extract call ID"
+        );
+        assert_eq!(
+            participants_call_id,
+            Some("123456"),
+            "Participants URI should extract call ID"
+        );
+        assert_eq!(
+            transcript_call_id,
+            Some("123456"),
+            "Transcript URI should extract call ID"
+        );
     }
 
     #[test]
@@ -1436,19 +3574,25 @@ mod tests {
         ];
 
         // Simulate the counting logic
-        let internal_count = participants.iter().filter(|p| {
-            p.get("affiliation")
-                .and_then(Value::as_str)
-                .map(|a| a == "Internal")
-                .unwrap_or(false)
-        }).count();
-
-        let external_count = participants.iter().filter(|p| {
-            p.get("affiliation")
-                .and_then(Value::as_str)
-                .map(|a| a == "External")
-                .unwrap_or(false)
-        }).count();
+        let internal_count = participants
+            .iter()
+            .filter(|p| {
+                p.get("affiliation")
+                    .and_then(Value::as_str)
+                    .map(|a| a == "Internal")
+                    .unwrap_or(false)
+            })
+            .count();
+
+        let external_count = participants
+            .iter()
+            .filter(|p| {
+                p.get("affiliation")
+                    .and_then(Value::as_str)
+                    .map(|a| a == "External")
+                    .unwrap_or(false)
+            })
+            .count();
 
         assert_eq!(internal_count, 2, "Should have 2 internal participants");
         assert_eq!(external_count, 3, "Should have 3 external participants");
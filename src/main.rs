@@ -1,12 +1,17 @@
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use gong_mcp::GongServer;
-use rmcp::{ServiceExt, transport::stdio};
+use gong_mcp::disk_cache::{self, DiskCache, DiskKind};
+use gong_mcp::export::{ExportEntity, ExportFormat, ExportOptions};
+use gong_mcp::mirror::{self, Mirror};
+use gong_mcp::webhook::{self, WebhookVerifier};
 use rmcp::transport::streamable_http_server::{
-    StreamableHttpService,
-    session::local::LocalSessionManager,
+    StreamableHttpService, session::local::LocalSessionManager,
 };
-use tracing_subscriber::EnvFilter;
+use rmcp::{ServiceExt, transport::stdio};
+use std::path::PathBuf;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{EnvFilter, fmt};
 
 // Axum is brought in by rmcp's transport-streamable-http-server feature
 use axum;
@@ -27,6 +32,70 @@ struct Cli {
     /// Port to bind to (HTTP mode only)
     #[arg(long, default_value_t = 8080)]
     port: u16,
+
+    /// Directory for the persistent transcript and call cache (overrides GONG_CACHE_DIR)
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+
+    /// SQLite database used by `sync` and `--offline` (overrides GONG_MIRROR_DB)
+    #[arg(long, global = true)]
+    mirror_db: Option<PathBuf>,
+
+    /// Serve search_calls and transcripts from the local mirror instead of the Gong API
+    #[arg(long)]
+    offline: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Mirror calls and transcripts into the local SQLite database
+    Sync {
+        /// Start date (YYYY-MM-DD or RFC 3339), required for the first sync; without it later runs continue from the last synced call
+        #[arg(long)]
+        since: Option<String>,
+    },
+    /// Export calls, parties, transcript sentences or users to a flat file
+    ///
+    /// Rerun the same command to resume an interrupted export.
+    Export {
+        /// What to export: one row per call, party, transcript sentence or user
+        #[arg(value_parser = ["calls", "parties", "transcripts", "users"])]
+        entity: String,
+
+        /// Only export calls started at or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only export calls started before this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Output format
+        #[arg(long, default_value = "jsonl", value_parser = ["jsonl", "csv", "parquet"])]
+        format: String,
+
+        /// Output file
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// Manage the persistent on-disk cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// Remove cached transcripts and call metadata
+    Purge {
+        /// Only remove entries of this kind
+        #[arg(long, value_parser = ["transcripts", "calls"])]
+        kind: Option<String>,
+    },
 }
 
 /// Determines default host based on environment
@@ -48,20 +117,66 @@ fn default_host() -> String {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
+    // Initialize tracing: stderr, and MCP sessions that asked for log messages
+    let stderr = fmt::layer()
         .with_writer(std::io::stderr)
         .with_ansi(false)
+        .with_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()));
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(gong_mcp::logging::layer())
         .init();
 
     // Parse CLI arguments
     let cli = Cli::parse();
 
+    let cache_dir = cli
+        .cache_dir
+        .clone()
+        .or_else(|| std::env::var_os(disk_cache::CACHE_DIR_ENV).map(PathBuf::from));
+
+    let mirror_db = cli
+        .mirror_db
+        .clone()
+        .or_else(|| std::env::var_os(mirror::MIRROR_DB_ENV).map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(mirror::DEFAULT_MIRROR_DB));
+
+    match cli.command {
+        Some(Command::Cache { action }) => return run_cache_command(action, cache_dir),
+        Some(Command::Sync { since }) => return run_sync(mirror_db, since).await,
+        Some(Command::Export {
+            entity,
+            from,
+            to,
+            format,
+            out,
+        }) => {
+            let options = ExportOptions {
+                entity: ExportEntity::parse(&entity).expect("validated by clap"),
+                format: ExportFormat::parse(&format).expect("validated by clap"),
+                from_date_time: from,
+                to_date_time: to,
+                out,
+            };
+            return run_export(options, cli.cache_dir).await;
+        }
+        None => {}
+    }
+
     tracing::info!("Starting Gong MCP server in {} mode", cli.mode);
 
     // Create the Gong server
-    let server = GongServer::new();
+    let mut server = GongServer::new();
+    if let Some(dir) = cli.cache_dir {
+        server = server.with_disk_cache(DiskCache::open(dir, disk_cache::max_bytes_from_env())?);
+    }
+    if cli.offline {
+        tracing::info!("Offline mode: serving calls from {}", mirror_db.display());
+        server = server.with_mirror(Mirror::open(mirror_db)?, true);
+    } else if mirror_db.exists() {
+        // An existing mirror provides the transcript search index
+        server = server.with_mirror(Mirror::open(mirror_db)?, false);
+    }
 
     match cli.mode.as_str() {
         "stdio" => {
@@ -82,15 +197,26 @@ async fn main() -> Result<()> {
             tracing::info!("Using Streamable HTTP transport on http://{}", addr);
             tracing::info!("HTTP endpoint: http://{}/mcp", addr);
 
-            // Create the streamable HTTP service
+            // Sessions created below share the server's webhook log
+            let verifier = WebhookVerifier::from_env();
+            if verifier.is_some() {
+                server = server.with_webhooks();
+                tracing::info!("Webhook endpoint: http://{}{}", addr, webhook::WEBHOOK_PATH);
+            }
+            let webhook_server = server.clone();
+
+            // Create the streamable HTTP service, with its own subscriptions per session
             let service = StreamableHttpService::new(
-                move || Ok(server.clone()),
+                move || Ok(server.new_session()),
                 LocalSessionManager::default().into(),
                 Default::default(),
             );
 
             // Create router and nest service under /mcp
-            let router = axum::Router::new().nest_service("/mcp", service);
+            let mut router = axum::Router::new().nest_service("/mcp", service);
+            if let Some(verifier) = verifier {
+                router = router.merge(webhook::router(webhook_server, verifier));
+            }
 
             // Bind to address
             let listener = tokio::net::TcpListener::bind(addr).await?;
@@ -110,3 +236,65 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Mirror calls and transcripts into the local database
+async fn run_sync(mirror_db: PathBuf, since: Option<String>) -> Result<()> {
+    let mirror = Mirror::open(mirror_db)?;
+    let stats = GongServer::new()
+        .sync_mirror(&mirror, since.as_deref())
+        .await?;
+
+    println!(
+        "Synced {} calls and {} transcripts into {} (up to {})",
+        stats.calls,
+        stats.transcripts,
+        mirror.path().display(),
+        stats.high_water_mark.as_deref().unwrap_or("no calls yet")
+    );
+    Ok(())
+}
+
+/// Export Gong data to a flat file, resuming an interrupted export if present
+async fn run_export(options: ExportOptions, cache_dir: Option<PathBuf>) -> Result<()> {
+    let mut server = GongServer::new();
+    if let Some(dir) = cache_dir {
+        // Transcripts fetched during earlier exports are reused
+        server = server.with_disk_cache(DiskCache::open(dir, disk_cache::max_bytes_from_env())?);
+    }
+
+    let out = options.out.clone();
+    let stats = server.export(options).await?;
+    println!(
+        "Exported {} rows to {}{}",
+        stats.rows,
+        out.display(),
+        if stats.resumed { " (resumed)" } else { "" }
+    );
+    Ok(())
+}
+
+/// Run a `cache` subcommand against the configured cache directory
+fn run_cache_command(action: CacheAction, cache_dir: Option<PathBuf>) -> Result<()> {
+    let Some(dir) = cache_dir else {
+        anyhow::bail!(
+            "No cache directory configured. Use --cache-dir or set {}",
+            disk_cache::CACHE_DIR_ENV
+        );
+    };
+    let cache = DiskCache::open(dir, disk_cache::max_bytes_from_env())?;
+
+    match action {
+        CacheAction::Purge { kind } => {
+            let kind = kind.as_deref().and_then(DiskKind::parse);
+            let stats = cache.purge(kind)?;
+            println!(
+                "Removed {} cached entries ({} bytes) from {}",
+                stats.files,
+                stats.bytes,
+                cache.dir().display()
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,713 @@
+//! Local SQLite mirror of Gong calls and transcripts.
+//!
+//! `gong-mcp sync` copies calls (metadata, parties and AI content) and their
+//! transcripts into a SQLite database. Each run remembers the latest `started`
+//! timestamp it has seen, so later runs only fetch newer calls. With
+//! `--offline` the server answers `search_calls` and transcript reads from the
+//! mirror instead of the Gong API.
+//!
+//! Calls and transcripts are stored as the JSON returned by Gong, next to a
+//! few indexed columns used for filtering. Transcripts are also added to the
+//! full-text index in [`crate::search_index`].
+
+use crate::search_index::{self, IndexQuery, PassageHit};
+use crate::sql_query::{self, QueryResult};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Environment variable selecting the mirror database
+pub const MIRROR_DB_ENV: &str = "GONG_MIRROR_DB";
+
+/// Database used when neither `--mirror-db` nor `GONG_MIRROR_DB` is set
+pub const DEFAULT_MIRROR_DB: &str = "gong-mirror.db";
+
+/// Bump when the schema changes, and migrate older databases in `init`
+const SCHEMA_VERSION: i32 = 1;
+
+/// Number of calls per page when serving `search_calls` from the mirror
+const OFFLINE_PAGE_SIZE: usize = 100;
+
+const OFFLINE_CURSOR_PREFIX: &str = "offline:";
+
+const HIGH_WATER_MARK_KEY: &str = "calls.started";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS calls (
+    id TEXT PRIMARY KEY,
+    started TEXT,
+    title TEXT,
+    url TEXT,
+    direction TEXT,
+    scope TEXT,
+    duration INTEGER,
+    primary_user_id TEXT,
+    workspace_id TEXT,
+    data TEXT NOT NULL,
+    synced_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS calls_started ON calls (started);
+CREATE INDEX IF NOT EXISTS calls_primary_user_id ON calls (primary_user_id);
+
+CREATE TABLE IF NOT EXISTS parties (
+    call_id TEXT NOT NULL REFERENCES calls (id) ON DELETE CASCADE,
+    party_id TEXT NOT NULL,
+    name TEXT,
+    email_address TEXT,
+    affiliation TEXT,
+    speaker_id TEXT,
+    user_id TEXT,
+    PRIMARY KEY (call_id, party_id)
+);
+CREATE INDEX IF NOT EXISTS parties_email_address ON parties (email_address);
+
+CREATE TABLE IF NOT EXISTS transcripts (
+    call_id TEXT PRIMARY KEY REFERENCES calls (id) ON DELETE CASCADE,
+    data TEXT NOT NULL,
+    synced_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS sentences (
+    call_id TEXT NOT NULL REFERENCES calls (id) ON DELETE CASCADE,
+    speaker_id TEXT,
+    start_ms INTEGER NOT NULL,
+    end_ms INTEGER NOT NULL,
+    text TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS sentences_call_id ON sentences (call_id);
+
+CREATE TABLE IF NOT EXISTS sync_state (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
+
+/// Filters supported when searching the mirror, mirroring `search_calls`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CallQuery {
+    pub from_date_time: Option<String>,
+    pub to_date_time: Option<String>,
+    pub workspace_id: Option<String>,
+    pub call_ids: Option<Vec<String>>,
+    pub primary_user_ids: Option<Vec<String>>,
+}
+
+/// Row counts and sync progress of a mirror
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorStats {
+    pub calls: usize,
+    pub transcripts: usize,
+    pub passages: usize,
+    pub high_water_mark: Option<String>,
+}
+
+/// Result of a `sync` run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncStats {
+    pub calls: usize,
+    pub transcripts: usize,
+    pub high_water_mark: Option<String>,
+}
+
+/// A local SQLite copy of Gong calls and transcripts
+pub struct Mirror {
+    path: PathBuf,
+    conn: Mutex<Connection>,
+}
+
+impl Mirror {
+    /// Open (and create if needed) a mirror database
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let conn = Connection::open(&path)
+            .with_context(|| format!("failed to open mirror database {}", path.display()))?;
+        Self::init(path, conn)
+    }
+
+    #[cfg(test)]
+    fn open_in_memory() -> Result<Self> {
+        Self::init(PathBuf::from(":memory:"), Connection::open_in_memory()?)
+    }
+
+    fn init(path: PathBuf, conn: Connection) -> Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            bail!(
+                "mirror database {} was created by a newer gong-mcp (schema {}, supported {})",
+                path.display(),
+                version,
+                SCHEMA_VERSION
+            );
+        }
+        conn.execute_batch(SCHEMA)?;
+        conn.execute_batch(search_index::SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+        Ok(Self {
+            path,
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Insert or update calls as returned by `list_calls_extensive`
+    ///
+    /// Returns the latest normalized `started` timestamp among the calls.
+    pub(crate) fn upsert_calls(&self, calls: &[Value]) -> Result<Option<String>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let synced_at = now();
+        let mut latest: Option<String> = None;
+
+        for call in calls {
+            let meta = &call["metaData"];
+            let Some(id) = meta["id"].as_str() else {
+                continue;
+            };
+            let started = meta["started"].as_str().and_then(normalize_timestamp);
+
+            tx.execute(
+                "INSERT INTO calls (id, started, title, url, direction, scope, duration,
+                                    primary_user_id, workspace_id, data, synced_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                 ON CONFLICT (id) DO UPDATE SET
+                    started = excluded.started, title = excluded.title, url = excluded.url,
+                    direction = excluded.direction, scope = excluded.scope,
+                    duration = excluded.duration, primary_user_id = excluded.primary_user_id,
+                    workspace_id = excluded.workspace_id, data = excluded.data,
+                    synced_at = excluded.synced_at",
+                params![
+                    id,
+                    started,
+                    meta["title"].as_str(),
+                    meta["url"].as_str(),
+                    meta["direction"].as_str(),
+                    meta["scope"].as_str(),
+                    meta["duration"].as_i64(),
+                    meta["primaryUserId"].as_str(),
+                    meta["workspaceId"].as_str(),
+                    call.to_string(),
+                    synced_at,
+                ],
+            )?;
+
+            tx.execute("DELETE FROM parties WHERE call_id = ?1", params![id])?;
+            for party in call["parties"].as_array().into_iter().flatten() {
+                let Some(party_id) = party["id"].as_str() else {
+                    continue;
+                };
+                tx.execute(
+                    "INSERT OR REPLACE INTO parties
+                        (call_id, party_id, name, email_address, affiliation, speaker_id, user_id)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        id,
+                        party_id,
+                        party["name"].as_str(),
+                        party["emailAddress"].as_str(),
+                        party["affiliation"].as_str(),
+                        party["speakerId"].as_str(),
+                        party["userId"].as_str(),
+                    ],
+                )?;
+            }
+
+            if let Some(started) = started
+                && latest.as_ref().is_none_or(|l| started > *l)
+            {
+                latest = Some(started);
+            }
+        }
+
+        tx.commit()?;
+        Ok(latest)
+    }
+
+    /// Store the transcript of one call, in the shape of a single-call response
+    pub(crate) fn upsert_transcript(&self, call_id: &str, transcript: &Value) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO transcripts (call_id, data, synced_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (call_id) DO UPDATE SET
+                data = excluded.data, synced_at = excluded.synced_at",
+            params![call_id, transcript.to_string(), now()],
+        )?;
+        store_sentences(&tx, call_id, transcript)?;
+        search_index::index_transcript(&tx, call_id, transcript)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Search the full-text index of mirrored transcripts
+    pub(crate) fn search_passages(&self, query: &IndexQuery) -> Result<Vec<PassageHit>> {
+        let conn = self.conn.lock().unwrap();
+        search_index::search(&conn, query)
+    }
+
+    /// Run an analyst's read-only SELECT with the standard time limit
+    pub(crate) fn query_sql(&self, sql: &str, max_rows: usize) -> Result<QueryResult> {
+        let conn = self.conn.lock().unwrap();
+        sql_query::run_query(&conn, sql, max_rows, sql_query::QUERY_TIMEOUT)
+    }
+
+    pub(crate) fn transcript(&self, call_id: &str) -> Result<Option<Value>> {
+        let conn = self.conn.lock().unwrap();
+        let data: Option<String> = conn
+            .query_row(
+                "SELECT data FROM transcripts WHERE call_id = ?1",
+                params![call_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        data.map(|d| serde_json::from_str(&d).context("corrupt transcript in mirror"))
+            .transpose()
+    }
+
+    /// Calls started at or after `since` that have no transcript yet
+    pub(crate) fn calls_without_transcript(&self, since: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id FROM calls
+             WHERE started >= ?1 AND id NOT IN (SELECT call_id FROM transcripts)
+             ORDER BY started",
+        )?;
+        let ids = stmt
+            .query_map(params![since], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+
+    pub(crate) fn high_water_mark(&self) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let value = conn
+            .query_row(
+                "SELECT value FROM sync_state WHERE key = ?1",
+                params![HIGH_WATER_MARK_KEY],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    /// Advance the high-water mark; it never moves backwards
+    pub(crate) fn advance_high_water_mark(&self, started: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO sync_state (key, value) VALUES (?1, ?2)
+             ON CONFLICT (key) DO UPDATE SET value = max(value, excluded.value)",
+            params![HIGH_WATER_MARK_KEY, started],
+        )?;
+        Ok(())
+    }
+
+    /// Search calls and return a page shaped like a `list_calls_extensive` response
+    pub(crate) fn search_calls(&self, query: &CallQuery, cursor: Option<&str>) -> Result<Value> {
+        let offset = match cursor {
+            None => 0,
+            Some(cursor) => cursor
+                .strip_prefix(OFFLINE_CURSOR_PREFIX)
+                .and_then(|n| n.parse::<usize>().ok())
+                .with_context(|| format!("invalid offline cursor '{}'", cursor))?,
+        };
+
+        let mut conditions: Vec<String> = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+
+        if let Some(from) = &query.from_date_time {
+            conditions.push("started >= ?".to_string());
+            values.push(
+                normalize_timestamp(from)
+                    .with_context(|| format!("invalid from_date_time '{}'", from))?,
+            );
+        }
+        if let Some(to) = &query.to_date_time {
+            conditions.push("started < ?".to_string());
+            values.push(
+                normalize_timestamp(to)
+                    .with_context(|| format!("invalid to_date_time '{}'", to))?,
+            );
+        }
+        if let Some(workspace_id) = &query.workspace_id {
+            conditions.push("workspace_id = ?".to_string());
+            values.push(workspace_id.clone());
+        }
+        for (column, ids) in [
+            ("id", &query.call_ids),
+            ("primary_user_id", &query.primary_user_ids),
+        ] {
+            if let Some(ids) = ids {
+                let placeholders = vec!["?"; ids.len()].join(", ");
+                conditions.push(format!("{} IN ({})", column, placeholders));
+                values.extend(ids.iter().cloned());
+            }
+        }
+
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let conn = self.conn.lock().unwrap();
+        let total: usize = conn.query_row(
+            &format!("SELECT COUNT(*) FROM calls {}", filter),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT data FROM calls {} ORDER BY started, id LIMIT {} OFFSET {}",
+            filter, OFFLINE_PAGE_SIZE, offset
+        ))?;
+        let calls = stmt
+            .query_map(params_from_iter(values.iter()), |row| {
+                row.get::<_, String>(0)
+            })?
+            .map(|data| Ok(serde_json::from_str::<Value>(&data?)?))
+            .collect::<Result<Vec<Value>>>()?;
+
+        let next = offset + calls.len();
+        let cursor = (next < total).then(|| format!("{}{}", OFFLINE_CURSOR_PREFIX, next));
+
+        Ok(json!({
+            "requestId": "offline",
+            "records": {
+                "totalRecords": total,
+                "currentPageSize": calls.len(),
+                "currentPageNumber": offset / OFFLINE_PAGE_SIZE,
+                "cursor": cursor,
+            },
+            "calls": calls,
+        }))
+    }
+
+    /// Transcripts of a page of matching calls, shaped like a `get_call_transcripts` response
+    ///
+    /// Calls without a mirrored transcript are left out, as Gong does.
+    pub(crate) fn transcripts_page(
+        &self,
+        query: &CallQuery,
+        cursor: Option<&str>,
+    ) -> Result<Value> {
+        let page = self.search_calls(query, cursor)?;
+
+        let mut transcripts = Vec::new();
+        for call in page["calls"].as_array().into_iter().flatten() {
+            let Some(call_id) = call["metaData"]["id"].as_str() else {
+                continue;
+            };
+            if let Some(transcript) = self.transcript(call_id)? {
+                transcripts.extend(
+                    transcript["callTranscripts"]
+                        .as_array()
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+            }
+        }
+
+        Ok(json!({
+            "requestId": "offline",
+            "records": page["records"],
+            "callTranscripts": transcripts,
+        }))
+    }
+
+    pub fn stats(&self) -> Result<MirrorStats> {
+        let (calls, transcripts, passages) = {
+            let conn = self.conn.lock().unwrap();
+            let calls = conn.query_row("SELECT COUNT(*) FROM calls", [], |row| row.get(0))?;
+            let transcripts =
+                conn.query_row("SELECT COUNT(*) FROM transcripts", [], |row| row.get(0))?;
+            (calls, transcripts, search_index::passage_count(&conn)?)
+        };
+
+        Ok(MirrorStats {
+            calls,
+            transcripts,
+            passages,
+            high_water_mark: self.high_water_mark()?,
+        })
+    }
+
+    /// Mirror statistics for the `gong://status` resource
+    pub(crate) fn status(&self) -> Value {
+        match self.stats() {
+            Ok(stats) => json!({
+                "path": self.path.display().to_string(),
+                "calls": stats.calls,
+                "transcripts": stats.transcripts,
+                "indexedPassages": stats.passages,
+                "highWaterMark": stats.high_water_mark,
+            }),
+            Err(e) => json!({
+                "path": self.path.display().to_string(),
+                "error": e.to_string(),
+            }),
+        }
+    }
+}
+
+/// Replace the rows of the `sentences` table for one call
+fn store_sentences(conn: &Connection, call_id: &str, transcript: &Value) -> Result<()> {
+    conn.execute("DELETE FROM sentences WHERE call_id = ?1", params![call_id])?;
+
+    let mut insert = conn.prepare(
+        "INSERT INTO sentences (call_id, speaker_id, start_ms, end_ms, text)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    let monologues = transcript["callTranscripts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|t| t["transcript"].as_array().into_iter().flatten());
+    for monologue in monologues {
+        for sentence in monologue["sentences"].as_array().into_iter().flatten() {
+            insert.execute(params![
+                call_id,
+                monologue["speakerId"].as_str(),
+                sentence["start"].as_f64().unwrap_or(0.0) as i64,
+                sentence["end"].as_f64().unwrap_or(0.0) as i64,
+                sentence["text"].as_str().unwrap_or_default(),
+            ])?;
+        }
+    }
+    Ok(())
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Normalize an RFC 3339 timestamp or a plain date to UTC for comparisons
+///
+/// Gong reports `started` in the call's local offset, so timestamps are
+/// converted to UTC before they are stored or compared as strings.
+pub fn normalize_timestamp(value: &str) -> Option<String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(
+            timestamp
+                .with_timezone(&Utc)
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+        );
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc().to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// Split a transcripts response into one single-call response per call
+///
+/// Calls without transcript (not yet processed by Gong) are skipped. The
+/// response shape is kept so each part deserializes like a single-call
+/// response.
+pub(crate) fn split_transcripts(response: &Value) -> Vec<(String, Value)> {
+    let Some(response) = response.as_object() else {
+        return Vec::new();
+    };
+
+    response
+        .get("callTranscripts")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let call_id = item.get("callId").and_then(|v| v.as_str())?;
+            let has_transcript = item
+                .get("transcript")
+                .and_then(|v| v.as_array())
+                .is_some_and(|monologues| !monologues.is_empty());
+            if !has_transcript {
+                return None;
+            }
+
+            let mut single = response.clone();
+            single.insert("callTranscripts".to_string(), json!([item]));
+            if let Some(records) = single.get_mut("records").and_then(|r| r.as_object_mut()) {
+                records.remove("cursor");
+            }
+            Some((call_id.to_string(), Value::Object(single)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(id: &str, started: &str, primary_user_id: &str) -> Value {
+        json!({
+            "metaData": {
+                "id": id,
+                "started": started,
+                "title": format!("Call {}", id),
+                "duration": 1800,
+                "primaryUserId": primary_user_id,
+                "workspaceId": "ws-1",
+            },
+            "parties": [
+                {"id": "p1", "name": "Rep", "affiliation": "Internal", "speakerId": "s1"},
+                {"id": "p2", "name": "Buyer", "affiliation": "External", "speakerId": "s2"},
+            ],
+        })
+    }
+
+    #[test]
+    fn test_normalize_timestamp() {
+        assert_eq!(
+            normalize_timestamp("2024-01-15T10:00:00-08:00").as_deref(),
+            Some("2024-01-15T18:00:00Z")
+        );
+        assert_eq!(
+            normalize_timestamp("2024-01-01").as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(normalize_timestamp("last week"), None);
+    }
+
+    #[test]
+    fn test_upsert_calls_tracks_latest_start() {
+        let mirror = Mirror::open_in_memory().unwrap();
+        let latest = mirror
+            .upsert_calls(&[
+                call("1", "2024-01-15T10:00:00-08:00", "u1"),
+                call("2", "2024-01-15T12:00:00Z", "u2"),
+            ])
+            .unwrap();
+        assert_eq!(latest.as_deref(), Some("2024-01-15T18:00:00Z"));
+
+        // Upserting again updates rows instead of duplicating them
+        mirror
+            .upsert_calls(&[call("1", "2024-01-15T10:00:00-08:00", "u1")])
+            .unwrap();
+        assert_eq!(mirror.stats().unwrap().calls, 2);
+    }
+
+    #[test]
+    fn test_high_water_mark_never_moves_backwards() {
+        let mirror = Mirror::open_in_memory().unwrap();
+        assert_eq!(mirror.high_water_mark().unwrap(), None);
+
+        mirror
+            .advance_high_water_mark("2024-02-01T00:00:00Z")
+            .unwrap();
+        mirror
+            .advance_high_water_mark("2024-01-01T00:00:00Z")
+            .unwrap();
+        assert_eq!(
+            mirror.high_water_mark().unwrap().as_deref(),
+            Some("2024-02-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_search_calls_filters_and_paginates() {
+        let mirror = Mirror::open_in_memory().unwrap();
+        let calls: Vec<Value> = (0..150)
+            .map(|i| {
+                call(
+                    &i.to_string(),
+                    &format!("2024-01-01T00:{:02}:{:02}Z", i / 60, i % 60),
+                    if i % 2 == 0 { "even" } else { "odd" },
+                )
+            })
+            .collect();
+        mirror.upsert_calls(&calls).unwrap();
+
+        let page = mirror.search_calls(&CallQuery::default(), None).unwrap();
+        assert_eq!(page["records"]["totalRecords"], 150);
+        assert_eq!(page["calls"].as_array().unwrap().len(), OFFLINE_PAGE_SIZE);
+        assert_eq!(page["records"]["cursor"], "offline:100");
+
+        let page = mirror
+            .search_calls(&CallQuery::default(), Some("offline:100"))
+            .unwrap();
+        assert_eq!(page["calls"].as_array().unwrap().len(), 50);
+        assert!(page["records"]["cursor"].is_null());
+
+        let query = CallQuery {
+            from_date_time: Some("2024-01-01T00:01:00Z".to_string()),
+            primary_user_ids: Some(vec!["even".to_string()]),
+            ..Default::default()
+        };
+        let page = mirror.search_calls(&query, None).unwrap();
+        assert_eq!(page["records"]["totalRecords"], 45);
+        assert_eq!(page["calls"][0]["metaData"]["id"], "60");
+
+        assert!(
+            mirror
+                .search_calls(&CallQuery::default(), Some("abc"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_transcripts_round_trip() {
+        let mirror = Mirror::open_in_memory().unwrap();
+        mirror
+            .upsert_calls(&[
+                call("1", "2024-01-15T10:00:00Z", "u1"),
+                call("2", "2024-01-16T10:00:00Z", "u1"),
+            ])
+            .unwrap();
+
+        let response = json!({
+            "requestId": "r1",
+            "records": {"totalRecords": 2, "cursor": "next"},
+            "callTranscripts": [
+                {"callId": "1", "transcript": [{"speakerId": "s1", "sentences": [
+                    {"start": 1000, "end": 2500, "text": "Hello."}
+                ]}]},
+                {"callId": "2", "transcript": []},
+            ],
+        });
+        let parts = split_transcripts(&response);
+        assert_eq!(parts.len(), 1);
+        assert!(parts[0].1["records"].get("cursor").is_none());
+
+        for (call_id, transcript) in &parts {
+            mirror.upsert_transcript(call_id, transcript).unwrap();
+        }
+        assert_eq!(
+            mirror.transcript("1").unwrap().unwrap()["callTranscripts"][0]["callId"],
+            "1"
+        );
+        assert_eq!(mirror.transcript("2").unwrap(), None);
+
+        let result = mirror
+            .query_sql(
+                "SELECT call_id, speaker_id, start_ms, text FROM sentences",
+                10,
+            )
+            .unwrap();
+        assert_eq!(
+            result.rows,
+            vec![vec![json!("1"), json!("s1"), json!(1000), json!("Hello.")]]
+        );
+        assert_eq!(
+            mirror
+                .calls_without_transcript("2024-01-01T00:00:00Z")
+                .unwrap(),
+            vec!["2".to_string()]
+        );
+
+        let query = CallQuery {
+            call_ids: Some(vec!["1".to_string(), "2".to_string()]),
+            ..Default::default()
+        };
+        let page = mirror.transcripts_page(&query, None).unwrap();
+        assert_eq!(page["callTranscripts"].as_array().unwrap().len(), 1);
+        assert_eq!(page["records"]["totalRecords"], 2);
+    }
+}
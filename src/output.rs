@@ -0,0 +1,363 @@
+//! Structured tool results.
+//!
+//! Every tool returns one of the structs below as `structuredContent` and
+//! advertises the schema derived from it as its `outputSchema`, so clients
+//! can use the result without parsing text. The same JSON is also sent as
+//! text for clients that predate structured output. Parts rendered by the
+//! analytics, transcript and tracker modules are kept as JSON objects.
+
+use crate::analytics::Bucket;
+use rmcp::model::{CallToolResult, Content};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+/// A successful tool result carrying `output` as structured content
+pub(crate) fn result<T: Serialize>(output: &T) -> CallToolResult {
+    let value = serde_json::to_value(output).unwrap();
+    CallToolResult {
+        content: vec![Content::text(serde_json::to_string_pretty(&value).unwrap())],
+        structured_content: Some(value),
+        is_error: None,
+        meta: None,
+    }
+}
+
+/// Result of `search_calls`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SearchCallsOutput {
+    pub calls: Vec<CallSummary>,
+    /// Number of calls returned, after `limit` was applied
+    pub count: usize,
+    /// Number of calls in the current API page before `limit` was applied (typically 100)
+    pub total_available: usize,
+    /// Whether `limit` reduced the result set
+    pub truncated: bool,
+    /// Pass as `cursor` to fetch the next page
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+    /// The filters the search was run with
+    pub filters: SearchCallsFilters,
+}
+
+/// Basic metadata of a call
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CallSummary {
+    pub id: String,
+    pub title: String,
+    /// Start time in ISO 8601, in the call's own UTC offset
+    pub started: String,
+    /// Duration in seconds
+    pub duration: i64,
+    /// `Inbound`, `Outbound`, `Conference` or `Unknown`
+    pub direction: String,
+    pub participants: Vec<Participant>,
+    /// Participant counts: `total`, `internal` and `external`
+    pub participant_summary: Value,
+    /// Link to the call in Gong
+    pub url: String,
+}
+
+/// A party of a call
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Participant {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub email_address: Option<String>,
+    pub title: Option<String>,
+    /// `Internal`, `External` or `Unknown`
+    pub affiliation: Option<String>,
+    /// Speaker ID used in the transcript
+    pub speaker_id: Option<String>,
+    /// Gong user ID, for internal participants
+    pub user_id: Option<String>,
+}
+
+/// Filters echoed back by `search_calls`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct SearchCallsFilters {
+    pub from_date_time: Option<String>,
+    pub to_date_time: Option<String>,
+    pub workspace_id: Option<String>,
+    pub call_ids: Option<Vec<String>>,
+    pub primary_user_ids: Option<Vec<String>>,
+    pub limit: Option<usize>,
+    pub include_structure: bool,
+}
+
+/// Result of `call_analytics`
+///
+/// Besides the fields below, the result has the aggregated metrics: `bucket`,
+/// `totals`, and `byPeriod` and `byPrimaryUser` with the same metrics per
+/// time bucket and per primary user.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CallAnalyticsOutput {
+    #[serde(flatten)]
+    pub analytics: Value,
+    /// Number of API pages aggregated
+    pub pages_fetched: usize,
+    /// False if `max_pages` was reached or the request was cancelled before all calls in the range were aggregated
+    pub complete: bool,
+    /// True if the client cancelled the request and the result is partial
+    pub cancelled: bool,
+    /// The filters the calls were aggregated with
+    pub filters: CallAnalyticsFilters,
+}
+
+/// Filters echoed back by `call_analytics`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct CallAnalyticsFilters {
+    pub from_date_time: String,
+    pub to_date_time: Option<String>,
+    pub workspace_id: Option<String>,
+    pub primary_user_ids: Option<Vec<String>>,
+    pub bucket: Bucket,
+    pub max_pages: usize,
+}
+
+/// Result of `transcript_metrics`
+///
+/// Besides the call ID, the result has the metrics: `durationMs`,
+/// `totalTalkTimeMs`, `speakers`, `talkRatio`, `questions`,
+/// `longestMonologue`, `speakerSwitches` and `speakerSwitchesPerMinute`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TranscriptMetricsOutput {
+    pub call_id: String,
+    #[serde(flatten)]
+    pub metrics: Value,
+}
+
+/// Result of `run_local_trackers`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LocalTrackersOutput {
+    /// Names of the trackers that were run
+    pub trackers: Vec<String>,
+    pub calls: Vec<TrackerCallResult>,
+    pub total_hits: usize,
+    /// True if the client cancelled the request and the result is partial
+    pub cancelled: bool,
+}
+
+/// Tracker hits in one call
+///
+/// Besides the fields below, the result has `counts` (hits per tracker) and
+/// `hits`, each with tracker, timestamp, speaker and sentence.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TrackerCallResult {
+    pub call_id: String,
+    pub has_transcript: bool,
+    #[serde(flatten)]
+    pub hits: Value,
+}
+
+/// Result of `compare_calls`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CompareCallsOutput {
+    /// The compared call IDs, without duplicates
+    pub call_ids: Vec<String>,
+    /// The calls found, in the requested order
+    pub calls: Vec<ComparedCall>,
+    /// Requested calls Gong did not return
+    pub not_found: Vec<String>,
+    /// True if the client cancelled the request and the result is partial
+    pub cancelled: bool,
+}
+
+/// One call laid out for comparison
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ComparedCall {
+    pub id: String,
+    pub title: Option<String>,
+    pub started: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<i64>,
+    pub direction: Option<String>,
+    pub primary_user_id: Option<String>,
+    pub url: Option<String>,
+    pub participants: Vec<ComparedParticipant>,
+    /// Participant counts: `total`, `internal` and `external`
+    pub participant_summary: Value,
+    /// Participants who joined or left since the previous call; null for the first call
+    pub participant_changes: Option<ParticipantChanges>,
+    /// Share of talk time by `internal`, `external` and `unknown` speakers, from the transcript
+    pub talk_ratio: Option<Value>,
+    /// Questions asked by `internal`, `external` and `unknown` speakers, from the transcript
+    pub questions: Option<Value>,
+    pub longest_monologue: Option<Value>,
+    /// Gong topics
+    pub topics: Option<Value>,
+    /// Gong trackers
+    pub trackers: Option<Value>,
+    /// Gong call brief
+    pub brief: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ComparedParticipant {
+    pub name: Option<String>,
+    pub email_address: Option<String>,
+    pub title: Option<String>,
+    pub affiliation: Option<String>,
+}
+
+/// Participants, by email address or name, who joined or left between two calls
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ParticipantChanges {
+    pub joined: Vec<String>,
+    pub left: Vec<String>,
+}
+
+/// Result of `get_transcripts`
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GetTranscriptsOutput {
+    pub transcripts: Vec<TranscriptText>,
+    pub count: usize,
+    pub total_token_estimate: usize,
+    /// Whether more transcripts matched than were returned
+    pub truncated: bool,
+    /// Calls left out because of `max_calls` or `max_tokens`
+    pub omitted_call_ids: Vec<String>,
+    /// Requested calls without a transcript
+    pub missing_call_ids: Vec<String>,
+    /// True if the client cancelled the request and the result is partial
+    pub cancelled: bool,
+}
+
+/// A transcript rendered as compact text
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TranscriptText {
+    pub call_id: String,
+    pub title: Option<String>,
+    pub started: Option<String>,
+    pub sentence_count: usize,
+    pub token_estimate: usize,
+    /// One line per speaker turn: `[mm:ss] Name (Affiliation): ...`
+    pub transcript: String,
+}
+
+/// Result of `search_index`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SearchIndexOutput {
+    pub query: String,
+    /// Best matching passages first
+    pub results: Vec<PassageResult>,
+    pub count: usize,
+}
+
+/// A transcript passage matching a `search_index` query
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PassageResult {
+    pub call_id: String,
+    pub call_title: Option<String>,
+    pub call_started: Option<String>,
+    /// Offset from the start of the call, in milliseconds
+    pub start_ms: i64,
+    /// `start_ms` as `mm:ss`
+    pub timestamp: String,
+    pub speaker_id: Option<String>,
+    pub speaker_name: Option<String>,
+    /// `Internal`, `External` or `Unknown`
+    pub affiliation: String,
+    /// BM25 relevance, higher is better
+    pub score: f64,
+    pub text: String,
+    pub context: PassageContext,
+}
+
+/// The passages around a search result
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct PassageContext {
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Result of `query_calls_sql`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueryCallsSqlOutput {
+    pub columns: Vec<String>,
+    /// One array of values per row, in column order
+    pub rows: Vec<Vec<Value>>,
+    pub row_count: usize,
+    /// Whether more rows were available than `max_rows`
+    pub truncated: bool,
+    pub elapsed_ms: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::handler::server::common::schema_for_output;
+    use serde_json::json;
+
+    #[test]
+    fn test_result_is_structured_and_text() {
+        let output = QueryCallsSqlOutput {
+            columns: vec!["id".to_string()],
+            rows: vec![vec![json!("1")]],
+            row_count: 1,
+            truncated: false,
+            elapsed_ms: 3,
+        };
+        let result = result(&output);
+
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["rowCount"], 1);
+        assert_eq!(structured["elapsedMs"], 3);
+        let text = &result.content[0].as_text().unwrap().text;
+        assert_eq!(serde_json::from_str::<Value>(text).unwrap(), structured);
+    }
+
+    #[test]
+    fn test_flattened_parts_are_inlined() {
+        let output = TranscriptMetricsOutput {
+            call_id: "123".to_string(),
+            metrics: json!({"durationMs": 60000, "speakerSwitches": 4}),
+        };
+        assert_eq!(
+            serde_json::to_value(&output).unwrap(),
+            json!({"callId": "123", "durationMs": 60000, "speakerSwitches": 4})
+        );
+    }
+
+    #[test]
+    fn test_output_schemas_are_objects() {
+        // schema_for_output rejects schemas whose root isn't an object
+        let schemas = [
+            schema_for_output::<SearchCallsOutput>(),
+            schema_for_output::<CallAnalyticsOutput>(),
+            schema_for_output::<TranscriptMetricsOutput>(),
+            schema_for_output::<LocalTrackersOutput>(),
+            schema_for_output::<CompareCallsOutput>(),
+            schema_for_output::<GetTranscriptsOutput>(),
+            schema_for_output::<SearchIndexOutput>(),
+            schema_for_output::<QueryCallsSqlOutput>(),
+        ];
+        for schema in schemas {
+            assert_eq!(schema.unwrap()["type"], "object");
+        }
+
+        let schema = schema_for_output::<SearchCallsOutput>().unwrap();
+        assert!(schema["properties"]["nextCursor"].is_object());
+        assert!(
+            schema["required"]
+                .as_array()
+                .unwrap()
+                .contains(&json!("calls"))
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Progress notifications for long-running tools.
+//!
+//! When a request carries a `progressToken`, tools that page through calls or
+//! fetch transcripts one by one report how far they got with
+//! `notifications/progress`: pages fetched, or calls processed of the
+//! estimated total. Like the cancellation token in [`crate::retry`], the
+//! reporter is passed down in a task-local, set by [`with_reporter`] around
+//! each request handler, so helpers deep in a tool can report without it
+//! being threaded through every call.
+
+use rmcp::model::{ProgressNotificationParam, ProgressToken};
+use rmcp::{Peer, RoleServer};
+use std::future::Future;
+use std::sync::Mutex;
+
+tokio::task_local! {
+    static REPORTER: Reporter;
+}
+
+/// Where to send the progress of one request
+pub(crate) struct Reporter {
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+    progress: Mutex<Progress>,
+}
+
+impl Reporter {
+    pub fn new(peer: Peer<RoleServer>, token: ProgressToken) -> Self {
+        Self {
+            peer,
+            token,
+            progress: Mutex::new(Progress::default()),
+        }
+    }
+}
+
+/// Run `future` with its progress reported to `reporter`, if any
+pub(crate) async fn with_reporter<F: Future>(reporter: Option<Reporter>, future: F) -> F::Output {
+    match reporter {
+        Some(reporter) => REPORTER.scope(reporter, future).await,
+        None => future.await,
+    }
+}
+
+/// Report that `done` of `total` items are processed
+///
+/// Does nothing when the client didn't ask for progress.
+pub(crate) async fn report(done: usize, total: Option<usize>, message: impl Into<String>) {
+    let notification = REPORTER.try_with(|reporter| {
+        if !reporter.progress.lock().unwrap().advance(done) {
+            return None;
+        }
+        let param = ProgressNotificationParam {
+            progress_token: reporter.token.clone(),
+            progress: done as f64,
+            total: total.map(|total| total.max(done) as f64),
+            message: Some(message.into()),
+        };
+        Some((reporter.peer.clone(), param))
+    });
+
+    if let Ok(Some((peer, param))) = notification
+        && let Err(e) = peer.notify_progress(param).await
+    {
+        tracing::debug!("Could not send progress notification: {:?}", e);
+    }
+}
+
+/// The last progress sent, which the protocol requires to increase
+#[derive(Debug, Default)]
+struct Progress {
+    last: Option<usize>,
+}
+
+impl Progress {
+    /// Record `done` and return whether it is more than last reported
+    fn advance(&mut self, done: usize) -> bool {
+        if self.last.is_some_and(|last| done <= last) {
+            return false;
+        }
+        self.last = Some(done);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_only_increases() {
+        let mut progress = Progress::default();
+        assert!(progress.advance(0));
+        assert!(progress.advance(100));
+        assert!(!progress.advance(100));
+        assert!(!progress.advance(50));
+        assert!(progress.advance(101));
+    }
+
+    #[tokio::test]
+    async fn test_report_without_reporter() {
+        // No progress token: nothing to send, and nothing fails
+        report(1, Some(2), "Fetched page 1").await;
+        with_reporter(None, report(2, None, "Fetched page 2")).await;
+    }
+}
@@ -0,0 +1,579 @@
+//! Prompts for common sales workflows.
+//!
+//! Each prompt turns a few arguments into a ready-made request, such as
+//! summarizing a call or reviewing a deal, and the server embeds the
+//! `gong://` resources the workflow needs when the prompt is fetched. Picking
+//! a prompt from the client's menu therefore starts the workflow with the
+//! call data already in context instead of leaving the model to look it up.
+//!
+//! Prompt arguments are strings, so lists and periods are parsed here.
+
+use crate::args;
+use crate::mirror;
+use crate::search_index::PassageHit;
+use crate::transcript;
+use chrono::{Duration, SecondsFormat, Utc};
+use rmcp::ErrorData as McpError;
+use rmcp::model::{JsonObject, Prompt, PromptArgument};
+use serde_json::Value;
+
+/// Maximum number of calls embedded in a single prompt
+pub(crate) const MAX_PROMPT_CALLS: usize = 5;
+
+/// Period covered by `competitor_brief` when none is given
+const DEFAULT_PERIOD_DAYS: i64 = 90;
+
+/// A prompt with its parsed arguments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum WorkflowPrompt {
+    CallSummary {
+        call_id: String,
+    },
+    DealReview {
+        crm_opportunity_id: String,
+        call_ids: Vec<String>,
+    },
+    CoachingFeedback {
+        call_id: String,
+        rep_email: String,
+    },
+    CompetitorBrief {
+        competitor: String,
+        from_date_time: String,
+        to_date_time: String,
+    },
+}
+
+/// The prompts offered by the server
+pub(crate) fn list() -> Vec<Prompt> {
+    vec![
+        Prompt::new(
+            "call_summary",
+            Some("Summarize a call: participants, key points, objections and next steps"),
+            Some(vec![argument(
+                "callId",
+                "ID of the call, as returned by search_calls",
+                true,
+            )]),
+        ),
+        Prompt::new(
+            "deal_review",
+            Some("Review the state of a deal across its calls: stakeholders, risks and next steps"),
+            Some(vec![
+                argument("crmOpportunityId", "ID of the opportunity in the CRM", true),
+                argument(
+                    "callIds",
+                    "Comma-separated IDs of the calls on the deal, up to 5. Without them the recent calls Gong links to the opportunity are embedded.",
+                    false,
+                ),
+            ]),
+        ),
+        Prompt::new(
+            "coaching_feedback",
+            Some(
+                "Coaching feedback for a rep on one call: talk ratio, questions, discovery and objection handling",
+            ),
+            Some(vec![
+                argument(
+                    "callId",
+                    "ID of the call, as returned by search_calls",
+                    true,
+                ),
+                argument("repEmail", "Email address of the rep to coach", true),
+            ]),
+        ),
+        Prompt::new(
+            "competitor_brief",
+            Some(
+                "Brief on how a competitor comes up in calls: who mentions it, in which context, and how reps respond",
+            ),
+            Some(vec![
+                argument("competitor", "Name of the competitor", true),
+                argument(
+                    "period",
+                    "Either the last N days, such as 30d, or a date range such as 2024-01-01/2024-04-01. Defaults to the last 90 days.",
+                    false,
+                ),
+            ]),
+        ),
+    ]
+}
+
+fn argument(name: &str, description: &str, required: bool) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        title: None,
+        description: Some(description.to_string()),
+        required: Some(required),
+    }
+}
+
+impl WorkflowPrompt {
+    /// Parse the arguments of the prompt called `name`
+    pub fn parse(name: &str, arguments: Option<&JsonObject>) -> Result<Self, McpError> {
+        let empty = JsonObject::new();
+        let arguments = arguments.unwrap_or(&empty);
+        let prompt = list()
+            .into_iter()
+            .find(|prompt| prompt.name == name)
+            .ok_or_else(|| {
+                McpError::invalid_params(
+                    "unknown_prompt",
+                    Some(serde_json::json!({
+                        "prompt": name,
+                        "available": list().into_iter().map(|p| p.name).collect::<Vec<_>>(),
+                    })),
+                )
+            })?;
+        let known: Vec<String> = prompt
+            .arguments
+            .into_iter()
+            .flatten()
+            .map(|a| a.name)
+            .collect();
+        if let Some(unknown) = arguments.keys().find(|key| !known.contains(key)) {
+            return Err(args::invalid(
+                unknown,
+                &format!("unknown argument, expected one of {}", known.join(", ")),
+            ));
+        }
+
+        let parsed = match name {
+            "call_summary" => Self::CallSummary {
+                call_id: required(arguments, "callId")?,
+            },
+            "deal_review" => Self::DealReview {
+                crm_opportunity_id: required(arguments, "crmOpportunityId")?,
+                call_ids: call_ids(optional(arguments, "callIds")?.as_deref())?,
+            },
+            "coaching_feedback" => Self::CoachingFeedback {
+                call_id: required(arguments, "callId")?,
+                rep_email: required(arguments, "repEmail")?,
+            },
+            _ => {
+                let (from_date_time, to_date_time) =
+                    period(optional(arguments, "period")?.as_deref())?;
+                Self::CompetitorBrief {
+                    competitor: required(arguments, "competitor")?,
+                    from_date_time,
+                    to_date_time,
+                }
+            }
+        };
+        Ok(parsed)
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Self::CallSummary { call_id } => format!("Summary of call {}", call_id),
+            Self::DealReview {
+                crm_opportunity_id, ..
+            } => format!("Review of opportunity {}", crm_opportunity_id),
+            Self::CoachingFeedback { call_id, rep_email } => {
+                format!("Coaching feedback for {} on call {}", rep_email, call_id)
+            }
+            Self::CompetitorBrief { competitor, .. } => format!("Brief on {}", competitor),
+        }
+    }
+
+    /// The `gong://` resources to embed, besides calls found by searching
+    pub fn resources(&self) -> Vec<String> {
+        match self {
+            Self::CallSummary { call_id } => vec![
+                format!("gong://calls/{}", call_id),
+                format!("gong://calls/{}/transcript", call_id),
+            ],
+            Self::DealReview { call_ids, .. } => call_ids
+                .iter()
+                .map(|call_id| format!("gong://calls/{}", call_id))
+                .collect(),
+            Self::CoachingFeedback { call_id, .. } => vec![
+                format!("gong://calls/{}/participants", call_id),
+                format!("gong://calls/{}/transcript", call_id),
+            ],
+            Self::CompetitorBrief { .. } => Vec::new(),
+        }
+    }
+
+    /// The request to the model
+    ///
+    /// `mentions` are passages found in the local transcript index for
+    /// `competitor_brief`, or `None` without an index.
+    pub fn instructions(&self, mentions: Option<&[PassageHit]>) -> String {
+        match self {
+            Self::CallSummary { call_id } => format!(
+                "Summarize Gong call {}. The call metadata and transcript are attached.\n\n\
+                 Cover:\n\
+                 1. Who took part, by company and role\n\
+                 2. The purpose of the call and the key points discussed\n\
+                 3. Pain points, objections and questions raised by the customer\n\
+                 4. Agreed next steps, with owners and dates where mentioned\n\n\
+                 Quote the transcript where it supports a point, with its timestamp.",
+                call_id
+            ),
+            Self::DealReview {
+                crm_opportunity_id,
+                call_ids,
+            } => {
+                let calls = if call_ids.is_empty() {
+                    "Gong links none of the recent calls to this opportunity. Find the calls on this deal \
+                     first: use search_calls over the last few months and pick the calls with the \
+                     opportunity's account, then read them with get_transcripts."
+                        .to_string()
+                } else {
+                    "The metadata of the calls on this deal is attached; read their transcripts with \
+                     get_transcripts and compare them with compare_calls."
+                        .to_string()
+                };
+                format!(
+                    "Review the deal for CRM opportunity {}. {}\n\n\
+                     Cover:\n\
+                     1. Stakeholders on the customer side, their roles and how engaged they are\n\
+                     2. How the deal has progressed from call to call\n\
+                     3. Needs, budget, timeline and decision process as far as they were discussed\n\
+                     4. Risks: open objections, competitors, missing stakeholders or stalled next steps\n\
+                     5. Recommended next steps to move the deal forward",
+                    crm_opportunity_id, calls
+                )
+            }
+            Self::CoachingFeedback { call_id, rep_email } => format!(
+                "Give coaching feedback to the rep {} on Gong call {}. The participants and \
+                 transcript are attached; the rep is the participant with that email address.\n\n\
+                 Use transcript_metrics for the talk ratio, questions and longest monologue, then cover:\n\
+                 1. What the rep did well, with examples\n\
+                 2. Discovery: the quality and number of questions and how well the rep listened\n\
+                 3. Objection handling and how the rep positioned the product\n\
+                 4. Two or three concrete things to do differently on the next call\n\n\
+                 Quote the transcript with timestamps and keep the tone constructive.",
+                rep_email, call_id
+            ),
+            Self::CompetitorBrief {
+                competitor,
+                from_date_time,
+                to_date_time,
+            } => {
+                let sources = match mentions {
+                    Some([]) => format!(
+                        "The local transcript index has no mentions of {} in this period; say so and \
+                         suggest checking the spelling or a longer period.",
+                        competitor
+                    ),
+                    Some(hits) => format!(
+                        "Mentions found in the local transcript index are listed below and the \
+                         metadata of the calls is attached; use search_index for more.\n\n{}",
+                        mention_list(hits)
+                    ),
+                    None => format!(
+                        "Find the mentions with search_calls for the period and run_local_trackers \
+                         or get_transcripts on the calls, looking for {}.",
+                        competitor
+                    ),
+                };
+                format!(
+                    "Write a competitive brief on {} from Gong calls between {} and {}. {}\n\n\
+                     Cover:\n\
+                     1. How often it comes up and on which deals\n\
+                     2. Who brings it up, customers or reps, and in which context\n\
+                     3. Strengths and weaknesses customers attribute to it\n\
+                     4. How reps respond, and which responses work\n\
+                     5. Recommendations for positioning against it",
+                    competitor, from_date_time, to_date_time, sources
+                )
+            }
+        }
+    }
+}
+
+/// The distinct calls of `hits`, best match first, up to [`MAX_PROMPT_CALLS`]
+pub(crate) fn mentioned_calls(hits: &[PassageHit]) -> Vec<String> {
+    let mut call_ids: Vec<String> = Vec::new();
+    for hit in hits {
+        if call_ids.len() == MAX_PROMPT_CALLS {
+            break;
+        }
+        if !call_ids.contains(&hit.call_id) {
+            call_ids.push(hit.call_id.clone());
+        }
+    }
+    call_ids
+}
+
+/// The calls linked to the CRM opportunity `opportunity_id`, newest first, up
+/// to [`MAX_PROMPT_CALLS`]
+///
+/// `calls` are calls as returned by Gong with their CRM context, which lists
+/// the objects each call is linked to per CRM system.
+pub(crate) fn opportunity_calls(calls: &[Value], opportunity_id: &str) -> Vec<String> {
+    let mut linked: Vec<&Value> = calls
+        .iter()
+        .filter(|call| {
+            call["context"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .flat_map(|system| system["objects"].as_array().into_iter().flatten())
+                .any(|object| {
+                    object["objectType"]
+                        .as_str()
+                        .is_some_and(|kind| kind.eq_ignore_ascii_case("opportunity"))
+                        && object["objectId"].as_str() == Some(opportunity_id)
+                })
+        })
+        .collect();
+    linked.sort_by_cached_key(|call| {
+        std::cmp::Reverse(
+            call["metaData"]["started"]
+                .as_str()
+                .and_then(mirror::normalize_timestamp),
+        )
+    });
+    linked
+        .iter()
+        .filter_map(|call| call["metaData"]["id"].as_str())
+        .take(MAX_PROMPT_CALLS)
+        .map(String::from)
+        .collect()
+}
+
+fn mention_list(hits: &[PassageHit]) -> String {
+    hits.iter()
+        .map(|hit| {
+            format!(
+                "- {} ({}), call {} at {}, {} ({}): \"{}\"",
+                hit.call_title.as_deref().unwrap_or("Untitled"),
+                hit.call_started.as_deref().unwrap_or("unknown date"),
+                hit.call_id,
+                transcript::format_timestamp(hit.start_ms),
+                hit.speaker_name.as_deref().unwrap_or("Unknown speaker"),
+                hit.affiliation.as_deref().unwrap_or("Unknown"),
+                hit.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn optional(arguments: &JsonObject, name: &str) -> Result<Option<String>, McpError> {
+    match arguments.get(name) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::String(value)) if value.trim().is_empty() => Ok(None),
+        Some(serde_json::Value::String(value)) => Ok(Some(value.trim().to_string())),
+        Some(value) => Err(args::invalid(
+            name,
+            &format!("expected a string, got {}", value),
+        )),
+    }
+}
+
+fn required(arguments: &JsonObject, name: &str) -> Result<String, McpError> {
+    optional(arguments, name)?.ok_or_else(|| args::invalid(name, "is required"))
+}
+
+/// Parse a comma-separated list of call IDs
+fn call_ids(value: Option<&str>) -> Result<Vec<String>, McpError> {
+    let mut call_ids: Vec<String> = Vec::new();
+    for id in value.unwrap_or_default().split(',').map(str::trim) {
+        if !id.is_empty() && !call_ids.iter().any(|existing| existing == id) {
+            call_ids.push(id.to_string());
+        }
+    }
+    if call_ids.len() > MAX_PROMPT_CALLS {
+        return Err(args::invalid(
+            "callIds",
+            &format!(
+                "at most {} calls can be embedded, got {}",
+                MAX_PROMPT_CALLS,
+                call_ids.len()
+            ),
+        ));
+    }
+    Ok(call_ids)
+}
+
+/// Parse a period as normalized `(from, to)` timestamps
+///
+/// `30d` means the last 30 days and `2024-01-01/2024-04-01` a date or
+/// timestamp range.
+fn period(value: Option<&str>) -> Result<(String, String), McpError> {
+    let invalid = |value: &str| {
+        args::invalid(
+            "period",
+            &format!(
+                "expected the last N days such as 30d or a range such as 2024-01-01/2024-04-01, got {:?}",
+                value
+            ),
+        )
+    };
+
+    let Some(value) = value else {
+        return Ok(last_days(DEFAULT_PERIOD_DAYS));
+    };
+    if let Some(days) = value.strip_suffix('d') {
+        return match days.parse::<i64>() {
+            Ok(days) if days > 0 => Ok(last_days(days)),
+            _ => Err(invalid(value)),
+        };
+    }
+
+    let (from, to) = value.split_once('/').ok_or_else(|| invalid(value))?;
+    let from = mirror::normalize_timestamp(from.trim()).ok_or_else(|| invalid(value))?;
+    let to = mirror::normalize_timestamp(to.trim()).ok_or_else(|| invalid(value))?;
+    if from >= to {
+        return Err(args::invalid(
+            "period",
+            &format!("must start before it ends, got {:?}", value),
+        ));
+    }
+    Ok((from, to))
+}
+
+fn last_days(days: i64) -> (String, String) {
+    let now = Utc::now();
+    (
+        (now - Duration::days(days)).to_rfc3339_opts(SecondsFormat::Secs, true),
+        now.to_rfc3339_opts(SecondsFormat::Secs, true),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn arguments(value: serde_json::Value) -> JsonObject {
+        value.as_object().unwrap().clone()
+    }
+
+    fn hit(call_id: &str) -> PassageHit {
+        PassageHit {
+            call_id: call_id.to_string(),
+            call_title: Some("Discovery".to_string()),
+            call_started: Some("2024-03-01T10:00:00Z".to_string()),
+            start_ms: 65_000,
+            end_ms: 70_000,
+            speaker_id: None,
+            speaker_name: Some("Jane".to_string()),
+            affiliation: Some("External".to_string()),
+            score: 1.0,
+            text: "We are also looking at Acme".to_string(),
+            before: None,
+            after: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_prompts() {
+        let prompt = WorkflowPrompt::parse(
+            "coaching_feedback",
+            Some(&arguments(
+                json!({"callId": "123", "repEmail": " rep@example.com "}),
+            )),
+        )
+        .unwrap();
+        assert_eq!(
+            prompt,
+            WorkflowPrompt::CoachingFeedback {
+                call_id: "123".to_string(),
+                rep_email: "rep@example.com".to_string(),
+            }
+        );
+        assert_eq!(
+            prompt.resources(),
+            vec![
+                "gong://calls/123/participants",
+                "gong://calls/123/transcript"
+            ]
+        );
+
+        let prompt = WorkflowPrompt::parse(
+            "deal_review",
+            Some(&arguments(
+                json!({"crmOpportunityId": "006A", "callIds": "1, 2,1"}),
+            )),
+        )
+        .unwrap();
+        assert_eq!(prompt.resources(), vec!["gong://calls/1", "gong://calls/2"]);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_arguments() {
+        let error = WorkflowPrompt::parse("call_summary", None).unwrap_err();
+        assert_eq!(error.data.unwrap()["parameter"], "callId");
+
+        let error = WorkflowPrompt::parse(
+            "call_summary",
+            Some(&arguments(json!({"callId": "1", "call_id": "1"}))),
+        )
+        .unwrap_err();
+        assert_eq!(error.data.unwrap()["parameter"], "call_id");
+
+        let error = WorkflowPrompt::parse("forecast", None).unwrap_err();
+        assert_eq!(error.message, "unknown_prompt");
+
+        let error = WorkflowPrompt::parse(
+            "deal_review",
+            Some(&arguments(
+                json!({"crmOpportunityId": "006A", "callIds": "1,2,3,4,5,6"}),
+            )),
+        )
+        .unwrap_err();
+        assert_eq!(error.data.unwrap()["parameter"], "callIds");
+    }
+
+    #[test]
+    fn test_opportunity_calls() {
+        let call = |id: &str, started: &str, opportunity: &str| {
+            json!({
+                "metaData": {"id": id, "started": started},
+                "context": [{
+                    "system": "Salesforce",
+                    "objects": [
+                        {"objectType": "Account", "objectId": "001A"},
+                        {"objectType": "Opportunity", "objectId": opportunity},
+                    ],
+                }],
+            })
+        };
+        let calls = vec![
+            call("1", "2024-03-01T10:00:00Z", "006A"),
+            call("2", "2024-03-08T10:00:00-08:00", "006A"),
+            call("3", "2024-03-09T10:00:00Z", "006B"),
+            json!({"metaData": {"id": "4", "started": "2024-03-10T10:00:00Z"}}),
+        ];
+        assert_eq!(opportunity_calls(&calls, "006A"), vec!["2", "1"]);
+        assert!(opportunity_calls(&calls, "001A").is_empty());
+    }
+
+    #[test]
+    fn test_period() {
+        let (from, to) = period(Some("2024-01-01/2024-04-01")).unwrap();
+        assert_eq!(from, "2024-01-01T00:00:00Z");
+        assert_eq!(to, "2024-04-01T00:00:00Z");
+
+        let (from, to) = period(Some("30d")).unwrap();
+        assert!(from < to);
+        assert!(period(None).unwrap().0 < from);
+
+        for value in ["0d", "last month", "2024-04-01/2024-01-01", "2024-01-01"] {
+            assert!(period(Some(value)).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_competitor_brief_lists_mentions() {
+        let prompt = WorkflowPrompt::parse(
+            "competitor_brief",
+            Some(&arguments(
+                json!({"competitor": "Acme", "period": "2024-01-01/2024-04-01"}),
+            )),
+        )
+        .unwrap();
+        let hits = vec![hit("1"), hit("2"), hit("1")];
+        assert_eq!(mentioned_calls(&hits), vec!["1", "2"]);
+
+        let text = prompt.instructions(Some(&hits));
+        assert!(text.contains("between 2024-01-01T00:00:00Z and 2024-04-01T00:00:00Z"));
+        assert!(text.contains("call 2 at 01:05, Jane (External): \"We are also looking at Acme\""));
+        assert!(prompt.instructions(None).contains("run_local_trackers"));
+    }
+}
@@ -0,0 +1,427 @@
+//! Client-side rate limiting for the Gong API.
+//!
+//! Gong allows three requests per second and 10,000 requests per day per
+//! company. In HTTP mode every session works on a clone of the same
+//! `GongServer`, so one limiter is shared by all of them: a token bucket
+//! spaces requests out to the per-second limit, and a daily counter rejects
+//! requests once the day's quota is used up. Days follow UTC.
+//!
+//! The counter is persisted, by default next to the disk cache, so a restart
+//! doesn't forget the requests already made today. Writes happen on a
+//! blocking thread, one at a time, and add this process's new requests to
+//! the count on disk, so several servers sharing the file count each other's
+//! requests too.
+
+use crate::disk_cache::CACHE_DIR_ENV;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const QUOTA_FILE_ENV: &str = "GONG_QUOTA_FILE";
+pub const DEFAULT_QUOTA_FILE: &str = "gong-quota.json";
+
+/// Rate and quota limits
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RateLimitConfig {
+    /// Sustained requests per second; also the burst size
+    pub requests_per_second: f64,
+    pub daily_quota: u64,
+    /// Where the daily counter is kept; `None` keeps it in memory only
+    pub quota_file: Option<PathBuf>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 3.0,
+            daily_quota: 10_000,
+            quota_file: None,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Read overrides from `GONG_RATE_LIMIT_PER_SECOND`, `GONG_DAILY_QUOTA` and `GONG_QUOTA_FILE`
+    ///
+    /// Without `GONG_QUOTA_FILE`, the counter is kept in the disk cache
+    /// directory, if there is one.
+    pub fn from_env() -> Self {
+        fn env<T: std::str::FromStr>(name: &str) -> Option<T> {
+            let value = std::env::var(name).ok()?;
+            match value.parse() {
+                Ok(parsed) => Some(parsed),
+                Err(_) => {
+                    tracing::warn!("Ignoring invalid value for {}: {}", name, value);
+                    None
+                }
+            }
+        }
+
+        let defaults = Self::default();
+        Self {
+            requests_per_second: env("GONG_RATE_LIMIT_PER_SECOND")
+                .filter(|rate: &f64| *rate > 0.0)
+                .unwrap_or(defaults.requests_per_second),
+            daily_quota: env("GONG_DAILY_QUOTA").unwrap_or(defaults.daily_quota),
+            quota_file: std::env::var_os(QUOTA_FILE_ENV)
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os(CACHE_DIR_ENV)
+                        .map(|dir| PathBuf::from(dir).join(DEFAULT_QUOTA_FILE))
+                }),
+        }
+    }
+}
+
+/// The daily quota is used up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct QuotaExhausted {
+    pub daily_quota: u64,
+    /// Start of the next UTC day, RFC 3339
+    pub resets_at: String,
+}
+
+/// Persisted request count of one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DailyUsage {
+    /// UTC date, YYYY-MM-DD
+    date: String,
+    requests: u64,
+}
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    refilled: Instant,
+    usage: DailyUsage,
+    /// Requests counted since the last write of the quota file
+    unsaved: u64,
+    /// Whether a write of the quota file is in progress
+    saving: bool,
+}
+
+/// Token bucket and daily counter shared by all clones of the server
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    config: RateLimitConfig,
+    state: Arc<Mutex<State>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let today = Utc::now().date_naive();
+        let requests = config
+            .quota_file
+            .as_ref()
+            .map_or(0, |path| saved_requests(path, &today.to_string()));
+
+        Self {
+            state: Arc::new(Mutex::new(State {
+                tokens: config.requests_per_second,
+                refilled: Instant::now(),
+                usage: DailyUsage {
+                    date: today.to_string(),
+                    requests,
+                },
+                unsaved: 0,
+                saving: false,
+            })),
+            config,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(RateLimitConfig::from_env())
+    }
+
+    /// Wait for a request slot and count it against today's quota
+    ///
+    /// The request is reserved before waiting, so queued requests can't
+    /// overrun the quota, and refunded if the caller gives up while waiting.
+    pub async fn acquire(&self) -> Result<(), QuotaExhausted> {
+        let (wait, date) = {
+            let mut state = self.state.lock().unwrap();
+            let today = Utc::now().date_naive();
+            if state.usage.date != today.to_string() {
+                state.usage = DailyUsage {
+                    date: today.to_string(),
+                    requests: 0,
+                };
+                state.unsaved = 0;
+            }
+            if state.usage.requests >= self.config.daily_quota {
+                return Err(QuotaExhausted {
+                    daily_quota: self.config.daily_quota,
+                    resets_at: next_reset(today),
+                });
+            }
+            state.usage.requests += 1;
+
+            // Taking the token even when the bucket is empty queues concurrent
+            // requests behind each other instead of waking them all at once
+            let now = Instant::now();
+            let rate = self.config.requests_per_second;
+            state.tokens =
+                (state.tokens + now.duration_since(state.refilled).as_secs_f64() * rate).min(rate);
+            state.refilled = now;
+            state.tokens -= 1.0;
+            let wait = (state.tokens < 0.0).then(|| Duration::from_secs_f64(-state.tokens / rate));
+            (wait, state.usage.date.clone())
+        };
+
+        let mut reservation = Reservation {
+            state: &self.state,
+            date,
+            refund: true,
+        };
+        if let Some(wait) = wait {
+            tracing::debug!("Rate limit reached, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+        reservation.refund = false;
+
+        let save = {
+            let mut state = self.state.lock().unwrap();
+            if state.usage.date != reservation.date {
+                return Ok(());
+            }
+            state.unsaved += 1;
+            let save = self.config.quota_file.is_some() && !state.saving;
+            state.saving |= save;
+            save
+        };
+        if save && let Some(path) = self.config.quota_file.clone() {
+            let state = self.state.clone();
+            tokio::task::spawn_blocking(move || save_usage(&state, &path));
+        }
+        Ok(())
+    }
+
+    /// Limits and today's usage for `gong://status`
+    pub fn status(&self) -> Value {
+        let state = self.state.lock().unwrap();
+        let today = Utc::now().date_naive();
+        let used = if state.usage.date == today.to_string() {
+            state.usage.requests
+        } else {
+            0
+        };
+
+        json!({
+            "requestsPerSecond": self.config.requests_per_second,
+            "dailyQuota": self.config.daily_quota,
+            "usedToday": used,
+            "remainingToday": self.config.daily_quota.saturating_sub(used),
+            "resetsAt": next_reset(today),
+            "quotaFile": self.config.quota_file.as_ref().map(|p| p.display().to_string()),
+        })
+    }
+}
+
+/// A request counted against the quota while it waits for a token
+struct Reservation<'a> {
+    state: &'a Mutex<State>,
+    date: String,
+    refund: bool,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if !self.refund {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.usage.date == self.date {
+            state.usage.requests = state.usage.requests.saturating_sub(1);
+        }
+    }
+}
+
+/// Requests counted in the quota file for `date`
+fn saved_requests(path: &Path, date: &str) -> u64 {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<DailyUsage>(&contents).ok())
+        .filter(|usage| usage.date == date)
+        .map_or(0, |usage| usage.requests)
+}
+
+/// Add the unsaved requests to the quota file until none are left
+///
+/// Runs on a blocking thread; `State::saving` keeps it the only writer in this
+/// process. The count on disk includes other processes' requests, which are
+/// taken over into the in-memory counter.
+fn save_usage(state: &Mutex<State>, path: &Path) {
+    loop {
+        let (date, unsaved) = {
+            let mut state = state.lock().unwrap();
+            if state.unsaved == 0 {
+                state.saving = false;
+                return;
+            }
+            (state.usage.date.clone(), std::mem::take(&mut state.unsaved))
+        };
+
+        let usage = DailyUsage {
+            requests: saved_requests(path, &date) + unsaved,
+            date,
+        };
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let result = serde_json::to_vec(&usage)
+            .map_err(std::io::Error::from)
+            .and_then(|contents| fs::write(&tmp, contents))
+            .and_then(|_| fs::rename(&tmp, path));
+
+        let mut state = state.lock().unwrap();
+        if let Err(e) = result {
+            tracing::warn!(
+                "Failed to persist quota counter to {}: {}",
+                path.display(),
+                e
+            );
+            // Try again with the next request
+            state.unsaved += unsaved;
+            state.saving = false;
+            return;
+        }
+        if state.usage.date == usage.date {
+            state.usage.requests = state.usage.requests.max(usage.requests + state.unsaved);
+        }
+    }
+}
+
+fn next_reset(today: NaiveDate) -> String {
+    today
+        .succ_opt()
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|midnight| {
+            midnight
+                .and_utc()
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(requests_per_second: f64, daily_quota: u64) -> RateLimitConfig {
+        RateLimitConfig {
+            requests_per_second,
+            daily_quota,
+            quota_file: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spaces_requests_beyond_burst() {
+        let limiter = RateLimiter::new(config(20.0, 100));
+        let start = Instant::now();
+        for _ in 0..25 {
+            limiter.acquire().await.unwrap();
+        }
+        // The first 20 requests use the burst, the next 5 wait 50ms each
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(limiter.status()["usedToday"], 25);
+        assert_eq!(limiter.status()["remainingToday"], 75);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_requests_over_daily_quota() {
+        let limiter = RateLimiter::new(config(100.0, 2));
+        limiter.acquire().await.unwrap();
+        limiter.acquire().await.unwrap();
+
+        let exhausted = limiter.acquire().await.unwrap_err();
+        assert_eq!(exhausted.daily_quota, 2);
+        assert!(exhausted.resets_at.ends_with("T00:00:00Z"));
+        assert_eq!(limiter.status()["remainingToday"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_wait_is_not_counted() {
+        let limiter = RateLimiter::new(config(1.0, 10));
+        limiter.acquire().await.unwrap();
+
+        // The bucket is empty, so the second request waits a second
+        let cancelled = tokio::time::timeout(Duration::from_millis(20), limiter.acquire()).await;
+        assert!(cancelled.is_err());
+        assert_eq!(limiter.status()["usedToday"], 1);
+        assert_eq!(limiter.state.lock().unwrap().unsaved, 1);
+    }
+
+    #[tokio::test]
+    async fn test_counter_survives_restart() {
+        let path = std::env::temp_dir().join(format!("gong-mcp-quota-{}.json", std::process::id()));
+        let config = RateLimitConfig {
+            quota_file: Some(path.clone()),
+            ..config(100.0, 10)
+        };
+
+        let limiter = RateLimiter::new(config.clone());
+        for _ in 0..3 {
+            limiter.acquire().await.unwrap();
+        }
+        wait_until_saved(&limiter).await;
+        drop(limiter);
+
+        let limiter = RateLimiter::new(config);
+        assert_eq!(limiter.status()["usedToday"], 3);
+
+        // Counts from an earlier day are discarded
+        fs::write(&path, r#"{"date":"2000-01-01","requests":9}"#).unwrap();
+        let limiter = RateLimiter::new(RateLimitConfig {
+            quota_file: Some(path.clone()),
+            ..self::config(100.0, 10)
+        });
+        assert_eq!(limiter.status()["usedToday"], 0);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_processes_sharing_the_file_add_up() {
+        let path =
+            std::env::temp_dir().join(format!("gong-mcp-quota-shared-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let config = RateLimitConfig {
+            quota_file: Some(path.clone()),
+            ..config(100.0, 10)
+        };
+
+        let first = RateLimiter::new(config.clone());
+        let second = RateLimiter::new(config.clone());
+        for _ in 0..2 {
+            first.acquire().await.unwrap();
+            wait_until_saved(&first).await;
+            second.acquire().await.unwrap();
+            wait_until_saved(&second).await;
+        }
+
+        assert_eq!(
+            saved_requests(&path, &Utc::now().date_naive().to_string()),
+            4
+        );
+        // Each limiter learns of the other's requests when it saves
+        assert_eq!(second.status()["usedToday"], 4);
+        assert_eq!(RateLimiter::new(config).status()["usedToday"], 4);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    async fn wait_until_saved(limiter: &RateLimiter) {
+        for _ in 0..200 {
+            if !limiter.state.lock().unwrap().saving {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("quota file was not saved");
+    }
+}
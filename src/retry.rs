@@ -0,0 +1,414 @@
+//! Retries for transient Gong API failures.
+//!
+//! Gong allows three requests per second and occasionally answers with a 5xx
+//! under load, so API calls are retried on 429, 5xx, timeouts and connection
+//! errors with jittered exponential backoff. After a 429 the wait is what
+//! Gong asked for in `Retry-After`, which [`crate::api`] reads from the
+//! response, or at least one second, the length of Gong's rate limit window.
+//! Requests Gong asks to delay for longer than the maximum backoff fail
+//! instead, rather than hold up the tool call.
+//!
+//! Every attempt, including retries, goes through the shared rate limiter.
+//! Retries stop as soon as the MCP request that triggered them is cancelled.
+//! The server handlers run inside [`with_cancellation`], which makes the
+//! request's cancellation token available to every API call below them.
+
+use crate::error::ApiErrorKind;
+use crate::rate_limit::{QuotaExhausted, RateLimiter};
+use chrono::{DateTime, Utc};
+use gong_rs::apis::Error;
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Minimum wait after Gong rejected a request for exceeding its rate limit
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
+tokio::task_local! {
+    static CANCELLATION: CancellationToken;
+    /// `Retry-After` of the last response, until the retry loop reads it
+    static RETRY_AFTER: Cell<Option<Duration>>;
+}
+
+/// Run `future` with `ct` as the cancellation token for API retries
+pub(crate) async fn with_cancellation<F: Future>(ct: CancellationToken, future: F) -> F::Output {
+    CANCELLATION.scope(ct, future).await
+}
+
+/// Whether the MCP request this task runs for was cancelled
+///
+/// Tools that page through calls check this when a request fails, to return
+/// what they collected so far instead of an error.
+pub(crate) fn is_cancelled() -> bool {
+    CANCELLATION
+        .try_with(|ct| ct.is_cancelled())
+        .unwrap_or(false)
+}
+
+/// Record the `Retry-After` of a rejected request for the retry in progress
+pub(crate) fn record_retry_after(delay: Duration) {
+    let _ = RETRY_AFTER.try_with(|retry_after| retry_after.set(Some(delay)));
+}
+
+/// Parse a `Retry-After` value, either seconds or an HTTP date
+pub(crate) fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Retry limits and backoff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RetryConfig {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Read overrides from `GONG_RETRY_*` environment variables
+    ///
+    /// Delays are given in milliseconds. Setting `GONG_RETRY_MAX=0` disables
+    /// retries.
+    pub fn from_env() -> Self {
+        fn env<T: std::str::FromStr>(name: &str) -> Option<T> {
+            let value = std::env::var(name).ok()?;
+            match value.parse() {
+                Ok(parsed) => Some(parsed),
+                Err(_) => {
+                    tracing::warn!("Ignoring invalid value for {}: {}", name, value);
+                    None
+                }
+            }
+        }
+
+        let defaults = Self::default();
+        Self {
+            max_retries: env("GONG_RETRY_MAX").unwrap_or(defaults.max_retries),
+            base_delay: env("GONG_RETRY_BASE_DELAY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.base_delay),
+            max_delay: env("GONG_RETRY_MAX_DELAY_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.max_delay),
+        }
+    }
+
+    /// Backoff before retry number `retry` (starting at 0)
+    ///
+    /// The exponential delay is jittered over its upper half so that
+    /// concurrent sessions hitting the rate limit don't retry in lockstep.
+    fn delay(&self, retry: u32, min_delay: Duration) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let jittered = exponential / 2 + exponential.mul_f64(jitter() / 2.0);
+        jittered.max(min_delay)
+    }
+}
+
+/// A request that failed for good
+#[derive(Debug)]
+pub(crate) enum RetryError<E> {
+    /// The last error, after `retries` retries
+    Failed { error: E, retries: u32 },
+    /// The MCP request was cancelled while waiting for Gong
+    Cancelled { retries: u32 },
+    /// The daily Gong API quota is used up
+    QuotaExhausted(QuotaExhausted),
+}
+
+/// Send a gong-rs request, retrying transient failures
+///
+/// `request` is called once per attempt.
+pub(crate) async fn send<T, E, F, Fut>(
+    config: &RetryConfig,
+    limiter: &RateLimiter,
+    request: F,
+) -> Result<T, RetryError<Error<E>>>
+where
+    E: std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error<E>>>,
+{
+    let cancellation = CANCELLATION.try_with(|ct| ct.clone()).ok();
+    let retrying = retry(config, Some(limiter), cancellation, retry_delay, request);
+    RETRY_AFTER.scope(Cell::new(None), retrying).await
+}
+
+/// Minimum wait before retrying `error`, or `None` if it isn't transient
+fn retry_delay<E>(error: &Error<E>) -> Option<Duration> {
+    let retry_after = RETRY_AFTER
+        .try_with(|retry_after| retry_after.take())
+        .ok()
+        .flatten();
+    match ApiErrorKind::classify(error) {
+        ApiErrorKind::RateLimited => Some(retry_after.unwrap_or(RATE_LIMIT_WINDOW)),
+        kind if kind.is_transient() => Some(Duration::ZERO),
+        _ => None,
+    }
+}
+
+async fn retry<T, E, F, Fut>(
+    config: &RetryConfig,
+    limiter: Option<&RateLimiter>,
+    cancellation: Option<CancellationToken>,
+    classify: fn(&E) -> Option<Duration>,
+    mut request: F,
+) -> Result<T, RetryError<E>>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let cancellation = cancellation.unwrap_or_default();
+    let mut retries = 0;
+    loop {
+        if let Some(limiter) = limiter {
+            tokio::select! {
+                _ = cancellation.cancelled() => return Err(RetryError::Cancelled { retries }),
+                acquired = limiter.acquire() => acquired.map_err(RetryError::QuotaExhausted)?,
+            }
+        }
+
+        let result = tokio::select! {
+            _ = cancellation.cancelled() => return Err(RetryError::Cancelled { retries }),
+            result = request() => result,
+        };
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        let min_delay = match classify(&error) {
+            Some(min_delay)
+                if retries < config.max_retries
+                    && min_delay <= config.max_delay.max(RATE_LIMIT_WINDOW) =>
+            {
+                min_delay
+            }
+            _ => return Err(RetryError::Failed { error, retries }),
+        };
+        let delay = config.delay(retries, min_delay);
+        tracing::warn!(
+            "Gong API request failed ({}), retrying in {:?}",
+            error,
+            delay
+        );
+
+        tokio::select! {
+            _ = cancellation.cancelled() => return Err(RetryError::Cancelled { retries }),
+            _ = tokio::time::sleep(delay) => {}
+        }
+        retries += 1;
+    }
+}
+
+/// Uniformly distributed value in [0, 1)
+fn jitter() -> f64 {
+    // RandomState is seeded randomly per instance
+    (RandomState::new().hash_one(0u8) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::RateLimitConfig;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast() -> RetryConfig {
+        RetryConfig {
+            max_retries: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(4),
+        }
+    }
+
+    fn transient(error: &String) -> Option<Duration> {
+        (error == "busy").then_some(Duration::ZERO)
+    }
+
+    #[test]
+    fn test_delay_grows_and_is_capped() {
+        let config = RetryConfig {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        for _ in 0..20 {
+            let first = config.delay(0, Duration::ZERO);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = config.delay(2, Duration::ZERO);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+            assert!(config.delay(10, Duration::ZERO) <= Duration::from_secs(1));
+        }
+        assert_eq!(config.delay(0, RATE_LIMIT_WINDOW), RATE_LIMIT_WINDOW);
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let attempts = AtomicU32::new(0);
+        let result = retry(&fast(), None, None, transient, || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err("busy".to_string()),
+                _ => Ok(42),
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_with_retry_count() {
+        let result: Result<(), _> = retry(&fast(), None, None, transient, || async {
+            Err("busy".to_string())
+        })
+        .await;
+        match result {
+            Err(RetryError::Failed { error, retries }) => {
+                assert_eq!(error, "busy");
+                assert_eq!(retries, 3);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let result: Result<(), _> = retry(&fast(), None, None, transient, || async {
+            Err("denied".to_string())
+        })
+        .await;
+        assert!(matches!(result, Err(RetryError::Failed { retries: 0, .. })));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_when_asked_to_wait_too_long() {
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = retry(
+            &fast(),
+            None,
+            None,
+            |_: &String| Some(Duration::from_secs(3600)),
+            || async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err("rate limited".to_string())
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(RetryError::Failed { retries: 0, .. })));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_retry_after("2", now), Some(Duration::from_secs(2)));
+        assert_eq!(
+            parse_retry_after("Wed, 01 May 2024 12:00:05 GMT", now),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 01 May 2024 11:59:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_waits_for_retry_after() {
+        let rate_limited = || {
+            Error::<()>::ResponseError(gong_rs::apis::ResponseContent {
+                status: reqwest::StatusCode::TOO_MANY_REQUESTS,
+                content: String::new(),
+                entity: None,
+            })
+        };
+        assert_eq!(retry_delay(&rate_limited()), Some(RATE_LIMIT_WINDOW));
+
+        let delay = RETRY_AFTER
+            .scope(Cell::new(None), async {
+                record_retry_after(Duration::from_secs(3));
+                let first = retry_delay(&rate_limited());
+                // Read once, so a later 429 without the header isn't delayed
+                (first, retry_delay(&rate_limited()))
+            })
+            .await;
+        assert_eq!(
+            delay,
+            (Some(Duration::from_secs(3)), Some(RATE_LIMIT_WINDOW))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stops_when_cancelled() {
+        let ct = CancellationToken::new();
+        let config = RetryConfig {
+            base_delay: Duration::from_secs(60),
+            max_delay: Duration::from_secs(60),
+            ..fast()
+        };
+
+        let cancel = ct.clone();
+        let result: Result<(), _> = retry(&config, None, Some(ct), transient, || {
+            // Cancel while the first retry is waiting
+            cancel.cancel();
+            async { Err("busy".to_string()) }
+        })
+        .await;
+        assert!(matches!(result, Err(RetryError::Cancelled { retries: 0 })));
+    }
+
+    #[tokio::test]
+    async fn test_send_uses_request_cancellation() {
+        let ct = CancellationToken::new();
+        ct.cancel();
+        let limiter = RateLimiter::new(RateLimitConfig {
+            quota_file: None,
+            ..RateLimitConfig::default()
+        });
+        let result: Result<(), RetryError<Error<()>>> =
+            with_cancellation(ct, send(&fast(), &limiter, std::future::pending)).await;
+        assert!(matches!(result, Err(RetryError::Cancelled { retries: 0 })));
+    }
+
+    #[tokio::test]
+    async fn test_retries_count_against_quota() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 100.0,
+            daily_quota: 2,
+            quota_file: None,
+        });
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), _> = retry(&fast(), Some(&limiter), None, transient, || async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err("busy".to_string())
+        })
+        .await;
+
+        assert!(matches!(result, Err(RetryError::QuotaExhausted(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,433 @@
+//! Full-text index over mirrored transcripts.
+//!
+//! Transcripts are split into passages (a speaker's monologue, at most
+//! [`PASSAGE_SENTENCES`] sentences long) and stored in an SQLite FTS5 table
+//! next to the mirror. FTS5 ranks matches with BM25 and supports phrase
+//! queries; speaker affiliation and call start are stored with each passage
+//! so results can be filtered without touching the transcripts.
+
+use anyhow::Result;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Maximum number of sentences per indexed passage
+const PASSAGE_SENTENCES: usize = 5;
+
+pub(crate) const SCHEMA: &str = "
+CREATE VIRTUAL TABLE IF NOT EXISTS passages USING fts5 (
+    text,
+    call_id UNINDEXED,
+    start_ms UNINDEXED,
+    end_ms UNINDEXED,
+    speaker_id UNINDEXED,
+    speaker_name UNINDEXED,
+    affiliation UNINDEXED,
+    started UNINDEXED,
+    tokenize = 'porter unicode61'
+);
+";
+
+/// A ranked search over indexed passages
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IndexQuery {
+    pub text: String,
+    /// Require every term and phrase instead of ranking passages matching any
+    pub match_all: bool,
+    /// `Internal` or `External`
+    pub affiliation: Option<String>,
+    /// Normalized UTC timestamps, see [`crate::mirror::normalize_timestamp`]
+    pub from_date_time: Option<String>,
+    pub to_date_time: Option<String>,
+    pub call_ids: Option<Vec<String>>,
+    pub limit: usize,
+}
+
+/// A passage matching an [`IndexQuery`]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PassageHit {
+    pub call_id: String,
+    pub call_title: Option<String>,
+    pub call_started: Option<String>,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub speaker_id: Option<String>,
+    pub speaker_name: Option<String>,
+    pub affiliation: Option<String>,
+    /// BM25 relevance, higher is better
+    pub score: f64,
+    pub text: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Passage {
+    speaker_id: Option<String>,
+    start_ms: i64,
+    end_ms: i64,
+    text: String,
+}
+
+/// Split a single-call transcript response into passages
+fn passages(transcript: &Value) -> Vec<Passage> {
+    let monologues = transcript["callTranscripts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|t| t["transcript"].as_array().into_iter().flatten());
+
+    let mut passages = Vec::new();
+    for monologue in monologues {
+        let speaker_id = monologue["speakerId"].as_str().map(String::from);
+        let sentences: Vec<&Value> = monologue["sentences"]
+            .as_array()
+            .map(|s| s.iter().collect())
+            .unwrap_or_default();
+
+        for chunk in sentences.chunks(PASSAGE_SENTENCES) {
+            let text = chunk
+                .iter()
+                .filter_map(|s| s["text"].as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if text.trim().is_empty() {
+                continue;
+            }
+
+            passages.push(Passage {
+                speaker_id: speaker_id.clone(),
+                start_ms: chunk[0]["start"].as_f64().unwrap_or(0.0) as i64,
+                end_ms: chunk[chunk.len() - 1]["end"].as_f64().unwrap_or(0.0) as i64,
+                text,
+            });
+        }
+    }
+    passages
+}
+
+/// Replace the indexed passages of a call
+///
+/// Speaker names and affiliations come from the call's mirrored parties, so
+/// the call must be stored before its transcript.
+pub(crate) fn index_transcript(
+    conn: &Connection,
+    call_id: &str,
+    transcript: &Value,
+) -> Result<usize> {
+    conn.execute("DELETE FROM passages WHERE call_id = ?1", params![call_id])?;
+
+    let started: Option<String> = conn
+        .query_row(
+            "SELECT started FROM calls WHERE id = ?1",
+            params![call_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten();
+
+    let mut stmt = conn.prepare(
+        "SELECT speaker_id, name, affiliation FROM parties
+         WHERE call_id = ?1 AND speaker_id IS NOT NULL",
+    )?;
+    let speakers = stmt
+        .query_map(params![call_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                (
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ),
+            ))
+        })?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+
+    let mut insert = conn.prepare(
+        "INSERT INTO passages
+            (text, call_id, start_ms, end_ms, speaker_id, speaker_name, affiliation, started)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    let passages = passages(transcript);
+    for passage in &passages {
+        let (name, affiliation) = passage
+            .speaker_id
+            .as_ref()
+            .and_then(|id| speakers.get(id))
+            .cloned()
+            .unwrap_or_default();
+        insert.execute(params![
+            passage.text,
+            call_id,
+            passage.start_ms,
+            passage.end_ms,
+            passage.speaker_id,
+            name,
+            affiliation,
+            started,
+        ])?;
+    }
+
+    Ok(passages.len())
+}
+
+pub(crate) fn passage_count(conn: &Connection) -> Result<usize> {
+    Ok(conn.query_row("SELECT COUNT(*) FROM passages", [], |row| row.get(0))?)
+}
+
+/// Turn free text into an FTS5 query
+///
+/// Quoted parts become phrase queries and every other word becomes a term.
+/// All words are quoted, so punctuation and FTS5 operators in user input are
+/// matched literally instead of causing syntax errors.
+pub(crate) fn fts_query(input: &str, match_all: bool) -> Option<String> {
+    let mut parts = Vec::new();
+    for (i, segment) in input.split('"').enumerate() {
+        let words = words(segment);
+        if words.is_empty() {
+            continue;
+        }
+        if i % 2 == 1 {
+            parts.push(format!("\"{}\"", words.join(" ")));
+        } else {
+            parts.extend(words.iter().map(|w| format!("\"{}\"", w)));
+        }
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join(if match_all { " AND " } else { " OR " }))
+    }
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Return the best matching passages, most relevant first
+pub(crate) fn search(conn: &Connection, query: &IndexQuery) -> Result<Vec<PassageHit>> {
+    let Some(fts) = fts_query(&query.text, query.match_all) else {
+        return Ok(Vec::new());
+    };
+
+    let mut conditions = vec!["passages MATCH ?".to_string()];
+    let mut values = vec![fts];
+
+    if let Some(affiliation) = &query.affiliation {
+        conditions.push("passages.affiliation = ?".to_string());
+        values.push(affiliation.clone());
+    }
+    if let Some(from) = &query.from_date_time {
+        conditions.push("passages.started >= ?".to_string());
+        values.push(from.clone());
+    }
+    if let Some(to) = &query.to_date_time {
+        conditions.push("passages.started < ?".to_string());
+        values.push(to.clone());
+    }
+    if let Some(call_ids) = &query.call_ids {
+        let placeholders = vec!["?"; call_ids.len()].join(", ");
+        conditions.push(format!("passages.call_id IN ({})", placeholders));
+        values.extend(call_ids.iter().cloned());
+    }
+
+    let sql = format!(
+        "SELECT passages.rowid, passages.call_id, calls.title, passages.started,
+                passages.start_ms, passages.end_ms, passages.speaker_id,
+                passages.speaker_name, passages.affiliation, passages.text,
+                bm25(passages)
+         FROM passages LEFT JOIN calls ON calls.id = passages.call_id
+         WHERE {}
+         ORDER BY bm25(passages)
+         LIMIT {}",
+        conditions.join(" AND "),
+        query.limit
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt
+        .query_map(params_from_iter(values.iter()), |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                PassageHit {
+                    call_id: row.get(1)?,
+                    call_title: row.get(2)?,
+                    call_started: row.get(3)?,
+                    start_ms: row.get(4)?,
+                    end_ms: row.get(5)?,
+                    speaker_id: row.get(6)?,
+                    speaker_name: row.get(7)?,
+                    affiliation: row.get(8)?,
+                    text: row.get(9)?,
+                    // FTS5 reports BM25 as a negative number, lower is better
+                    score: -row.get::<_, f64>(10)?,
+                    before: None,
+                    after: None,
+                },
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    // Passages of a call are inserted together, so neighbours have adjacent rowids
+    let mut context =
+        conn.prepare("SELECT text FROM passages WHERE rowid = ?1 AND call_id = ?2")?;
+    let mut neighbour = |rowid: i64, call_id: &str| -> Result<Option<String>> {
+        Ok(context
+            .query_row(params![rowid, call_id], |row| row.get(0))
+            .optional()?)
+    };
+
+    rows.into_iter()
+        .map(|(rowid, mut hit)| {
+            hit.before = neighbour(rowid - 1, &hit.call_id)?;
+            hit.after = neighbour(rowid + 1, &hit.call_id)?;
+            Ok(hit)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn transcript(call_id: &str, monologues: Value) -> Value {
+        json!({"callTranscripts": [{"callId": call_id, "transcript": monologues}]})
+    }
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE calls (id TEXT PRIMARY KEY, started TEXT, title TEXT);
+             CREATE TABLE parties (call_id TEXT, speaker_id TEXT, name TEXT, affiliation TEXT);
+             INSERT INTO calls VALUES ('1', '2024-01-15T10:00:00Z', 'Kickoff');
+             INSERT INTO calls VALUES ('2', '2024-03-01T10:00:00Z', 'Renewal');
+             INSERT INTO parties VALUES ('1', 's1', 'Rep', 'Internal');
+             INSERT INTO parties VALUES ('1', 's2', 'Buyer', 'External');
+             INSERT INTO parties VALUES ('2', 's2', 'Buyer', 'External');",
+        )
+        .unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+
+        index_transcript(
+            &conn,
+            "1",
+            &transcript(
+                "1",
+                json!([
+                    {"speakerId": "s1", "sentences": [
+                        {"start": 0, "end": 4000, "text": "How was the onboarding so far?"}
+                    ]},
+                    {"speakerId": "s2", "sentences": [
+                        {"start": 5000, "end": 9000, "text": "Honestly the onboarding process was painful."},
+                        {"start": 9000, "end": 12000, "text": "We complained to support twice."}
+                    ]},
+                    {"speakerId": "s1", "sentences": [
+                        {"start": 13000, "end": 15000, "text": "Sorry to hear that."}
+                    ]}
+                ]),
+            ),
+        )
+        .unwrap();
+        index_transcript(
+            &conn,
+            "2",
+            &transcript(
+                "2",
+                json!([{"speakerId": "s2", "sentences": [
+                    {"start": 0, "end": 3000, "text": "Pricing is our main concern for the renewal."}
+                ]}]),
+            ),
+        )
+        .unwrap();
+        conn
+    }
+
+    fn query(text: &str) -> IndexQuery {
+        IndexQuery {
+            text: text.to_string(),
+            limit: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_passages_split_long_monologues() {
+        let sentences: Vec<Value> = (0..7)
+            .map(|i| json!({"start": i * 1000, "end": i * 1000 + 900, "text": format!("Sentence {}.", i)}))
+            .collect();
+        let passages = passages(&transcript(
+            "1",
+            json!([{"speakerId": "s1", "sentences": sentences}]),
+        ));
+
+        assert_eq!(passages.len(), 2);
+        assert_eq!(passages[0].start_ms, 0);
+        assert_eq!(passages[0].end_ms, 4900);
+        assert_eq!(passages[1].text, "Sentence 5. Sentence 6.");
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(
+            fts_query("customers \"onboarding process\" AND", false).as_deref(),
+            Some("\"customers\" OR \"onboarding process\" OR \"and\"")
+        );
+        assert_eq!(
+            fts_query("don't", true).as_deref(),
+            Some("\"don\" AND \"t\"")
+        );
+        assert_eq!(fts_query("?!", false), None);
+    }
+
+    #[test]
+    fn test_search_ranks_and_returns_context() {
+        let conn = setup();
+        let hits = search(&conn, &query("customers complained about onboarding")).unwrap();
+
+        assert_eq!(hits[0].call_id, "1");
+        assert_eq!(hits[0].start_ms, 5000);
+        assert_eq!(hits[0].speaker_name.as_deref(), Some("Buyer"));
+        assert_eq!(hits[0].call_title.as_deref(), Some("Kickoff"));
+        assert_eq!(
+            hits[0].before.as_deref(),
+            Some("How was the onboarding so far?")
+        );
+        assert_eq!(hits[0].after.as_deref(), Some("Sorry to hear that."));
+        assert!(hits[0].score > 0.0);
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let conn = setup();
+
+        let mut q = query("onboarding");
+        q.affiliation = Some("Internal".to_string());
+        let hits = search(&conn, &q).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].speaker_id.as_deref(), Some("s1"));
+
+        let mut q = query("onboarding pricing");
+        q.from_date_time = Some("2024-02-01T00:00:00Z".to_string());
+        let hits = search(&conn, &q).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].call_id, "2");
+
+        let hits = search(&conn, &query("\"process was painful\"")).unwrap();
+        assert_eq!(hits.len(), 1);
+        let hits = search(&conn, &query("\"painful process\"")).unwrap();
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn test_reindexing_replaces_passages() {
+        let conn = setup();
+        assert_eq!(passage_count(&conn).unwrap(), 4);
+
+        index_transcript(&conn, "2", &transcript("2", json!([]))).unwrap();
+        assert_eq!(passage_count(&conn).unwrap(), 3);
+    }
+}
@@ -0,0 +1,403 @@
+//! Read-only SQL over the local mirror.
+//!
+//! `query_calls_sql` lets analysts answer questions no other tool covers by
+//! running a single `SELECT` against the mirror database. Statements are
+//! checked to be read-only, run with `PRAGMA query_only` enabled and are
+//! interrupted when they exceed the time limit. The tables are described by
+//! [`schema`], which backs the `gong://schema` resource.
+
+use anyhow::{Result, anyhow, bail};
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, ErrorCode, Statement};
+use serde_json::{Value, json};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Rows returned when the caller doesn't ask for a limit
+pub(crate) const DEFAULT_MAX_ROWS: usize = 100;
+
+/// Upper bound for `max_rows`
+pub(crate) const MAX_ROWS: usize = 1_000;
+
+/// Queries running longer than this are interrupted
+pub(crate) const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Columns and rows returned by a query
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// More rows were available than `max_rows`
+    pub truncated: bool,
+    pub elapsed: Duration,
+}
+
+/// Run a single read-only `SELECT` (or `WITH ... SELECT`) statement
+pub(crate) fn run_query(
+    conn: &Connection,
+    sql: &str,
+    max_rows: usize,
+    timeout: Duration,
+) -> Result<QueryResult> {
+    if !matches!(first_keyword(sql).as_deref(), Some("select" | "with"))
+        || !is_single_statement(sql)
+    {
+        bail!("only a single SELECT statement is allowed");
+    }
+
+    conn.pragma_update(None, "query_only", true)?;
+    let result = run_read_only(conn, sql, max_rows, timeout);
+    conn.pragma_update(None, "query_only", false)?;
+    result
+}
+
+fn run_read_only(
+    conn: &Connection,
+    sql: &str,
+    max_rows: usize,
+    timeout: Duration,
+) -> Result<QueryResult> {
+    let mut stmt = conn.prepare(sql)?;
+    if !stmt.readonly() {
+        bail!("only read-only statements are allowed");
+    }
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+
+    // Interrupt the query from a watchdog thread once the time limit is reached
+    let started = Instant::now();
+    let (finished, wait_for_finish) = mpsc::channel::<()>();
+    let interrupt = conn.get_interrupt_handle();
+    let watchdog = std::thread::spawn(move || {
+        if let Err(mpsc::RecvTimeoutError::Timeout) = wait_for_finish.recv_timeout(timeout) {
+            interrupt.interrupt();
+        }
+    });
+
+    let rows = collect_rows(&mut stmt, columns.len(), max_rows);
+    drop(finished);
+    let _ = watchdog.join();
+
+    let (rows, truncated) = rows.map_err(|e| {
+        if e.sqlite_error_code() == Some(ErrorCode::OperationInterrupted) {
+            anyhow!(
+                "query exceeded the time limit of {} seconds",
+                timeout.as_secs_f64()
+            )
+        } else {
+            anyhow!(e)
+        }
+    })?;
+
+    Ok(QueryResult {
+        columns,
+        rows,
+        truncated,
+        elapsed: started.elapsed(),
+    })
+}
+
+fn collect_rows(
+    stmt: &mut Statement,
+    column_count: usize,
+    max_rows: usize,
+) -> rusqlite::Result<(Vec<Vec<Value>>, bool)> {
+    let mut rows = stmt.query([])?;
+    let mut collected = Vec::new();
+
+    while let Some(row) = rows.next()? {
+        if collected.len() == max_rows {
+            return Ok((collected, true));
+        }
+        let values = (0..column_count)
+            .map(|i| row.get_ref(i).map(to_json))
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        collected.push(values);
+    }
+
+    Ok((collected, false))
+}
+
+fn to_json(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => json!(i),
+        ValueRef::Real(f) => json!(f),
+        ValueRef::Text(text) => json!(String::from_utf8_lossy(text)),
+        ValueRef::Blob(blob) => json!(format!("<{} byte blob>", blob.len())),
+    }
+}
+
+/// Skip leading whitespace and comments
+fn skip_comments(sql: &str) -> &str {
+    let mut rest = sql;
+    loop {
+        rest = rest.trim_start();
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map(|(_, r)| r).unwrap_or("");
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map(|(_, r)| r).unwrap_or("");
+        } else {
+            return rest;
+        }
+    }
+}
+
+/// First keyword of a statement, skipping whitespace and comments
+fn first_keyword(sql: &str) -> Option<String> {
+    let rest = skip_comments(sql);
+    let keyword: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    (!keyword.is_empty()).then(|| keyword.to_ascii_lowercase())
+}
+
+/// Whether nothing but comments follows the first top-level `;`
+///
+/// SQLite prepares only the first statement of a string, so anything after
+/// it would be silently ignored; reject it instead.
+fn is_single_statement(sql: &str) -> bool {
+    let mut quote: Option<char> = None;
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '\'' | '"' | '`' => quote = Some(c),
+                '[' => quote = Some(']'),
+                '-' if chars.peek().is_some_and(|(_, n)| *n == '-') => {
+                    // Line comment: skip to the end of the line
+                    for (_, c) in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                '/' if chars.peek().is_some_and(|(_, n)| *n == '*') => {
+                    chars.next();
+                    let mut previous = ' ';
+                    for (_, c) in chars.by_ref() {
+                        if previous == '*' && c == '/' {
+                            break;
+                        }
+                        previous = c;
+                    }
+                }
+                ';' => {
+                    return skip_comments(&sql[i + 1..])
+                        .trim_start_matches(';')
+                        .trim()
+                        .is_empty();
+                }
+                _ => {}
+            },
+        }
+    }
+    true
+}
+
+/// Documentation of the mirror tables for the `gong://schema` resource
+pub(crate) fn schema() -> Value {
+    json!({
+        "dialect": "SQLite",
+        "limits": {
+            "statements": "A single read-only SELECT (or WITH ... SELECT) statement",
+            "defaultMaxRows": DEFAULT_MAX_ROWS,
+            "maxRows": MAX_ROWS,
+            "timeoutSeconds": QUERY_TIMEOUT.as_secs(),
+        },
+        "notes": [
+            "Timestamps are ISO 8601 strings in UTC (e.g. 2024-01-15T18:00:00Z) and compare correctly as text; use strftime('%Y-%m', started) to group by month.",
+            "calls.data and transcripts.data hold the JSON returned by the Gong API; use json_extract(data, '$.content.brief') and json_each to read fields without a column.",
+            "Only calls and transcripts mirrored by 'gong-mcp sync' are available."
+        ],
+        "tables": [
+            {
+                "name": "calls",
+                "description": "One row per mirrored call",
+                "columns": [
+                    {"name": "id", "type": "TEXT", "description": "Gong call ID (primary key)"},
+                    {"name": "started", "type": "TEXT", "description": "Actual start time, UTC"},
+                    {"name": "title", "type": "TEXT", "description": "Call title"},
+                    {"name": "url", "type": "TEXT", "description": "Link to the call in Gong"},
+                    {"name": "direction", "type": "TEXT", "description": "Inbound, Outbound, Conference or Unknown"},
+                    {"name": "scope", "type": "TEXT", "description": "Internal, External or Unknown"},
+                    {"name": "duration", "type": "INTEGER", "description": "Duration in seconds"},
+                    {"name": "primary_user_id", "type": "TEXT", "description": "Gong user ID of the host"},
+                    {"name": "workspace_id", "type": "TEXT", "description": "Gong workspace ID"},
+                    {"name": "data", "type": "TEXT", "description": "Full call JSON: metaData, parties and content (topics, trackers, brief, outline, highlights, key points)"},
+                    {"name": "synced_at", "type": "TEXT", "description": "When the call was last mirrored, UTC"}
+                ]
+            },
+            {
+                "name": "parties",
+                "description": "Participants of each call",
+                "columns": [
+                    {"name": "call_id", "type": "TEXT", "description": "References calls.id"},
+                    {"name": "party_id", "type": "TEXT", "description": "Gong party ID, unique within a call"},
+                    {"name": "name", "type": "TEXT", "description": "Participant name"},
+                    {"name": "email_address", "type": "TEXT", "description": "Participant email address"},
+                    {"name": "affiliation", "type": "TEXT", "description": "Internal, External or Unknown"},
+                    {"name": "speaker_id", "type": "TEXT", "description": "Speaker ID used in transcripts, NULL if the participant didn't speak"},
+                    {"name": "user_id", "type": "TEXT", "description": "Gong user ID for internal participants"}
+                ]
+            },
+            {
+                "name": "sentences",
+                "description": "Transcript sentences, one row per sentence",
+                "columns": [
+                    {"name": "call_id", "type": "TEXT", "description": "References calls.id"},
+                    {"name": "speaker_id", "type": "TEXT", "description": "References parties.speaker_id of the same call"},
+                    {"name": "start_ms", "type": "INTEGER", "description": "Offset from the start of the call in milliseconds"},
+                    {"name": "end_ms", "type": "INTEGER", "description": "End offset in milliseconds"},
+                    {"name": "text", "type": "TEXT", "description": "Sentence text"}
+                ]
+            },
+            {
+                "name": "transcripts",
+                "description": "Raw transcript of each call",
+                "columns": [
+                    {"name": "call_id", "type": "TEXT", "description": "References calls.id (primary key)"},
+                    {"name": "data", "type": "TEXT", "description": "Transcript JSON as returned by the Gong API"},
+                    {"name": "synced_at", "type": "TEXT", "description": "When the transcript was mirrored, UTC"}
+                ]
+            },
+            {
+                "name": "passages",
+                "description": "FTS5 full-text index over transcripts (used by search_index); filter with 'passages MATCH ...' and rank with bm25(passages)",
+                "columns": [
+                    {"name": "text", "type": "TEXT", "description": "Up to five consecutive sentences of one speaker"},
+                    {"name": "call_id", "type": "TEXT", "description": "References calls.id"},
+                    {"name": "start_ms", "type": "INTEGER", "description": "Offset of the first sentence in milliseconds"},
+                    {"name": "end_ms", "type": "INTEGER", "description": "End offset of the last sentence in milliseconds"},
+                    {"name": "speaker_id", "type": "TEXT", "description": "Speaker ID"},
+                    {"name": "speaker_name", "type": "TEXT", "description": "Speaker name"},
+                    {"name": "affiliation", "type": "TEXT", "description": "Internal, External or Unknown"},
+                    {"name": "started", "type": "TEXT", "description": "Call start time, UTC"}
+                ]
+            },
+            {
+                "name": "sync_state",
+                "description": "Sync bookkeeping",
+                "columns": [
+                    {"name": "key", "type": "TEXT", "description": "'calls.started' holds the high-water mark"},
+                    {"name": "value", "type": "TEXT", "description": "Value"}
+                ]
+            }
+        ],
+        "examples": [
+            {
+                "question": "Calls per month with more than 3 external participants, and their average duration in minutes",
+                "sql": "SELECT strftime('%Y-%m', c.started) AS month, COUNT(*) AS calls, ROUND(AVG(c.duration) / 60.0, 1) AS avg_minutes FROM calls c WHERE (SELECT COUNT(*) FROM parties p WHERE p.call_id = c.id AND p.affiliation = 'External') > 3 GROUP BY month ORDER BY month"
+            },
+            {
+                "question": "Talk time per speaker on a call",
+                "sql": "SELECT p.name, p.affiliation, SUM(s.end_ms - s.start_ms) / 1000 AS talk_seconds FROM sentences s JOIN parties p ON p.call_id = s.call_id AND p.speaker_id = s.speaker_id WHERE s.call_id = '1234' GROUP BY p.name, p.affiliation ORDER BY talk_seconds DESC"
+            }
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE calls (id TEXT PRIMARY KEY, duration INTEGER, data TEXT);
+             INSERT INTO calls VALUES ('1', 600, '{\"metaData\": {\"title\": \"Kickoff\"}}');
+             INSERT INTO calls VALUES ('2', 1200, NULL);
+             INSERT INTO calls VALUES ('3', 1800.5, NULL);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn query(conn: &Connection, sql: &str, max_rows: usize) -> Result<QueryResult> {
+        run_query(conn, sql, max_rows, QUERY_TIMEOUT)
+    }
+
+    #[test]
+    fn test_select_returns_columns_and_rows() {
+        let conn = conn();
+        let result = query(
+            &conn,
+            "-- titles\nSELECT id, json_extract(data, '$.metaData.title') AS title FROM calls ORDER BY id",
+            10,
+        )
+        .unwrap();
+
+        assert_eq!(result.columns, vec!["id", "title"]);
+        assert_eq!(result.rows[0], vec![json!("1"), json!("Kickoff")]);
+        assert_eq!(result.rows[1], vec![json!("2"), Value::Null]);
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn test_max_rows_truncates() {
+        let conn = conn();
+        let result = query(&conn, "WITH c AS (SELECT * FROM calls) SELECT id FROM c", 2).unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert!(result.truncated);
+    }
+
+    #[test]
+    fn test_rejects_writes_and_multiple_statements() {
+        let conn = conn();
+        assert!(query(&conn, "DELETE FROM calls", 10).is_err());
+        assert!(query(&conn, "SELECT 1; DELETE FROM calls", 10).is_err());
+        assert!(query(&conn, "/* sneaky */ DROP TABLE calls", 10).is_err());
+        assert!(
+            query(
+                &conn,
+                "WITH gone AS (SELECT 1) DELETE FROM calls WHERE id IN (SELECT * FROM gone)",
+                10
+            )
+            .is_err()
+        );
+        assert!(query(&conn, "PRAGMA table_info(calls)", 10).is_err());
+
+        // Writes are possible again once the query has finished
+        conn.execute("DELETE FROM calls WHERE id = '3'", [])
+            .unwrap();
+        let result = query(&conn, "SELECT COUNT(*) FROM calls", 10).unwrap();
+        assert_eq!(result.rows[0][0], json!(2));
+    }
+
+    #[test]
+    fn test_timeout_interrupts_long_queries() {
+        let conn = conn();
+        let result = run_query(
+            &conn,
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) SELECT COUNT(*) FROM n",
+            10,
+            Duration::from_millis(50),
+        );
+        assert!(result.unwrap_err().to_string().contains("time limit"));
+    }
+
+    #[test]
+    fn test_first_keyword() {
+        assert_eq!(first_keyword("  select 1").as_deref(), Some("select"));
+        assert_eq!(
+            first_keyword("-- a\n/* b */ WITH x AS (SELECT 1) SELECT * FROM x").as_deref(),
+            Some("with")
+        );
+        assert_eq!(first_keyword("-- only a comment"), None);
+    }
+
+    #[test]
+    fn test_is_single_statement() {
+        assert!(is_single_statement("SELECT 1"));
+        assert!(is_single_statement("SELECT 1; -- done"));
+        assert!(is_single_statement(
+            "SELECT ';' AS semicolon, \"a;b\" FROM t"
+        ));
+        assert!(is_single_statement("SELECT 1 /* ; */ -- ;\n"));
+        assert!(!is_single_statement("SELECT 1; DELETE FROM calls"));
+        assert!(!is_single_statement("SELECT 1;;SELECT 2"));
+    }
+}
@@ -0,0 +1,345 @@
+//! Local keyword trackers evaluated against transcripts.
+//!
+//! Gong trackers are managed by Gong admins. Local trackers are defined in a
+//! JSON file referenced by `GONG_TRACKERS_FILE` and evaluated by the server
+//! itself, so new competitor names or phrases can be tracked immediately:
+//!
+//! ```json
+//! [
+//!   {"name": "Competitors", "phrases": ["Acme", "Globex"], "speakerAffiliation": "External"},
+//!   {"name": "Pricing", "regexes": ["\\bprice[sd]?\\b", "\\bdiscount\\w*"]}
+//! ]
+//! ```
+//!
+//! Phrases match case-insensitively on word boundaries; regexes are used as
+//! written (prefix them with `(?i)` for case-insensitive matching).
+
+use crate::transcript::{SpeakerInfo, TranscriptSentence};
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Environment variable pointing to the local tracker definitions
+pub(crate) const TRACKERS_FILE_ENV: &str = "GONG_TRACKERS_FILE";
+
+/// A tracker as written in the configuration file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct TrackerDefinition {
+    name: String,
+    #[serde(default)]
+    phrases: Vec<String>,
+    #[serde(default)]
+    regexes: Vec<String>,
+    /// Only match sentences spoken by `Internal` or `External` speakers
+    #[serde(default)]
+    speaker_affiliation: Option<String>,
+}
+
+/// A compiled local tracker
+#[derive(Debug, Clone)]
+pub(crate) struct LocalTracker {
+    pub name: String,
+    patterns: Vec<Regex>,
+    pub speaker_affiliation: Option<String>,
+}
+
+/// A single tracker match within a transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TrackerHit {
+    pub tracker: String,
+    pub speaker_id: Option<String>,
+    pub start: i64,
+    pub matched: String,
+    pub text: String,
+}
+
+impl LocalTracker {
+    fn compile(definition: TrackerDefinition) -> Result<Self> {
+        if definition.phrases.is_empty() && definition.regexes.is_empty() {
+            bail!(
+                "tracker '{}' needs at least one phrase or regex",
+                definition.name
+            );
+        }
+
+        let speaker_affiliation = match definition.speaker_affiliation.as_deref() {
+            None => None,
+            Some(a) if a.eq_ignore_ascii_case("internal") => Some("Internal".to_string()),
+            Some(a) if a.eq_ignore_ascii_case("external") => Some("External".to_string()),
+            Some(other) => bail!(
+                "tracker '{}' has invalid speakerAffiliation '{}' (expected Internal or External)",
+                definition.name,
+                other
+            ),
+        };
+
+        if definition
+            .phrases
+            .iter()
+            .any(|phrase| phrase.trim().is_empty())
+        {
+            bail!("tracker '{}' has an empty phrase", definition.name);
+        }
+
+        let phrase_patterns = definition
+            .phrases
+            .iter()
+            .map(|phrase| phrase_pattern(phrase.trim()));
+        let patterns = phrase_patterns
+            .chain(definition.regexes.iter().cloned())
+            .map(|pattern| {
+                Regex::new(&pattern).with_context(|| {
+                    format!(
+                        "tracker '{}' has invalid pattern '{}'",
+                        definition.name, pattern
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            name: definition.name,
+            patterns,
+            speaker_affiliation,
+        })
+    }
+
+    /// Return the first matching fragment of a sentence, if any
+    fn find<'a>(&self, text: &'a str) -> Option<&'a str> {
+        self.patterns
+            .iter()
+            .filter_map(|pattern| pattern.find(text))
+            .min_by_key(|m| m.start())
+            .map(|m| m.as_str())
+    }
+}
+
+/// Build a case-insensitive pattern for a phrase, anchoring each end on a word
+/// boundary only where the phrase starts or ends with a word character, so
+/// phrases like "C++" or ".NET" still match
+fn phrase_pattern(phrase: &str) -> String {
+    let boundary = |c: Option<char>| match c {
+        Some(c) if c.is_alphanumeric() || c == '_' => r"\b",
+        _ => "",
+    };
+    let start = boundary(phrase.chars().next());
+    let end = boundary(phrase.chars().next_back());
+    format!("(?i){start}{}{end}", regex::escape(phrase))
+}
+
+/// Parse and compile tracker definitions from JSON
+pub(crate) fn parse_trackers(json: &str) -> Result<Vec<LocalTracker>> {
+    let definitions: Vec<TrackerDefinition> =
+        serde_json::from_str(json).context("invalid tracker definitions")?;
+    definitions.into_iter().map(LocalTracker::compile).collect()
+}
+
+/// Load tracker definitions from a JSON file
+pub(crate) fn load_trackers(path: &Path) -> Result<Vec<LocalTracker>> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    parse_trackers(&json)
+}
+
+/// Load trackers from `GONG_TRACKERS_FILE`, logging and skipping invalid files
+pub(crate) fn load_trackers_from_env() -> Vec<LocalTracker> {
+    let Ok(path) = std::env::var(TRACKERS_FILE_ENV) else {
+        return Vec::new();
+    };
+
+    match load_trackers(Path::new(&path)) {
+        Ok(trackers) => {
+            tracing::info!("Loaded {} local trackers from {}", trackers.len(), path);
+            trackers
+        }
+        Err(e) => {
+            tracing::warn!("Ignoring local trackers: {:#}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Evaluate trackers against a transcript, returning hits in time order
+pub(crate) fn run_trackers(
+    trackers: &[&LocalTracker],
+    sentences: &[TranscriptSentence],
+    directory: &HashMap<String, SpeakerInfo>,
+) -> Vec<TrackerHit> {
+    let mut hits = Vec::new();
+
+    for sentence in sentences {
+        let affiliation = sentence
+            .speaker_id
+            .as_ref()
+            .and_then(|id| directory.get(id))
+            .map(|info| info.affiliation.as_str());
+
+        for tracker in trackers {
+            if let Some(required) = tracker.speaker_affiliation.as_deref()
+                && affiliation != Some(required)
+            {
+                continue;
+            }
+
+            if let Some(matched) = tracker.find(&sentence.text) {
+                hits.push(TrackerHit {
+                    tracker: tracker.name.clone(),
+                    speaker_id: sentence.speaker_id.clone(),
+                    start: sentence.start,
+                    matched: matched.to_string(),
+                    text: sentence.text.clone(),
+                });
+            }
+        }
+    }
+
+    hits
+}
+
+/// Render hits with speaker names and per-tracker counts
+pub(crate) fn hits_to_json(hits: &[TrackerHit], directory: &HashMap<String, SpeakerInfo>) -> Value {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for hit in hits {
+        *counts.entry(hit.tracker.as_str()).or_default() += 1;
+    }
+
+    let hits: Vec<Value> = hits
+        .iter()
+        .map(|hit| {
+            let speaker = hit.speaker_id.as_ref().and_then(|id| directory.get(id));
+            json!({
+                "tracker": hit.tracker,
+                "startMs": hit.start,
+                "speakerId": hit.speaker_id,
+                "speakerName": speaker.and_then(|s| s.name.as_ref()),
+                "affiliation": speaker.map(|s| s.affiliation.as_str()).unwrap_or("Unknown"),
+                "matched": hit.matched,
+                "text": hit.text,
+            })
+        })
+        .collect();
+
+    json!({
+        "counts": counts,
+        "hits": hits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentence(speaker: &str, start: i64, text: &str) -> TranscriptSentence {
+        TranscriptSentence {
+            speaker_id: Some(speaker.to_string()),
+            start,
+            end: start + 1_000,
+            text: text.to_string(),
+        }
+    }
+
+    fn directory() -> HashMap<String, SpeakerInfo> {
+        HashMap::from([
+            (
+                "rep".to_string(),
+                SpeakerInfo {
+                    name: Some("Rep".to_string()),
+                    affiliation: "Internal".to_string(),
+                },
+            ),
+            (
+                "buyer".to_string(),
+                SpeakerInfo {
+                    name: Some("Buyer".to_string()),
+                    affiliation: "External".to_string(),
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_parse_trackers() {
+        let trackers = parse_trackers(
+            r#"[
+                {"name": "Competitors", "phrases": ["Acme Corp"], "speakerAffiliation": "external"},
+                {"name": "Pricing", "regexes": ["(?i)\\bdiscount\\w*"]}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(trackers.len(), 2);
+        assert_eq!(trackers[0].speaker_affiliation.as_deref(), Some("External"));
+        assert_eq!(trackers[1].speaker_affiliation, None);
+    }
+
+    #[test]
+    fn test_parse_trackers_rejects_invalid_definitions() {
+        assert!(parse_trackers(r#"[{"name": "Empty"}]"#).is_err());
+        assert!(parse_trackers(r#"[{"name": "Bad", "regexes": ["("]}]"#).is_err());
+        assert!(
+            parse_trackers(
+                r#"[{"name": "Bad", "phrases": ["x"], "speakerAffiliation": "partner"}]"#
+            )
+            .is_err()
+        );
+        assert!(parse_trackers(r#"[{"name": "Typo", "phrase": ["x"]}]"#).is_err());
+        assert!(parse_trackers(r#"[{"name": "Blank", "phrases": ["Acme", "  "]}]"#).is_err());
+    }
+
+    #[test]
+    fn test_phrases_match_whole_words_case_insensitively() {
+        let trackers = parse_trackers(r#"[{"name": "Competitors", "phrases": ["Acme"]}]"#).unwrap();
+        let trackers: Vec<&LocalTracker> = trackers.iter().collect();
+        let sentences = vec![
+            sentence("buyer", 0, "We also looked at ACME last year."),
+            sentence("buyer", 2_000, "Our acmeology team agreed."),
+        ];
+
+        let hits = run_trackers(&trackers, &sentences, &directory());
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].matched, "ACME");
+        assert_eq!(hits[0].start, 0);
+    }
+
+    #[test]
+    fn test_phrases_with_symbols_match() {
+        let trackers =
+            parse_trackers(r#"[{"name": "Stack", "phrases": ["C++", ".NET"]}]"#).unwrap();
+        let trackers: Vec<&LocalTracker> = trackers.iter().collect();
+        let sentences = vec![
+            sentence("buyer", 0, "Our backend is mostly C++ today."),
+            sentence("buyer", 2_000, "The rest runs on .NET services."),
+            sentence("buyer", 4_000, "We never used C at all."),
+        ];
+
+        let hits = run_trackers(&trackers, &sentences, &directory());
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].matched, "C++");
+        assert_eq!(hits[1].matched, ".NET");
+    }
+
+    #[test]
+    fn test_speaker_affiliation_filter() {
+        let trackers = parse_trackers(
+            r#"[{"name": "Competitors", "phrases": ["Acme"], "speakerAffiliation": "External"}]"#,
+        )
+        .unwrap();
+        let trackers: Vec<&LocalTracker> = trackers.iter().collect();
+        let sentences = vec![
+            sentence("rep", 0, "Are you evaluating Acme?"),
+            sentence("buyer", 2_000, "Yes, Acme is on our shortlist."),
+        ];
+
+        let hits = run_trackers(&trackers, &sentences, &directory());
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].speaker_id.as_deref(), Some("buyer"));
+
+        let json = hits_to_json(&hits, &directory());
+        assert_eq!(json["counts"]["Competitors"], 1);
+        assert_eq!(json["hits"][0]["speakerName"], "Buyer");
+    }
+}
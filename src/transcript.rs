@@ -0,0 +1,493 @@
+//! Transcript flattening and conversation metrics.
+//!
+//! Gong returns transcripts as monologues containing sentences. Most of the
+//! analysis done here works on a flat, time-ordered list of sentences tagged
+//! with their speaker, joined with the call's parties to learn whether each
+//! speaker is internal or external.
+
+use gong_rs::models;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+
+/// A single transcript sentence, flattened out of its monologue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TranscriptSentence {
+    pub speaker_id: Option<String>,
+    /// Start offset from the beginning of the call, in milliseconds
+    pub start: i64,
+    /// End offset from the beginning of the call, in milliseconds
+    pub end: i64,
+    pub text: String,
+}
+
+impl TranscriptSentence {
+    pub fn duration(&self) -> i64 {
+        (self.end - self.start).max(0)
+    }
+
+    pub fn is_question(&self) -> bool {
+        self.text.trim_end().ends_with('?')
+    }
+}
+
+/// Name and affiliation of a speaker, taken from the call's parties
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SpeakerInfo {
+    pub name: Option<String>,
+    /// `Internal`, `External` or `Unknown`
+    pub affiliation: String,
+}
+
+/// Flatten Gong transcripts into time-ordered sentences per call
+pub(crate) fn flatten_transcripts(
+    data: &models::CallTranscripts,
+) -> Vec<(String, Vec<TranscriptSentence>)> {
+    data.call_transcripts
+        .iter()
+        .flatten()
+        .map(|transcript| {
+            let call_id = transcript.call_id.clone().unwrap_or_default();
+            let mut sentences: Vec<TranscriptSentence> = transcript
+                .transcript
+                .iter()
+                .flatten()
+                .flat_map(|monologue| {
+                    let speaker_id = monologue.speaker_id.clone();
+                    monologue
+                        .sentences
+                        .iter()
+                        .flatten()
+                        .map(move |s| TranscriptSentence {
+                            speaker_id: speaker_id.clone(),
+                            start: s.start.unwrap_or(0),
+                            end: s.end.unwrap_or(0),
+                            text: s.text.clone().unwrap_or_default(),
+                        })
+                })
+                .collect();
+            sentences.sort_by_key(|s| s.start);
+            (call_id, sentences)
+        })
+        .collect()
+}
+
+/// Whether every call in a response has its transcript
+///
+/// Calls Gong hasn't processed yet come back with an empty transcript.
+pub(crate) fn has_all_transcripts(data: &models::CallTranscripts) -> bool {
+    data.call_transcripts.as_ref().is_some_and(|transcripts| {
+        !transcripts.is_empty()
+            && transcripts.iter().all(|transcript| {
+                transcript
+                    .transcript
+                    .as_ref()
+                    .is_some_and(|monologues| !monologues.is_empty())
+            })
+    })
+}
+
+/// Map speaker IDs to names and affiliations using the call's parties
+pub(crate) fn speaker_directory(
+    parties: Option<&Vec<models::Party>>,
+) -> HashMap<String, SpeakerInfo> {
+    parties
+        .map(|parties| {
+            parties
+                .iter()
+                .filter_map(|party| {
+                    party.speaker_id.as_ref().map(|speaker_id| {
+                        let affiliation = party
+                            .affiliation
+                            .as_ref()
+                            .map(|a| format!("{:?}", a))
+                            .filter(|a| a == "Internal" || a == "External")
+                            .unwrap_or_else(|| "Unknown".to_string());
+                        (
+                            speaker_id.clone(),
+                            SpeakerInfo {
+                                name: party.name.clone(),
+                                affiliation,
+                            },
+                        )
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Rough token count for a piece of text, at about four characters per token
+pub(crate) fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Format a millisecond offset as `mm:ss`, or `h:mm:ss` past the first hour
+pub(crate) fn format_timestamp(ms: i64) -> String {
+    let seconds = ms.max(0) / 1000;
+    let (hours, minutes, seconds) = (seconds / 3600, (seconds % 3600) / 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+fn speaker_label(directory: &HashMap<String, SpeakerInfo>, speaker_id: Option<&String>) -> String {
+    match speaker_id {
+        Some(id) => match directory.get(id) {
+            Some(SpeakerInfo {
+                name: Some(name),
+                affiliation,
+            }) => format!("{} ({})", name, affiliation),
+            Some(SpeakerInfo { affiliation, .. }) => format!("Speaker {} ({})", id, affiliation),
+            None => format!("Speaker {}", id),
+        },
+        None => "Unknown speaker".to_string(),
+    }
+}
+
+/// Render a transcript as compact text with one line per speaker turn:
+/// `[mm:ss] Name (Affiliation): sentence sentence ...`
+pub(crate) fn compact_transcript(
+    sentences: &[TranscriptSentence],
+    directory: &HashMap<String, SpeakerInfo>,
+) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_speaker: Option<Option<&String>> = None;
+
+    for sentence in sentences {
+        let text = sentence.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        let speaker = sentence.speaker_id.as_ref();
+        match lines.last_mut() {
+            Some(line) if current_speaker == Some(speaker) => {
+                line.push(' ');
+                line.push_str(text);
+            }
+            _ => {
+                lines.push(format!(
+                    "[{}] {}: {}",
+                    format_timestamp(sentence.start),
+                    speaker_label(directory, speaker),
+                    text
+                ));
+                current_speaker = Some(speaker);
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn affiliation_of<'a>(directory: &'a HashMap<String, SpeakerInfo>, speaker_id: &str) -> &'a str {
+    directory
+        .get(speaker_id)
+        .map(|info| info.affiliation.as_str())
+        .unwrap_or("Unknown")
+}
+
+/// Talk time, questions and monologue length for one speaker
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SpeakerStats {
+    pub talk_time: i64,
+    pub sentences: usize,
+    pub questions: usize,
+    pub longest_monologue: i64,
+}
+
+/// An uninterrupted run of sentences by the same speaker
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Monologue {
+    pub speaker_id: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Monologue {
+    pub fn duration(&self) -> i64 {
+        (self.end - self.start).max(0)
+    }
+}
+
+/// Conversation metrics derived purely from transcript timing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TranscriptMetrics {
+    pub speakers: BTreeMap<String, SpeakerStats>,
+    pub longest_monologue: Option<Monologue>,
+    pub speaker_switches: usize,
+    /// Time from the first sentence start to the last sentence end, in milliseconds
+    pub span: i64,
+}
+
+impl TranscriptMetrics {
+    pub fn compute(sentences: &[TranscriptSentence]) -> Self {
+        let mut metrics = Self::default();
+        let mut current: Option<Monologue> = None;
+
+        for sentence in sentences {
+            let speaker_id = sentence
+                .speaker_id
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string());
+
+            let stats = metrics.speakers.entry(speaker_id.clone()).or_default();
+            stats.talk_time += sentence.duration();
+            stats.sentences += 1;
+            if sentence.is_question() {
+                stats.questions += 1;
+            }
+
+            match current.as_mut() {
+                Some(run) if run.speaker_id == speaker_id => {
+                    run.end = run.end.max(sentence.end);
+                }
+                _ => {
+                    if let Some(run) = current.take() {
+                        metrics.finish_monologue(run);
+                        metrics.speaker_switches += 1;
+                    }
+                    current = Some(Monologue {
+                        speaker_id,
+                        start: sentence.start,
+                        end: sentence.end,
+                    });
+                }
+            }
+        }
+
+        if let Some(run) = current {
+            metrics.finish_monologue(run);
+        }
+
+        if let (Some(first), Some(last)) =
+            (sentences.first(), sentences.iter().map(|s| s.end).max())
+        {
+            metrics.span = (last - first.start).max(0);
+        }
+
+        metrics
+    }
+
+    fn finish_monologue(&mut self, run: Monologue) {
+        if let Some(stats) = self.speakers.get_mut(&run.speaker_id) {
+            stats.longest_monologue = stats.longest_monologue.max(run.duration());
+        }
+        let is_longer = self
+            .longest_monologue
+            .as_ref()
+            .is_none_or(|longest| run.duration() > longest.duration());
+        if is_longer {
+            self.longest_monologue = Some(run);
+        }
+    }
+
+    pub fn total_talk_time(&self) -> i64 {
+        self.speakers.values().map(|s| s.talk_time).sum()
+    }
+
+    pub fn switches_per_minute(&self) -> f64 {
+        if self.span <= 0 {
+            0.0
+        } else {
+            self.speaker_switches as f64 / (self.span as f64 / 60_000.0)
+        }
+    }
+
+    /// Render the metrics, grouping speakers by affiliation
+    pub fn to_json(&self, directory: &HashMap<String, SpeakerInfo>) -> Value {
+        let total_talk_time = self.total_talk_time();
+        let share = |talk_time: i64| {
+            if total_talk_time == 0 {
+                0.0
+            } else {
+                talk_time as f64 / total_talk_time as f64
+            }
+        };
+
+        let mut side_talk: BTreeMap<&str, i64> = BTreeMap::new();
+        let mut side_questions: BTreeMap<&str, usize> = BTreeMap::new();
+        for side in ["Internal", "External", "Unknown"] {
+            side_talk.insert(side, 0);
+            side_questions.insert(side, 0);
+        }
+
+        let speakers: Vec<Value> = self
+            .speakers
+            .iter()
+            .map(|(speaker_id, stats)| {
+                let affiliation = affiliation_of(directory, speaker_id);
+                *side_talk.entry(affiliation).or_default() += stats.talk_time;
+                *side_questions.entry(affiliation).or_default() += stats.questions;
+                json!({
+                    "speakerId": speaker_id,
+                    "name": directory.get(speaker_id).and_then(|info| info.name.as_ref()),
+                    "affiliation": affiliation,
+                    "talkTimeMs": stats.talk_time,
+                    "talkShare": share(stats.talk_time),
+                    "sentenceCount": stats.sentences,
+                    "questions": stats.questions,
+                    "longestMonologueMs": stats.longest_monologue,
+                })
+            })
+            .collect();
+
+        json!({
+            "durationMs": self.span,
+            "totalTalkTimeMs": total_talk_time,
+            "speakers": speakers,
+            "talkRatio": {
+                "internal": share(side_talk["Internal"]),
+                "external": share(side_talk["External"]),
+                "unknown": share(side_talk["Unknown"]),
+            },
+            "questions": {
+                "internal": side_questions["Internal"],
+                "external": side_questions["External"],
+                "unknown": side_questions["Unknown"],
+            },
+            "longestMonologue": self.longest_monologue.as_ref().map(|m| json!({
+                "speakerId": m.speaker_id,
+                "affiliation": affiliation_of(directory, &m.speaker_id),
+                "startMs": m.start,
+                "durationMs": m.duration(),
+            })),
+            "speakerSwitches": self.speaker_switches,
+            "speakerSwitchesPerMinute": self.switches_per_minute(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentence(speaker: &str, start: i64, end: i64, text: &str) -> TranscriptSentence {
+        TranscriptSentence {
+            speaker_id: Some(speaker.to_string()),
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+
+    fn directory() -> HashMap<String, SpeakerInfo> {
+        HashMap::from([
+            (
+                "rep".to_string(),
+                SpeakerInfo {
+                    name: Some("Rep".to_string()),
+                    affiliation: "Internal".to_string(),
+                },
+            ),
+            (
+                "buyer".to_string(),
+                SpeakerInfo {
+                    name: Some("Buyer".to_string()),
+                    affiliation: "External".to_string(),
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_question_detection() {
+        assert!(sentence("rep", 0, 1, "How is onboarding going? ").is_question());
+        assert!(!sentence("rep", 0, 1, "Onboarding is going well.").is_question());
+    }
+
+    #[test]
+    fn test_metrics_talk_time_and_monologues() {
+        let sentences = vec![
+            sentence("rep", 0, 10_000, "Hi, thanks for joining."),
+            sentence("rep", 10_000, 30_000, "What are your goals?"),
+            sentence("buyer", 30_000, 40_000, "We want faster onboarding."),
+            sentence("rep", 40_000, 60_000, "Got it."),
+        ];
+
+        let metrics = TranscriptMetrics::compute(&sentences);
+        assert_eq!(metrics.speakers["rep"].talk_time, 50_000);
+        assert_eq!(metrics.speakers["rep"].questions, 1);
+        assert_eq!(metrics.speakers["rep"].longest_monologue, 30_000);
+        assert_eq!(metrics.speakers["buyer"].talk_time, 10_000);
+        assert_eq!(metrics.speaker_switches, 2);
+        assert_eq!(metrics.span, 60_000);
+        assert_eq!(metrics.switches_per_minute(), 2.0);
+
+        let longest = metrics.longest_monologue.as_ref().unwrap();
+        assert_eq!(longest.speaker_id, "rep");
+        assert_eq!(longest.duration(), 30_000);
+    }
+
+    #[test]
+    fn test_metrics_json_talk_ratio() {
+        let sentences = vec![
+            sentence("rep", 0, 30_000, "Tell me about your team?"),
+            sentence("buyer", 30_000, 40_000, "Sure."),
+            sentence(
+                "buyer",
+                40_000,
+                50_000,
+                "Does it integrate with Salesforce?",
+            ),
+        ];
+
+        let json = TranscriptMetrics::compute(&sentences).to_json(&directory());
+        assert_eq!(json["talkRatio"]["internal"], 0.6);
+        assert_eq!(json["talkRatio"]["external"], 0.4);
+        assert_eq!(json["questions"]["internal"], 1);
+        assert_eq!(json["questions"]["external"], 1);
+        assert_eq!(json["longestMonologue"]["affiliation"], "Internal");
+    }
+
+    #[test]
+    fn test_compact_transcript_merges_turns() {
+        let sentences = vec![
+            sentence("rep", 0, 2_000, "Hi there."),
+            sentence("rep", 2_000, 4_000, "Thanks for joining."),
+            sentence("buyer", 65_000, 70_000, "Happy to be here."),
+            sentence("other", 3_725_000, 3_726_000, "Sorry I'm late."),
+        ];
+
+        let text = compact_transcript(&sentences, &directory());
+        assert_eq!(
+            text,
+            "[00:00] Rep (Internal): Hi there. Thanks for joining.\n\
+             [01:05] Buyer (External): Happy to be here.\n\
+             [1:02:05] Speaker other: Sorry I'm late."
+        );
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+    }
+
+    #[test]
+    fn test_metrics_empty_transcript() {
+        let metrics = TranscriptMetrics::compute(&[]);
+        assert_eq!(metrics.span, 0);
+        assert_eq!(metrics.switches_per_minute(), 0.0);
+        assert!(metrics.longest_monologue.is_none());
+    }
+
+    #[test]
+    fn test_has_all_transcripts() {
+        let response = |transcripts: serde_json::Value| -> models::CallTranscripts {
+            serde_json::from_value(json!({ "callTranscripts": transcripts })).unwrap()
+        };
+        let processed = json!({
+            "callId": "1",
+            "transcript": [{"speakerId": "rep", "sentences": [{"start": 0, "end": 1000, "text": "Hi"}]}]
+        });
+        assert!(has_all_transcripts(&response(json!([processed]))));
+        assert!(!has_all_transcripts(&response(json!([]))));
+        assert!(!has_all_transcripts(&response(
+            json!([processed, {"callId": "2", "transcript": []}])
+        )));
+    }
+}
@@ -0,0 +1,266 @@
+//! Change notifications for new calls and transcripts.
+//!
+//! Gong can't push changes to this server, so a background task polls
+//! `list_calls_extensive` for the calls of the last day. A call ID it hasn't
+//! seen before makes every session receive `notifications/resources/list_changed`
+//! (new calls appear in `resources/list`), and sessions subscribed to
+//! `gong://calls/{id}/transcript` or `gong://calls/{id}` receive
+//! `notifications/resources/updated` once Gong has processed the transcript.
+//!
+//! The poller starts with the first initialized session and skips polls
+//! while no session is connected, so an idle server uses no API quota.
+
+use crate::logging;
+use rmcp::model::{LoggingLevel, ResourceUpdatedNotificationParam};
+use rmcp::{Peer, RoleServer};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// How far back the poller looks for new calls
+///
+/// Gong lists a call once it has processed the recording, which can be hours
+/// after the call started.
+pub(crate) const WATCH_WINDOW_HOURS: i64 = 24;
+
+/// How often to poll for changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WatchConfig {
+    /// `None` disables polling and subscriptions
+    pub interval: Option<Duration>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            interval: Some(Duration::from_secs(300)),
+        }
+    }
+}
+
+impl WatchConfig {
+    /// Read the interval from `GONG_WATCH_INTERVAL_SECS`
+    ///
+    /// Setting it to 0 disables polling.
+    pub fn from_env() -> Self {
+        let Ok(value) = std::env::var("GONG_WATCH_INTERVAL_SECS") else {
+            return Self::default();
+        };
+        match value.parse::<u64>() {
+            Ok(0) => Self { interval: None },
+            Ok(secs) => Self {
+                interval: Some(Duration::from_secs(secs)),
+            },
+            Err(_) => {
+                tracing::warn!(
+                    "Ignoring invalid value for GONG_WATCH_INTERVAL_SECS: {}",
+                    value
+                );
+                Self::default()
+            }
+        }
+    }
+}
+
+/// One client session, the resources it subscribed to and its log level
+pub(crate) struct Session {
+    peer: OnceLock<Peer<RoleServer>>,
+    subscriptions: Mutex<HashSet<String>>,
+    log_level: Mutex<LoggingLevel>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            peer: OnceLock::new(),
+            subscriptions: Mutex::new(HashSet::new()),
+            log_level: Mutex::new(logging::DEFAULT_LEVEL),
+        }
+    }
+}
+
+impl Session {
+    pub fn log_level(&self) -> LoggingLevel {
+        *self.log_level.lock().unwrap()
+    }
+
+    pub fn set_log_level(&self, level: LoggingLevel) {
+        *self.log_level.lock().unwrap() = level;
+    }
+
+    pub fn subscribe(&self, uri: String) {
+        self.subscriptions.lock().unwrap().insert(uri);
+    }
+
+    pub fn unsubscribe(&self, uri: &str) {
+        self.subscriptions.lock().unwrap().remove(uri);
+    }
+
+    fn is_subscribed(&self, uri: &str) -> bool {
+        self.subscriptions.lock().unwrap().contains(uri)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.peer
+            .get()
+            .is_none_or(|peer| peer.is_transport_closed())
+    }
+}
+
+/// The sessions to notify, shared by all sessions
+#[derive(Default)]
+pub(crate) struct Watcher {
+    sessions: Mutex<Vec<Arc<Session>>>,
+    polling: AtomicBool,
+}
+
+impl Watcher {
+    /// Register an initialized session
+    ///
+    /// Returns true for the first session, which starts the poller.
+    pub fn register(&self, session: Arc<Session>, peer: Peer<RoleServer>) -> bool {
+        if session.peer.set(peer).is_ok() {
+            self.sessions.lock().unwrap().push(session);
+        }
+        !self.polling.swap(true, Ordering::SeqCst)
+    }
+
+    /// The connected sessions, forgetting closed ones
+    fn sessions(&self) -> Vec<Arc<Session>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|session| !session.is_closed());
+        sessions.clone()
+    }
+
+    pub fn has_sessions(&self) -> bool {
+        !self.sessions().is_empty()
+    }
+
+    /// The URIs any session is subscribed to
+    pub fn subscriptions(&self) -> HashSet<String> {
+        self.sessions()
+            .iter()
+            .flat_map(|session| session.subscriptions.lock().unwrap().clone())
+            .collect()
+    }
+
+    pub async fn notify_list_changed(&self) {
+        for session in self.sessions() {
+            if let Some(peer) = session.peer.get()
+                && let Err(e) = peer.notify_resource_list_changed().await
+            {
+                tracing::debug!("Could not notify session of new resources: {:?}", e);
+            }
+        }
+    }
+
+    pub async fn notify_updated(&self, uri: &str) {
+        for session in self.sessions() {
+            if !session.is_subscribed(uri) {
+                continue;
+            }
+            if let Some(peer) = session.peer.get() {
+                let param = ResourceUpdatedNotificationParam {
+                    uri: uri.to_string(),
+                };
+                if let Err(e) = peer.notify_resource_updated(param).await {
+                    tracing::debug!("Could not notify session of update to {}: {:?}", uri, e);
+                }
+            }
+        }
+    }
+}
+
+/// What the poller has seen so far
+#[derive(Debug, Default)]
+pub(crate) struct PollState {
+    /// Call IDs of the previous poll, `None` before the first one
+    calls: Option<HashSet<String>>,
+    /// Whether the transcript of a checked call was available
+    transcripts: HashMap<String, bool>,
+}
+
+impl PollState {
+    /// Record the call IDs of a poll and return the ones not seen before
+    ///
+    /// The first poll only establishes what exists, so it returns nothing.
+    pub fn new_calls(&mut self, call_ids: Vec<String>) -> Vec<String> {
+        let new = match &self.calls {
+            Some(known) => call_ids
+                .iter()
+                .filter(|id| !known.contains(*id))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        // Calls outside the window can't come back, so only the latest poll is kept
+        self.calls = Some(call_ids.into_iter().collect());
+        new
+    }
+
+    /// Calls with a subscribed resource whose transcript isn't known to be available
+    pub fn awaited_transcripts(&self, subscriptions: &HashSet<String>) -> Vec<String> {
+        let mut call_ids: Vec<String> = subscriptions
+            .iter()
+            .filter_map(|uri| uri.strip_prefix("gong://calls/"))
+            .map(|rest| rest.strip_suffix("/transcript").unwrap_or(rest))
+            .filter(|id| !id.is_empty() && !id.contains('/'))
+            .filter(|id| self.transcripts.get(*id) != Some(&true))
+            .map(String::from)
+            .collect();
+        call_ids.sort();
+        call_ids.dedup();
+        call_ids
+    }
+
+    /// Record whether the transcript of `call_id` is available
+    ///
+    /// Returns true if it became available since the last check. A
+    /// transcript available at the first check was there all along.
+    pub fn transcript_checked(&mut self, call_id: &str, available: bool) -> bool {
+        let previous = self.transcripts.insert(call_id.to_string(), available);
+        available && previous == Some(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_new_calls_after_first_poll() {
+        let mut state = PollState::default();
+        assert!(state.new_calls(ids(&["1", "2"])).is_empty());
+        assert_eq!(state.new_calls(ids(&["1", "2", "3"])), ids(&["3"]));
+        assert!(state.new_calls(ids(&["2", "3"])).is_empty());
+    }
+
+    #[test]
+    fn test_transcripts_awaited_until_available() {
+        let mut state = PollState::default();
+        let subscriptions: HashSet<String> = [
+            "gong://calls/1/transcript",
+            "gong://calls/1",
+            "gong://calls/2",
+            "gong://calls/3/participants",
+            "gong://users",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        assert_eq!(state.awaited_transcripts(&subscriptions), ids(&["1", "2"]));
+
+        // Already available when first checked
+        assert!(!state.transcript_checked("2", true));
+        // Missing, then processed
+        assert!(!state.transcript_checked("1", false));
+        assert_eq!(state.awaited_transcripts(&subscriptions), ids(&["1"]));
+        assert!(state.transcript_checked("1", true));
+        assert!(state.awaited_transcripts(&subscriptions).is_empty());
+    }
+}
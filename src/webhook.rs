@@ -0,0 +1,335 @@
+//! Receiver for Gong webhooks in HTTP mode.
+//!
+//! A Gong automation rule can post a call to `/webhooks/gong` once it matches
+//! the rule's criteria. Gong signs each request with a JWT in the
+//! `Authorization` header: RS256 with the account's webhook key pair, with
+//! claims naming the webhook URL (`webhook_url`) and the SHA-256 of the body
+//! (`body_sha256`). Requests are only trusted once all three check out, since
+//! anyone who can reach the endpoint can post to it.
+//!
+//! A verified call drops the cached call data and transcript, so the next read
+//! fetches Gong's latest, and subscribed sessions are notified as if the
+//! poller had seen the change.
+
+use crate::GongServer;
+use anyhow::{Context, Result};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, header};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+pub const WEBHOOK_PATH: &str = "/webhooks/gong";
+pub const PUBLIC_KEY_ENV: &str = "GONG_WEBHOOK_PUBLIC_KEY";
+pub const PUBLIC_KEY_FILE_ENV: &str = "GONG_WEBHOOK_PUBLIC_KEY_FILE";
+pub const URL_ENV: &str = "GONG_WEBHOOK_URL";
+
+/// How many received call IDs `gong://status` shows
+const RECENT_CALLS: usize = 20;
+
+/// Why a webhook request was rejected
+#[derive(Debug, PartialEq, Eq)]
+pub enum WebhookError {
+    MissingToken,
+    InvalidToken(String),
+    UrlMismatch,
+    BodyMismatch,
+}
+
+impl std::fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingToken => write!(f, "missing Authorization header"),
+            Self::InvalidToken(reason) => write!(f, "invalid token: {}", reason),
+            Self::UrlMismatch => write!(f, "token was issued for another webhook URL"),
+            Self::BodyMismatch => write!(f, "body does not match the signed hash"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    webhook_url: Option<String>,
+    body_sha256: String,
+}
+
+/// Checks the JWT Gong signs each webhook request with
+pub struct WebhookVerifier {
+    key: DecodingKey,
+    url: Option<String>,
+}
+
+impl WebhookVerifier {
+    /// A verifier for Gong's public key, as PEM or as the bare base64 Gong shows
+    ///
+    /// With a `url`, tokens issued for other webhook URLs are rejected.
+    pub fn new(public_key: &str, url: Option<String>) -> Result<Self> {
+        let public_key = public_key.trim();
+        let pem = if public_key.starts_with("-----BEGIN") {
+            public_key.to_string()
+        } else {
+            format!(
+                "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----",
+                public_key
+            )
+        };
+        let key =
+            DecodingKey::from_rsa_pem(pem.as_bytes()).context("invalid webhook public key")?;
+        Ok(Self { key, url })
+    }
+
+    /// Read the key from `GONG_WEBHOOK_PUBLIC_KEY` or `GONG_WEBHOOK_PUBLIC_KEY_FILE`
+    ///
+    /// Returns `None`, disabling the endpoint, when neither is set or the key
+    /// can't be read.
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var(URL_ENV).ok().filter(|url| !url.is_empty());
+        let public_key = match (
+            std::env::var(PUBLIC_KEY_ENV),
+            std::env::var_os(PUBLIC_KEY_FILE_ENV),
+        ) {
+            (Ok(key), _) => Ok(key),
+            (Err(_), Some(path)) => read_key_file(Path::new(&path)),
+            (Err(_), None) => return None,
+        };
+
+        match public_key.and_then(|key| Self::new(&key, url)) {
+            Ok(verifier) => Some(verifier),
+            Err(e) => {
+                tracing::warn!("Webhook receiver disabled: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Verify the `Authorization` header of a request against its body
+    pub fn verify(&self, authorization: Option<&str>, body: &[u8]) -> Result<(), WebhookError> {
+        let token = authorization
+            .map(|value| value.trim())
+            .map(|value| value.strip_prefix("Bearer ").unwrap_or(value))
+            .filter(|token| !token.is_empty())
+            .ok_or(WebhookError::MissingToken)?;
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.validate_aud = false;
+        let claims = jsonwebtoken::decode::<Claims>(token, &self.key, &validation)
+            .map_err(|e| WebhookError::InvalidToken(e.to_string()))?
+            .claims;
+
+        if let Some(url) = &self.url
+            && claims.webhook_url.as_deref() != Some(url.as_str())
+        {
+            return Err(WebhookError::UrlMismatch);
+        }
+        if !claims.body_sha256.eq_ignore_ascii_case(&sha256_hex(body)) {
+            return Err(WebhookError::BodyMismatch);
+        }
+        Ok(())
+    }
+}
+
+fn read_key_file(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+fn sha256_hex(body: &[u8]) -> String {
+    Sha256::digest(body)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The call a webhook is about
+#[derive(Debug, PartialEq, Eq)]
+pub struct WebhookCall {
+    pub call_id: String,
+    /// Sent by "Test" in Gong's automation rule editor
+    pub is_test: bool,
+}
+
+impl WebhookCall {
+    /// Parse the body Gong posts, the call in the `list_calls_extensive` format
+    pub fn parse(body: &[u8]) -> Option<Self> {
+        let payload: Value = serde_json::from_slice(body).ok()?;
+        let call_id = payload["callData"]["metaData"]["id"].as_str()?;
+        Some(Self {
+            call_id: call_id.to_string(),
+            is_test: payload["isTest"].as_bool().unwrap_or(false),
+        })
+    }
+}
+
+/// The calls received so far, for `gong://status`
+#[derive(Default)]
+pub(crate) struct WebhookLog {
+    received: Mutex<(u64, VecDeque<String>)>,
+}
+
+impl WebhookLog {
+    pub fn record(&self, call_id: &str) {
+        let mut received = self.received.lock().unwrap();
+        received.0 += 1;
+        received.1.retain(|id| id != call_id);
+        received.1.push_front(call_id.to_string());
+        received.1.truncate(RECENT_CALLS);
+    }
+
+    pub fn status(&self) -> Value {
+        let received = self.received.lock().unwrap();
+        json!({
+            "path": WEBHOOK_PATH,
+            "received": received.0,
+            "recentCallIds": received.1,
+        })
+    }
+}
+
+/// The `/webhooks/gong` route, to merge into the HTTP router
+///
+/// `server` must have been built `with_webhooks` so it records calls.
+pub fn router(server: GongServer, verifier: WebhookVerifier) -> axum::Router {
+    axum::Router::new()
+        .route(WEBHOOK_PATH, axum::routing::post(receive))
+        .with_state((server, Arc::new(verifier)))
+}
+
+async fn receive(
+    State((server, verifier)): State<(GongServer, Arc<WebhookVerifier>)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    if let Err(e) = verifier.verify(authorization, &body) {
+        tracing::warn!("Rejected webhook: {}", e);
+        return StatusCode::UNAUTHORIZED;
+    }
+
+    let Some(call) = WebhookCall::parse(&body) else {
+        tracing::warn!("Rejected webhook without call ID");
+        return StatusCode::BAD_REQUEST;
+    };
+    if call.is_test {
+        tracing::info!("Received test webhook for call {}", call.call_id);
+        return StatusCode::OK;
+    }
+
+    tracing::info!("Received webhook for call {}", call.call_id);
+    server.call_changed(&call.call_id).await;
+    StatusCode::OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{EncodingKey, Header};
+
+    const PUBLIC_KEY: &str = include_str!("../fixtures/webhook/public.pem");
+    const PRIVATE_KEY: &str = include_str!("../fixtures/webhook/private.pem");
+    const BODY: &str = include_str!("../fixtures/webhook/call-processed.json");
+    const TOKEN: &str = include_str!("../fixtures/webhook/call-processed.jwt");
+    const URL: &str = "http://localhost:8080/webhooks/gong";
+
+    fn verifier(url: Option<&str>) -> WebhookVerifier {
+        WebhookVerifier::new(PUBLIC_KEY, url.map(String::from)).unwrap()
+    }
+
+    fn sign(header: Header, key: &EncodingKey, claims: Value) -> String {
+        jsonwebtoken::encode(&header, &claims, key).unwrap()
+    }
+
+    #[test]
+    fn test_signed_fixture_verifies() {
+        let token = TOKEN.trim();
+        assert_eq!(
+            verifier(Some(URL)).verify(Some(token), BODY.as_bytes()),
+            Ok(())
+        );
+        assert_eq!(
+            verifier(None).verify(Some(&format!("Bearer {}", token)), BODY.as_bytes()),
+            Ok(())
+        );
+
+        // Gong shows the key without PEM armor
+        let bare: String = PUBLIC_KEY
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect();
+        let verifier = WebhookVerifier::new(&bare, None).unwrap();
+        assert_eq!(verifier.verify(Some(token), BODY.as_bytes()), Ok(()));
+    }
+
+    #[test]
+    fn test_rejects_tampered_requests() {
+        let token = TOKEN.trim();
+        let verifier = verifier(Some(URL));
+        let tampered = BODY.replace("7782342274025937895", "7782342274025937896");
+        assert_eq!(
+            verifier.verify(Some(token), tampered.as_bytes()),
+            Err(WebhookError::BodyMismatch)
+        );
+        assert_eq!(
+            verifier.verify(None, BODY.as_bytes()),
+            Err(WebhookError::MissingToken)
+        );
+
+        let other_url =
+            WebhookVerifier::new(PUBLIC_KEY, Some("https://example.com/hook".into())).unwrap();
+        assert_eq!(
+            other_url.verify(Some(token), BODY.as_bytes()),
+            Err(WebhookError::UrlMismatch)
+        );
+
+        let claims = json!({"webhook_url": URL, "body_sha256": sha256_hex(BODY.as_bytes()), "exp": 4102444800u64});
+        let hmac = sign(
+            Header::new(Algorithm::HS256),
+            &EncodingKey::from_secret(PUBLIC_KEY.as_bytes()),
+            claims,
+        );
+        assert!(matches!(
+            verifier.verify(Some(&hmac), BODY.as_bytes()),
+            Err(WebhookError::InvalidToken(_))
+        ));
+
+        let private_key = EncodingKey::from_rsa_pem(PRIVATE_KEY.as_bytes()).unwrap();
+        let expired = sign(
+            Header::new(Algorithm::RS256),
+            &private_key,
+            json!({"webhook_url": URL, "body_sha256": sha256_hex(BODY.as_bytes()), "exp": 1_000_000_000u64}),
+        );
+        assert!(matches!(
+            verifier.verify(Some(&expired), BODY.as_bytes()),
+            Err(WebhookError::InvalidToken(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_payload() {
+        assert_eq!(
+            WebhookCall::parse(BODY.as_bytes()),
+            Some(WebhookCall {
+                call_id: "7782342274025937895".to_string(),
+                is_test: false,
+            })
+        );
+        assert_eq!(WebhookCall::parse(br#"{"isTest": true}"#), None);
+        assert_eq!(WebhookCall::parse(b"not json"), None);
+    }
+
+    #[test]
+    fn test_log_keeps_recent_calls() {
+        let log = WebhookLog::default();
+        log.record("1");
+        log.record("2");
+        log.record("1");
+        let status = log.status();
+        assert_eq!(status["received"], 3);
+        assert_eq!(status["recentCallIds"], json!(["1", "2"]));
+    }
+}